@@ -0,0 +1,101 @@
+# Tests of the Python bindings, run with `maturin develop && pytest`.
+
+import signal
+
+import pytest
+
+from sat_solver import Formula
+
+
+def pigeonhole(holes):
+    """DIMACS for putting holes + 1 pigeons into holes, which is unsatisfiable and hard for DPLL."""
+    var = lambda pigeon, hole: pigeon * holes + hole + 1
+    clauses = [[var(p, h) for h in range(holes)] for p in range(holes + 1)]
+    for h in range(holes):
+        for p in range(holes + 1):
+            for q in range(p + 1, holes + 1):
+                clauses.append([-var(p, h), -var(q, h)])
+
+    lines = [f"p cnf {(holes + 1) * holes} {len(clauses)}"]
+    lines += [" ".join(map(str, clause)) + " 0" for clause in clauses]
+    return "\n".join(lines) + "\n"
+
+
+def test_sat():
+    formula = Formula.parse("(a | -b) & (b | c) & -c")
+
+    assert formula.solve() == {"a": True, "b": True, "c": False}
+
+
+def test_unsat():
+    assert Formula.parse("a & -a").solve() is None
+    assert Formula.from_dimacs(pigeonhole(3)).solve() is None
+
+
+def test_assumptions():
+    formula = Formula.parse("(a | -b) & (b | c) & -c")
+
+    assert formula.solve(assumptions=[1]) == {"a": True, "b": True, "c": False}
+    assert formula.solve(assumptions=[-1]) is None
+
+    with pytest.raises(ValueError):
+        formula.solve(assumptions=[4])
+
+
+def test_timeout():
+    formula = Formula.from_dimacs(pigeonhole(9))
+
+    with pytest.raises(TimeoutError):
+        formula.solve(timeout=0.001)
+
+
+def test_keyboard_interrupt():
+    def interrupt(signum, frame):
+        raise KeyboardInterrupt
+
+    formula = Formula.from_dimacs(pigeonhole(10))
+    previous = signal.signal(signal.SIGALRM, interrupt)
+    signal.setitimer(signal.ITIMER_REAL, 0.2)
+    try:
+        with pytest.raises(KeyboardInterrupt):
+            formula.solve()
+    finally:
+        signal.setitimer(signal.ITIMER_REAL, 0)
+        signal.signal(signal.SIGALRM, previous)
+
+
+def test_dimacs():
+    formula = Formula.from_dimacs("c two clauses\np cnf 2 2\n1 2 0\n-1 0\n")
+
+    assert formula.solve() == {"1": False, "2": True}
+    assert formula.count_models() == 1
+    assert formula.cnf_dimacs().startswith("p cnf 2 2\n")
+
+    with pytest.raises(ValueError):
+        Formula.from_dimacs("1 2 0\n")
+
+
+def test_add_clause():
+    formula = Formula.from_dimacs("p cnf 2 1\n1 2 0\n")
+    assert formula.count_models() == 3
+
+    formula.add_clause([-1])
+    formula.add_clause([-2, 4])
+    model = formula.solve()
+    assert (model["1"], model["2"], model["4"]) == (False, True, True)
+    assert formula.count_models() == 2
+
+    formula.add_clause([-4])
+    assert formula.solve() is None
+
+    with pytest.raises(ValueError):
+        formula.add_clause([0])
+
+
+def test_count_models():
+    assert Formula.parse("(a | b | c) & -(a & b & c)").count_models() == 6
+
+
+def test_invalid():
+    with pytest.raises(ValueError):
+        Formula.parse("a & ")
@@ -0,0 +1,110 @@
+// Helpers shared by the subcommands of the binary.
+
+use std::{fmt::Display, fs, io::{self, Read, Write}, path::{Path, PathBuf}, process::{ExitCode, Termination}};
+
+use clap::ValueEnum;
+use sat_solver::{color::{paint, Color}, parser::{parse_dimacs_str, parse_str}, solver::instance::SATInstance};
+
+pub mod config;
+pub mod json;
+pub mod solve;
+pub mod convert;
+pub mod generate;
+pub mod project;
+pub mod progress;
+pub mod compare;
+pub mod serve;
+
+/// How a subcommand finished, reported as the exit status of the process.
+#[derive(Debug)]
+pub enum Outcome {
+    Success,
+    /// Exit code 10, as expected from SAT competition solvers
+    Satisfiable,
+    /// Exit code 20, as expected from SAT competition solvers
+    Unsatisfiable,
+    /// Exit code 30, the solver gave up
+    Unknown,
+    Error(String),
+}
+
+impl Termination for Outcome {
+    fn report(self) -> ExitCode {
+        match self {
+            Outcome::Success => ExitCode::SUCCESS,
+            Outcome::Satisfiable => ExitCode::from(10),
+            Outcome::Unsatisfiable => ExitCode::from(20),
+            Outcome::Unknown => ExitCode::from(30),
+            Outcome::Error(err) => {
+                eprintln!("{}: {}", paint("error", Color::Red).bold(), err);
+                ExitCode::FAILURE
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    Auto,
+    Expr,
+    Dimacs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Expr,
+    Dimacs,
+}
+
+pub fn read_input(path: &Path) -> Result<String, String> {
+    if path == Path::new("-") {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content).map_err(|err| format!("couldn't read stdin: {}", err))?;
+        Ok(content)
+    } else {
+        fs::read_to_string(path).map_err(|err| format!("couldn't read '{}': {}", path.display(), err))
+    }
+}
+
+/// Write `content` to `path`, or to stdout if there is no path.
+pub fn write_output(path: Option<&PathBuf>, content: &str) -> Result<(), String> {
+    match path {
+        Some(path) => fs::write(path, content).map_err(|err| format!("couldn't write '{}': {}", path.display(), err)),
+        None => io::stdout().write_all(content.as_bytes()).map_err(|err| format!("couldn't write to stdout: {}", err)),
+    }
+}
+
+/// Resolve [InputFormat::Auto] by looking at `content`.
+pub fn detect_format(content: &str, format: InputFormat) -> InputFormat {
+    match format {
+        InputFormat::Auto if is_dimacs(content) => InputFormat::Dimacs,
+        InputFormat::Auto => InputFormat::Expr,
+        format => format,
+    }
+}
+
+pub fn parse_instance(content: &str, format: InputFormat, path: &Path) -> Result<SATInstance, String> {
+    match detect_format(content, format) {
+        InputFormat::Dimacs => parse_dimacs_str(content).map_err(|err| format!("couldn't parse '{}': {}", path.display(), err)),
+        _ => parse_str(content).map_err(|err| format!("couldn't parse '{}': {}", path.display(), err.report(content))),
+    }
+}
+
+// DIMACS files start with comment lines followed by the 'p cnf' header
+fn is_dimacs(content: &str) -> bool {
+    let is_comment = |line: &str| line == "c" || line.starts_with("c ") || line.starts_with("c\t");
+
+    content.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !is_comment(line))
+        .is_some_and(|line| line.starts_with("p "))
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Format::Expr => write!(f, "expr"),
+            Format::Dimacs => write!(f, "dimacs"),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+// The compare subcommand.
+
+use std::{fs, path::PathBuf, time::Duration};
+
+use clap::Args;
+use sat_solver::solver::{external::ExternalSolver, harness::{differential, Engine, HarnessReport}, heuristics::BranchingHeuristic, instance::SolverConfig};
+
+use super::{parse_instance, read_input, InputFormat, Outcome};
+
+#[derive(Debug, Args)]
+pub struct CompareArgs {
+    /// Directory with instances in the expression syntax or DIMACS CNF
+    dir: PathBuf,
+
+    /// Also run this external solver, can be given multiple times
+    #[arg(long)]
+    external: Vec<PathBuf>,
+
+    /// Time limit per instance and engine, e.g. `10s`
+    #[arg(long, value_parser = humantime::parse_duration)]
+    timeout: Option<Duration>,
+
+    /// Print the report as JSON instead of a table
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn run(args: CompareArgs) -> Result<Outcome, String> {
+    let entries = fs::read_dir(&args.dir).map_err(|err| format!("couldn't read '{}': {}", args.dir.display(), err))?;
+    let mut paths = entries
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("couldn't read '{}': {}", args.dir.display(), err))?;
+    paths.retain(|path| path.is_file());
+    paths.sort();
+
+    let instances = paths.iter()
+        .map(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            Ok((name, parse_instance(&read_input(path)?, InputFormat::Auto, path)?))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut engines = [("dpll-random", BranchingHeuristic::Random), ("dpll-jw", BranchingHeuristic::JeroslowWang), ("dpll-vsids", BranchingHeuristic::Vsids)]
+        .into_iter()
+        .map(|(name, heuristic)| Engine::dpll(name, SolverConfig { heuristic, ..Default::default() }))
+        .collect::<Vec<_>>();
+    engines.push(Engine::cdcl("cdcl"));
+    for path in &args.external {
+        engines.push(Engine::external(path.display().to_string(), ExternalSolver::new(path)));
+    }
+
+    let report = differential(&instances, &mut engines, args.timeout);
+
+    if args.json {
+        println!("{}", serde_json::to_string(&report).map_err(|err| err.to_string())?);
+    } else {
+        print_report(&report);
+    }
+
+    match report.is_consistent() {
+        true => Ok(Outcome::Success),
+        false => Err(format!("{} disagreements and {} invalid models", report.disagreements.len(), report.invalid_models.len())),
+    }
+}
+
+// a table with a row per instance and a column per engine, followed by the problems found
+fn print_report(report: &HarnessReport) {
+    let mut rows = vec![std::iter::once("instance".to_string()).chain(report.engines.iter().cloned()).collect::<Vec<_>>()];
+    for instance in &report.instances {
+        let runs = instance.runs.iter().map(|run| format!("{} {:.3}s", run.verdict, run.time));
+        rows.push(std::iter::once(instance.name.clone()).chain(runs).collect());
+    }
+
+    let widths = (0..rows[0].len()).map(|column| rows.iter().map(|row| row[column].len()).max().unwrap_or(0)).collect::<Vec<_>>();
+    for row in &rows {
+        let cells = row.iter().zip(&widths).map(|(cell, width)| format!("{:<width$}", cell, width = width)).collect::<Vec<_>>();
+        println!("{}", cells.join("  ").trim_end());
+    }
+
+    for disagreement in &report.disagreements {
+        println!(
+            "disagreement on {}: SAT by {}, UNSAT by {}, suspects {}",
+            disagreement.instance, disagreement.sat.join(", "), disagreement.unsat.join(", "), disagreement.suspects.join(", "),
+        );
+    }
+    for invalid in &report.invalid_models {
+        let problem = if invalid.missing { "no model" } else { "a wrong model" };
+        println!("invalid model on {}: {} reported SAT with {}", invalid.instance, invalid.engine, problem);
+    }
+    for instance in &report.instances {
+        for (run, engine) in instance.runs.iter().zip(&report.engines) {
+            if let Some(error) = &run.error {
+                println!("error on {}: {} failed: {}", instance.name, engine, error);
+            }
+        }
+    }
+}
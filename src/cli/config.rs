@@ -0,0 +1,184 @@
+// Solver configuration from command line flags and TOML files.
+
+use std::{fs, path::PathBuf, time::Duration};
+
+use clap::{Args, ValueEnum};
+use sat_solver::solver::{heuristics::BranchingHeuristic, instance::SolverConfig, restart::RestartPolicy};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Args)]
+pub struct ConfigArgs {
+    /// Give up after this long, e.g. `30s` or `1m 30s`
+    #[arg(long, value_parser = humantime::parse_duration)]
+    timeout: Option<Duration>,
+
+    /// Seed for random choices
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// How to choose decision variables
+    #[arg(long, value_enum)]
+    heuristic: Option<Heuristic>,
+
+    /// Don't assign pure literals
+    #[arg(long)]
+    no_pure_literal: bool,
+
+    /// Restart schedule, `luby:<unit>`, `geometric:<first>:<factor>` or `none`
+    #[arg(long, value_parser = parse_restarts)]
+    restarts: Option<Restarts>,
+
+    /// Read the configuration from a TOML file with the same keys as the flags, flags take
+    /// precedence
+    #[arg(long)]
+    config: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Heuristic {
+    Vsids,
+    /// Jeroslow-Wang
+    Jw,
+    /// Dynamic largest individual sum
+    Dlis,
+    /// First unassigned variable
+    First,
+    Random,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Restarts(Option<RestartPolicy>);
+
+/// Keys of a configuration file, also used to report the effective configuration.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigFile {
+    /// Human-readable duration like `30s`, no timeout if missing
+    pub timeout: Option<String>,
+    pub seed: Option<u64>,
+    pub heuristic: Option<Heuristic>,
+    pub no_pure_literal: Option<bool>,
+    /// `luby:<unit>`, `geometric:<first>:<factor>` or `none`
+    pub restarts: Option<String>,
+}
+
+impl ConfigArgs {
+    /// The configuration given by the flags and the configuration file.
+    pub fn resolve(&self) -> Result<SolverConfig, String> {
+        let file = match &self.config {
+            Some(path) => {
+                let content = fs::read_to_string(path).map_err(|err| format!("couldn't read '{}': {}", path.display(), err))?;
+                toml::from_str::<ConfigFile>(&content).map_err(|err| format!("invalid configuration '{}': {}", path.display(), err.message()))?
+            },
+            None => ConfigFile::default(),
+        };
+
+        let mut config = file.resolve()?;
+        config.timeout = self.timeout.or(config.timeout);
+        config.seed = self.seed.unwrap_or(config.seed);
+        config.pure_literals &= !self.no_pure_literal;
+
+        if let Some(heuristic) = self.heuristic {
+            config.heuristic = heuristic.into();
+        }
+        if let Some(Restarts(restarts)) = self.restarts.clone() {
+            config.restarts = restarts;
+        }
+
+        Ok(config)
+    }
+}
+
+impl ConfigFile {
+    /// The configuration given by the keys of the file, with defaults for missing keys.
+    pub fn resolve(&self) -> Result<SolverConfig, String> {
+        let mut config = SolverConfig::default();
+
+        config.timeout = self.timeout.as_deref()
+            .map(|timeout| humantime::parse_duration(timeout).map_err(|err| format!("invalid timeout '{}': {}", timeout, err)))
+            .transpose()?;
+        config.seed = self.seed.unwrap_or(config.seed);
+        config.pure_literals = !self.no_pure_literal.unwrap_or(false);
+
+        if let Some(heuristic) = self.heuristic {
+            config.heuristic = heuristic.into();
+        }
+        if let Some(Restarts(restarts)) = self.restarts.as_deref().map(parse_restarts).transpose()? {
+            config.restarts = restarts;
+        }
+
+        Ok(config)
+    }
+}
+
+impl From<Heuristic> for BranchingHeuristic {
+    fn from(heuristic: Heuristic) -> Self {
+        match heuristic {
+            Heuristic::Vsids => BranchingHeuristic::Vsids,
+            Heuristic::Jw => BranchingHeuristic::JeroslowWang,
+            Heuristic::Dlis => BranchingHeuristic::MostFrequent,
+            Heuristic::First => BranchingHeuristic::FirstUnassigned,
+            Heuristic::Random => BranchingHeuristic::Random,
+        }
+    }
+}
+
+impl From<&SolverConfig> for ConfigFile {
+    fn from(config: &SolverConfig) -> Self {
+        let heuristic = match config.heuristic {
+            BranchingHeuristic::Vsids => Heuristic::Vsids,
+            BranchingHeuristic::JeroslowWang => Heuristic::Jw,
+            BranchingHeuristic::MostFrequent => Heuristic::Dlis,
+            BranchingHeuristic::FirstUnassigned => Heuristic::First,
+            BranchingHeuristic::Random => Heuristic::Random,
+        };
+
+        Self {
+            timeout: config.timeout.map(|timeout| humantime::format_duration(timeout).to_string()),
+            seed: Some(config.seed),
+            heuristic: Some(heuristic),
+            no_pure_literal: Some(!config.pure_literals),
+            restarts: Some(config.restarts.as_ref().map_or("none".to_string(), format_restarts)),
+        }
+    }
+}
+
+/// The effective configuration as `c` lines, using the keys of the configuration file.
+pub fn config_comments(config: &SolverConfig) -> String {
+    let file = ConfigFile::from(config);
+    let heuristic = file.heuristic.and_then(|heuristic| heuristic.to_possible_value()).expect("Heuristics are named");
+
+    format!(
+        "c timeout = {}\nc seed = {}\nc heuristic = {}\nc no-pure-literal = {}\nc restarts = {}\n",
+        file.timeout.as_deref().unwrap_or("none"), config.seed, heuristic.get_name(), !config.pure_literals, file.restarts.unwrap_or_default(),
+    )
+}
+
+fn format_restarts(restarts: &RestartPolicy) -> String {
+    match restarts {
+        RestartPolicy::Luby(luby) => format!("luby:{}", luby.unit),
+        RestartPolicy::Geometric(geometric) => format!("geometric:{}:{}", geometric.first, geometric.factor),
+    }
+}
+
+// `luby:<unit>`, `geometric:<first>:<factor>` or `none`
+fn parse_restarts(restarts: &str) -> Result<Restarts, String> {
+    if restarts == "none" {
+        return Ok(Restarts(None));
+    }
+
+    let luby = restarts.strip_prefix("luby:")
+        .and_then(|unit| unit.parse::<u64>().ok())
+        .filter(|unit| *unit > 0)
+        .map(RestartPolicy::luby);
+    let geometric = || restarts.strip_prefix("geometric:")
+        .and_then(|parameters| parameters.split_once(':'))
+        .and_then(|(first, factor)| Some((first.parse::<u64>().ok()?, factor.parse::<f64>().ok()?)))
+        .filter(|(first, factor)| *first > 0 && *factor > 1.0)
+        .map(|(first, factor)| RestartPolicy::geometric(first, factor));
+
+    luby.or_else(geometric)
+        .map(|policy| Restarts(Some(policy)))
+        .ok_or_else(|| format!("invalid restart schedule '{}', expected 'luby:<unit>', 'geometric:<first>:<factor>' or 'none'", restarts))
+}
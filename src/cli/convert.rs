@@ -0,0 +1,118 @@
+// The convert subcommand.
+
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use sat_solver::{expression::normal::{CnfCache, Literal, CNF}, solver::instance::SATInstance};
+
+use super::{detect_format, parse_instance, read_input, write_output, Format, InputFormat, Outcome};
+
+#[derive(Debug, Args)]
+pub struct ConvertArgs {
+    /// Input file, `-` reads from stdin
+    file: PathBuf,
+
+    /// Input format, detected from the content by default
+    #[arg(long, value_enum, default_value_t = InputFormat::Auto)]
+    from: InputFormat,
+
+    /// Output format
+    #[arg(long, value_enum)]
+    to: Format,
+
+    /// Output file, stdout by default
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// How expressions are converted to CNF
+    #[arg(long, value_enum, default_value_t = Encoding::Distribute)]
+    encoding: Encoding,
+
+    /// Write the mapping from output to input variable names to this file
+    #[arg(long)]
+    map: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Encoding {
+    /// Equisatisfiable, linear size, introduces auxiliary variables
+    Tseitin,
+    /// Equivalent, but may grow exponentially
+    Distribute,
+}
+
+const SUPPORTED_CONVERSIONS: [(Format, Format); 2] = [
+    (Format::Expr, Format::Dimacs),
+    (Format::Dimacs, Format::Expr),
+];
+
+pub fn run(args: ConvertArgs) -> Result<Outcome, String> {
+    let content = read_input(&args.file)?;
+    let from = match detect_format(&content, args.from) {
+        InputFormat::Dimacs => Format::Dimacs,
+        _ => Format::Expr,
+    };
+
+    if !SUPPORTED_CONVERSIONS.contains(&(from, args.to)) {
+        let supported = SUPPORTED_CONVERSIONS.iter().map(|(from, to)| format!("{} -> {}", from, to)).collect::<Vec<_>>();
+        return Err(format!("can't convert from {} to {}, supported conversions are: {}", from, args.to, supported.join(", ")));
+    }
+
+    let instance = parse_instance(&content, args.from, &args.file)?;
+    let mut variables = instance.var_to_str.iter().collect::<Vec<_>>();
+    variables.sort();
+
+    let (output, map) = match args.to {
+        Format::Dimacs => {
+            let cnf = encode(&instance, args.encoding)?;
+            let mut output = Vec::new();
+            cnf.to_dimacs(&mut output).map_err(|err| err.to_string())?;
+
+            let map = variables.iter().map(|(var, name)| format!("{} {}\n", Literal::new(**var, true).to_dimacs(), name)).collect::<String>();
+            (String::from_utf8(output).expect("DIMACS output is ASCII"), map)
+        },
+        Format::Expr => {
+            // DIMACS names are numbers, which aren't valid identifiers
+            let var_to_str = instance.var_to_str.iter().map(|(var, name)| (*var, format!("x{}", name))).collect();
+            let renamed = SATInstance::new(instance.expression.clone(), var_to_str);
+
+            let map = variables.iter().map(|(var, name)| format!("{} {}\n", renamed.var_to_str[var], name)).collect::<String>();
+            (format!("{}\n", renamed.display_expression()), map)
+        },
+    };
+
+    write_output(args.output.as_ref(), &output)?;
+    if let Some(path) = &args.map {
+        write_output(Some(path), &map)?;
+    }
+
+    Ok(Outcome::Success)
+}
+
+fn encode(instance: &SATInstance, encoding: Encoding) -> Result<CNF, String> {
+    match encoding {
+        Encoding::Distribute => Ok(CNF::from(instance.expression.clone())),
+        Encoding::Tseitin => {
+            let out_of_ids = || "ran out of variable ids for the Tseitin encoding".to_string();
+            let first_free = instance.var_to_str.keys().max().map_or(Some(0), |var| var.checked_add(1)).ok_or_else(out_of_ids)?;
+            CNF::from_expression_with_cache(&instance.expression, &mut CnfCache::new(first_free)).ok_or_else(out_of_ids)
+        },
+    }
+}
+
+#[cfg(test)]
+use sat_solver::expression::expression::{Expression, VariableId};
+
+#[test]
+fn test_encode_out_of_ids() {
+    // the auxiliary variable of the disjunction would need the id after the largest one
+    let var_to_str = [(0, "a".to_string()), (VariableId::MAX - 1, "b".to_string())].into();
+    let instance = SATInstance::new(Expression::or(Expression::var(0), Expression::var(VariableId::MAX - 1)), var_to_str);
+
+    assert_eq!(encode(&instance, Encoding::Tseitin).unwrap_err(), "ran out of variable ids for the Tseitin encoding");
+    assert_eq!(encode(&instance, Encoding::Distribute).unwrap().clauses.len(), 1);
+
+    // no id left after the largest variable at all
+    let instance = SATInstance::new(Expression::var(VariableId::MAX), [(VariableId::MAX, "a".to_string())].into());
+    assert_eq!(encode(&instance, Encoding::Tseitin).unwrap_err(), "ran out of variable ids for the Tseitin encoding");
+}
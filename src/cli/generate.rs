@@ -0,0 +1,100 @@
+// The generate subcommand.
+
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand};
+use sat_solver::{encodings::sudoku::{encode_sudoku, sudoku_cnf}, expression::{expression::VariableId, normal::CNF}, generator::{max_ksat_clauses, pigeonhole, pigeonhole_cnf, random_ksat, random_ksat_cnf, sudoku_puzzle}};
+
+use super::{write_output, Format, Outcome};
+
+#[derive(Debug, Args)]
+pub struct GenerateArgs {
+    #[command(subcommand)]
+    generator: Generator,
+
+    /// Output format
+    #[arg(long, global = true, value_enum, default_value_t = Format::Dimacs)]
+    format: Format,
+
+    /// Output file, stdout by default
+    #[arg(short, long, global = true)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Generator {
+    /// Uniform random k-SAT
+    RandomKsat {
+        /// Number of variables
+        #[arg(long)]
+        vars: usize,
+        /// Ratio of clauses to variables, around 4.27 is hardest for 3-SAT
+        #[arg(long)]
+        ratio: f64,
+        /// Literals per clause
+        #[arg(short, long, default_value_t = 3)]
+        k: usize,
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+    },
+    /// Pigeonhole principle, unsatisfiable
+    Php {
+        /// Number of holes, there is one pigeon more than holes
+        #[arg(long)]
+        holes: usize,
+    },
+    /// Sudoku with random blank cells
+    Sudoku {
+        /// Number of blank cells
+        #[arg(long, default_value_t = 50)]
+        blank: usize,
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+    },
+}
+
+pub fn run(args: GenerateArgs) -> Result<Outcome, String> {
+    let (parameters, cnf, instance) = match args.generator {
+        Generator::RandomKsat { vars, ratio, k, seed } => {
+            if k == 0 || k > vars || VariableId::try_from(vars).is_err() {
+                return Err(format!("invalid random k-SAT parameters, k has to be in 1..=vars and vars at most {}", VariableId::MAX));
+            }
+            if ratio.is_nan() || ratio < 0.0 {
+                return Err("the clause ratio can't be negative".to_string());
+            }
+
+            let clauses = (vars as f64 * ratio).round() as usize;
+            if clauses as u128 > max_ksat_clauses(vars, k) {
+                return Err(format!("there are less than {} distinct clauses with {} out of {} variables", clauses, k, vars));
+            }
+
+            let parameters = format!("random-ksat --vars {} --ratio {} --k {} --seed {}", vars, ratio, k, seed);
+            (parameters, random_ksat_cnf(vars, clauses, k, seed), random_ksat(vars, clauses, k, seed))
+        },
+        Generator::Php { holes } => {
+            if VariableId::try_from(holes * (holes + 1)).is_err() {
+                return Err("too many holes".to_string());
+            }
+
+            (format!("php --holes {}", holes), pigeonhole_cnf(holes), pigeonhole(holes))
+        },
+        Generator::Sudoku { blank, seed } => {
+            let grid = sudoku_puzzle(blank, seed);
+            (format!("sudoku --blank {} --seed {}", blank, seed), sudoku_cnf(&grid), encode_sudoku(&grid))
+        },
+    };
+
+    let output = match args.format {
+        Format::Dimacs => dimacs_with_header(&parameters, &cnf)?,
+        Format::Expr => format!("# generated by sat-solver generate {}\n{}\n", parameters, instance.display_expression()),
+    };
+
+    write_output(args.output.as_ref(), &output)?;
+    Ok(Outcome::Success)
+}
+
+fn dimacs_with_header(parameters: &str, cnf: &CNF) -> Result<String, String> {
+    let mut output = format!("c generated by sat-solver generate {}\n", parameters).into_bytes();
+    cnf.to_dimacs(&mut output).map_err(|err| err.to_string())?;
+    Ok(String::from_utf8(output).expect("DIMACS output is ASCII"))
+}
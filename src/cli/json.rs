@@ -0,0 +1,103 @@
+// Machine-readable output of the solve subcommand.
+//
+// Fields may be added in the future, but existing fields are never renamed or removed.
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use sat_solver::{expression::expression::Assignment, solver::instance::{SATInstance, SolverResult, SolverStats}};
+use serde::{Deserialize, Serialize};
+
+use super::config::ConfigFile;
+
+/// Variable names mapped to their values.
+pub type JsonModel = BTreeMap<String, bool>;
+
+/// Result of `solve --json`. `solve --json --all-models` instead emits an array of [JsonModel]s.
+#[derive(Debug, Serialize)]
+pub struct JsonResult {
+    pub verdict: Verdict,
+    /// `null` unless the verdict is `SAT`
+    pub model: Option<JsonModel>,
+    pub stats: JsonStats,
+    /// The configuration used, with the keys of a configuration file
+    pub config: ConfigFile,
+    /// Wall-clock time spent solving in seconds
+    pub time: f64,
+    /// How the verdict was checked with `--verify`, `null` if it wasn't
+    pub verified: Option<Verification>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Verdict {
+    Sat,
+    Unsat,
+    Unknown,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonStats {
+    pub decisions: u64,
+    pub propagations: u64,
+    pub pure_literals: u64,
+    pub conflicts: u64,
+    pub restarts: u64,
+    pub conflicts_since_restart: u64,
+    pub max_depth: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Verification {
+    /// The model satisfies the original formula
+    Model,
+    /// Solving again with another heuristic and seed also gave UNSAT
+    ReSolve,
+}
+
+impl From<&SolverResult> for Verdict {
+    fn from(result: &SolverResult) -> Self {
+        match result {
+            SolverResult::Sat(_) => Verdict::Sat,
+            SolverResult::Unsat => Verdict::Unsat,
+            SolverResult::Unknown => Verdict::Unknown,
+        }
+    }
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Sat => write!(f, "SAT"),
+            Verdict::Unsat => write!(f, "UNSAT"),
+            Verdict::Unknown => write!(f, "UNKNOWN"),
+        }
+    }
+}
+
+impl Display for Verification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verification::Model => write!(f, "model"),
+            Verification::ReSolve => write!(f, "re-solve"),
+        }
+    }
+}
+
+impl From<SolverStats> for JsonStats {
+    fn from(stats: SolverStats) -> Self {
+        Self {
+            decisions: stats.decisions,
+            propagations: stats.propagations,
+            pure_literals: stats.pure_literals,
+            conflicts: stats.conflicts,
+            restarts: stats.restarts,
+            conflicts_since_restart: stats.conflicts_since_restart,
+            max_depth: stats.max_depth,
+        }
+    }
+}
+
+pub fn json_model(assignment: &Assignment, instance: &SATInstance) -> JsonModel {
+    assignment.values.iter().map(|(var, value)| (instance.var_to_str[var].clone(), *value)).collect()
+}
@@ -0,0 +1,89 @@
+// Live progress of long solves on stderr.
+
+use std::{io::{self, IsTerminal, Write}, sync::{Arc, Mutex}, time::{Duration, Instant}};
+
+use sat_solver::solver::instance::{ProgressCallback, SolverProgress};
+
+// how often a log line is printed if stderr isn't a terminal
+const LOG_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Prints progress snapshots of the solver, as a single updating line on a terminal and as
+/// periodic log lines otherwise.
+#[derive(Debug, Clone)]
+pub struct ProgressDisplay {
+    terminal: bool,
+    state: Arc<Mutex<DisplayState>>,
+}
+
+#[derive(Debug, Default)]
+struct DisplayState {
+    /// Whether the terminal line has to be cleared
+    line_shown: bool,
+    last_log: Option<Instant>,
+}
+
+impl ProgressDisplay {
+    pub fn new() -> Self {
+        Self { terminal: io::stderr().is_terminal(), state: Arc::default() }
+    }
+
+    pub fn callback(&self) -> ProgressCallback {
+        let display = self.clone();
+        ProgressCallback::new(move |progress| display.show(progress))
+    }
+
+    fn show(&self, progress: &SolverProgress) {
+        let mut state = self.state.lock().expect("Printing doesn't panic");
+        let mut stderr = io::stderr().lock();
+
+        if self.terminal {
+            let _ = write!(stderr, "\r\x1b[2K{}", format_progress(progress));
+            let _ = stderr.flush();
+            state.line_shown = true;
+        } else if state.last_log.is_none_or(|last_log| last_log.elapsed() >= LOG_INTERVAL) {
+            let _ = writeln!(stderr, "c progress: {}", format_progress(progress));
+            state.last_log = Some(Instant::now());
+        }
+    }
+
+    /// Remove the progress line, so it doesn't mix with the result.
+    pub fn clear(&self) {
+        let mut state = self.state.lock().expect("Printing doesn't panic");
+
+        if state.line_shown {
+            eprint!("\r\x1b[2K");
+            state.line_shown = false;
+        }
+    }
+}
+
+/// One line describing `progress`, e.g.
+/// `12.5s  1520 decisions/s  conflicts 8712  depth 17  assigned 63%  restarts 2`.
+pub fn format_progress(progress: &SolverProgress) -> String {
+    let seconds = progress.elapsed.as_secs_f64();
+    let decisions_per_second = if seconds > 0.0 { progress.stats.decisions as f64 / seconds } else { 0.0 };
+    let assigned = (100 * progress.max_assigned).checked_div(progress.num_vars).unwrap_or(100);
+
+    format!(
+        "{:.1}s  {:.0} decisions/s  conflicts {}  depth {}  assigned {}%  restarts {}",
+        seconds, decisions_per_second, progress.stats.conflicts, progress.depth, assigned, progress.stats.restarts,
+    )
+}
+
+#[cfg(test)]
+use sat_solver::solver::instance::SolverStats;
+
+#[test]
+fn test_format_progress() {
+    let progress = SolverProgress {
+        stats: SolverStats { decisions: 19000, propagations: 120000, conflicts: 8712, restarts: 2, ..Default::default() },
+        elapsed: Duration::from_millis(12500),
+        depth: 17,
+        max_assigned: 63,
+        num_vars: 100,
+    };
+    assert_eq!(format_progress(&progress), "12.5s  1520 decisions/s  conflicts 8712  depth 17  assigned 63%  restarts 2");
+
+    let progress = SolverProgress { stats: SolverStats::default(), elapsed: Duration::ZERO, depth: 0, max_assigned: 0, num_vars: 0 };
+    assert_eq!(format_progress(&progress), "0.0s  0 decisions/s  conflicts 0  depth 0  assigned 100%  restarts 0");
+}
@@ -0,0 +1,122 @@
+// The run subcommand and the project bundle format it reads.
+//
+// A project is a TOML file bundling an instance with assumptions, a solver configuration and
+// optionally the expected result, e.g.
+//
+//     assumptions = ["a", "-b"]
+//
+//     [instance]
+//     path = "formula.cnf"
+//
+//     [config]
+//     heuristic = "vsids"
+//
+//     [expected]
+//     verdict = "SAT"
+
+use std::{fs, path::{Path, PathBuf}};
+
+use clap::Args;
+use sat_solver::{expression::expression::Assignment, solver::{dpll::solve_dpll_with_config, instance::SATInstance}};
+use serde::Deserialize;
+
+use super::{config::ConfigFile, json::Verdict, parse_instance, read_input, solve::print_human, InputFormat, Outcome};
+
+#[derive(Debug, Args)]
+pub struct RunArgs {
+    /// Project file, usually with the extension `.satproj`
+    project: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Project {
+    pub instance: InstanceRef,
+    #[serde(default)]
+    pub assumptions: Vec<NamedLiteral>,
+    #[serde(default)]
+    pub config: ConfigFile,
+    pub expected: Option<ExpectedResult>,
+}
+
+/// The instance of a project, given inline or as a path relative to the project file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub enum InstanceRef {
+    Expression(String),
+    Dimacs(String),
+    /// Expression or DIMACS file, detected from the content
+    Path(PathBuf),
+}
+
+/// `name` or `-name`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "String")]
+pub struct NamedLiteral {
+    pub name: String,
+    pub value: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ExpectedResult {
+    pub verdict: Verdict,
+}
+
+pub fn run(args: RunArgs) -> Result<Outcome, String> {
+    let project = Project::load(&args.project)?;
+    let instance = project.load_instance(&args.project)?;
+    let assignment = project.assignment(&instance)?;
+    let config = project.config.resolve()?;
+
+    let result = solve_dpll_with_config(instance.clone(), assignment, config);
+    print_human(&result, &instance);
+
+    match project.expected {
+        Some(expected) if expected.verdict != Verdict::from(&result) => {
+            Err(format!("expected {}, but the result is {}", expected.verdict, Verdict::from(&result)))
+        },
+        _ => Ok(Outcome::Success),
+    }
+}
+
+impl Project {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|err| format!("couldn't read '{}': {}", path.display(), err))?;
+
+        toml::from_str(&content).map_err(|err| format!("invalid project '{}': {}", path.display(), err.message()))
+    }
+
+    /// Parse the instance, resolving a relative path against the directory of `project_path`.
+    pub fn load_instance(&self, project_path: &Path) -> Result<SATInstance, String> {
+        match &self.instance {
+            InstanceRef::Expression(expression) => parse_instance(expression, InputFormat::Expr, project_path),
+            InstanceRef::Dimacs(dimacs) => parse_instance(dimacs, InputFormat::Dimacs, project_path),
+            InstanceRef::Path(path) => {
+                let path = project_path.parent().unwrap_or(Path::new("")).join(path);
+                parse_instance(&read_input(&path)?, InputFormat::Auto, &path)
+            },
+        }
+    }
+
+    /// The assumptions as an initial assignment, failing on names that aren't in `instance`.
+    pub fn assignment(&self, instance: &SATInstance) -> Result<Assignment, String> {
+        let mut assignment = Assignment::default();
+
+        for literal in &self.assumptions {
+            let var = instance.str_to_var.get(&literal.name).ok_or_else(|| format!("unknown variable '{}' in assumption", literal.name))?;
+            assignment.values.insert(*var, literal.value);
+        }
+
+        Ok(assignment)
+    }
+}
+
+impl From<String> for NamedLiteral {
+    fn from(literal: String) -> Self {
+        match literal.trim().strip_prefix('-') {
+            Some(name) => Self { name: name.trim().to_string(), value: false },
+            None => Self { name: literal.trim().to_string(), value: true },
+        }
+    }
+}
@@ -0,0 +1,365 @@
+// The serve subcommand, a long-running solver speaking line-delimited JSON over a socket.
+//
+// Every request is a JSON object on its own line with a `type` and an optional `id`, which is
+// copied into the response, e.g.
+//
+//     {"id": 1, "type": "load", "expression": "(a | b) & -a"}
+//     {"id": 2, "type": "add-clause", "literals": ["-b", "c"]}
+//     {"id": 3, "type": "solve", "assumptions": ["c"], "timeout": 2.5}
+//     {"id": 4, "type": "model"}
+//     {"id": 5, "type": "stats"}
+//     {"id": 6, "type": "cancel"}
+//
+// Solving happens in the background, the `result` response arrives once it is done and other
+// requests are answered in the meantime. Every connection has its own incremental solver, so the
+// formula is converted once when loading and added clauses are kept for all following solves.
+
+use std::{io::{self, BufRead, BufReader, Read, Write}, net::TcpListener, path::PathBuf, sync::{atomic::{AtomicBool, Ordering}, mpsc::{self, Sender}, Arc}, thread, time::{Duration, Instant}};
+
+use clap::{ArgGroup, Args};
+use sat_solver::{expression::{expression::Expression, normal::{Clause, Literal}}, parser::{parse_dimacs_str, parse_str}, solver::{incremental::IncrementalSolver, instance::{SATInstance, SolverConfig, SolverResult, SolverStats}}};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{config::ConfigArgs, json::{json_model, JsonModel, JsonStats, Verdict}, project::NamedLiteral, Outcome};
+
+#[derive(Debug, Args)]
+#[command(group(ArgGroup::new("address").required(true).args(["socket", "tcp"])))]
+pub struct ServeArgs {
+    /// Listen on a Unix domain socket at this path
+    #[arg(long)]
+    socket: Option<PathBuf>,
+
+    /// Listen on a TCP address instead, e.g. `127.0.0.1:7000`, port 0 picks a free one
+    #[arg(long)]
+    tcp: Option<String>,
+
+    /// Configuration of every solve, the timeout can be overridden per request
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+enum Request {
+    /// Replace the formula, given in exactly one of the formats
+    Load {
+        expression: Option<String>,
+        dimacs: Option<String>,
+    },
+    /// Add a clause of named literals, unknown names add new variables
+    AddClause {
+        literals: Vec<NamedLiteral>,
+    },
+    Solve {
+        #[serde(default)]
+        assumptions: Vec<NamedLiteral>,
+        /// In seconds
+        timeout: Option<f64>,
+    },
+    /// The model of the last finished solve
+    Model,
+    /// The statistics of the last finished solve
+    Stats,
+    /// Stop the running solve, which then answers with `UNKNOWN`
+    Cancel,
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    id: Option<Value>,
+    #[serde(flatten)]
+    body: ResponseBody,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum ResponseBody {
+    Loaded { variables: usize },
+    ClauseAdded { variables: usize },
+    Result { verdict: Verdict, model: Option<JsonModel>, stats: JsonStats, time: f64 },
+    Model { model: Option<JsonModel> },
+    Stats { stats: JsonStats },
+    /// Whether there was a solve to cancel
+    Cancelled { running: bool },
+    Error { message: String },
+}
+
+enum Event {
+    /// A request line, possibly not UTF-8
+    Request(Vec<u8>),
+    Finished { id: Option<Value>, solver: Box<IncrementalSolver>, result: SolverResult, stats: SolverStats, time: f64 },
+    Closed,
+}
+
+/// State of one connection.
+struct Session {
+    config: SolverConfig,
+    /// Names of the variables of the loaded formula and the added clauses
+    instance: SATInstance,
+    /// The loaded formula and the added clauses, `None` while it is solving on a worker thread
+    solver: Option<Box<IncrementalSolver>>,
+    /// Cancellation flag of the running solve
+    running: Option<Arc<AtomicBool>>,
+    last: Option<(SolverResult, SolverStats)>,
+    events: Sender<Event>,
+}
+
+pub fn run(args: ServeArgs) -> Result<Outcome, String> {
+    let config = args.config.resolve()?;
+
+    match (args.socket, args.tcp) {
+        (Some(path), _) => serve_unix(path, config),
+        (None, Some(address)) => {
+            let listener = TcpListener::bind(&address).map_err(|err| format!("couldn't listen on '{}': {}", address, err))?;
+            let address = listener.local_addr().map_err(|err| err.to_string())?;
+            eprintln!("c listening on {}", address);
+
+            serve(listener.incoming(), std::net::TcpStream::try_clone, config)
+        },
+        (None, None) => unreachable!("clap requires an address"),
+    }
+}
+
+#[cfg(unix)]
+fn serve_unix(path: PathBuf, config: SolverConfig) -> Result<Outcome, String> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    // a socket left behind by a server that was killed can be reused, a live one can't
+    if path.exists() {
+        if UnixStream::connect(&path).is_ok() {
+            return Err(format!("'{}' is in use by another server", path.display()));
+        }
+        std::fs::remove_file(&path).map_err(|err| format!("couldn't remove '{}': {}", path.display(), err))?;
+    }
+
+    let listener = UnixListener::bind(&path).map_err(|err| format!("couldn't listen on '{}': {}", path.display(), err))?;
+    eprintln!("c listening on {}", path.display());
+
+    serve(listener.incoming(), UnixStream::try_clone, config)
+}
+
+#[cfg(not(unix))]
+fn serve_unix(_path: PathBuf, _config: SolverConfig) -> Result<Outcome, String> {
+    Err("Unix domain sockets aren't supported on this platform, use --tcp".to_string())
+}
+
+// handle every connection on its own thread
+fn serve<S: Read + Write + Send + 'static>(incoming: impl Iterator<Item = io::Result<S>>, try_clone: fn(&S) -> io::Result<S>, config: SolverConfig) -> Result<Outcome, String> {
+    for stream in incoming {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("c couldn't accept a connection: {}", err);
+                continue;
+            },
+        };
+
+        let config = config.clone();
+        thread::spawn(move || {
+            if let Err(err) = try_clone(&stream).and_then(|reader| serve_connection(reader, stream, config)) {
+                eprintln!("c connection closed: {}", err);
+            }
+        });
+    }
+
+    Ok(Outcome::Success)
+}
+
+/// Answer the requests read from `reader` until it is closed.
+fn serve_connection(reader: impl Read + Send + 'static, mut writer: impl Write, config: SolverConfig) -> io::Result<()> {
+    // requests and finished solves arrive on the same channel, so a running solve doesn't block
+    // reading a cancel request
+    let (sender, receiver) = mpsc::channel();
+
+    let requests = sender.clone();
+    thread::spawn(move || {
+        // lines are read as bytes, so invalid UTF-8 is answered with an error like invalid JSON
+        let mut reader = BufReader::new(reader);
+        loop {
+            let mut line = Vec::new();
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if requests.send(Event::Request(line)).is_err() {
+                        return;
+                    }
+                },
+            }
+        }
+        let _ = requests.send(Event::Closed);
+    });
+
+    let mut session = Session::new(config, sender);
+    for event in receiver.iter() {
+        let response = match event {
+            Event::Request(line) => match std::str::from_utf8(&line) {
+                Ok(line) if line.trim().is_empty() => continue,
+                Ok(line) => session.handle(line),
+                Err(err) => Some(error(None, format!("requests must be UTF-8: {}", err))),
+            },
+            Event::Finished { id, solver, result, stats, time } => Some(session.finish(id, solver, result, stats, time)),
+            Event::Closed => break,
+        };
+
+        if let Some(response) = response {
+            writeln!(writer, "{}", serde_json::to_string(&response).map_err(io::Error::other)?)?;
+            writer.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+impl Session {
+    fn new(config: SolverConfig, events: Sender<Event>) -> Self {
+        let instance = SATInstance::new(Expression::Constant(true), Default::default());
+        let solver = Box::new(IncrementalSolver::new(instance.clone()));
+        Self { config, instance, solver: Some(solver), running: None, last: None, events }
+    }
+
+    fn solver(&mut self) -> &mut IncrementalSolver {
+        self.solver.as_mut().expect("The solver is only missing while a solve is running")
+    }
+
+    /// The response to a request line, or nothing if it starts a solve.
+    fn handle(&mut self, line: &str) -> Option<Response> {
+        let mut request = match serde_json::from_str::<Value>(line) {
+            Ok(Value::Object(request)) => request,
+            Ok(_) => return Some(error(None, "requests must be JSON objects".to_string())),
+            Err(err) => return Some(error(None, format!("invalid JSON: {}", err))),
+        };
+
+        let id = request.remove("id");
+        let request = match serde_json::from_value::<Request>(Value::Object(request)) {
+            Ok(request) => request,
+            Err(err) => return Some(error(id, format!("invalid request: {}", err))),
+        };
+
+        let body = match request {
+            Request::Model => match &self.last {
+                Some((SolverResult::Sat(model), _)) => Ok(ResponseBody::Model { model: Some(json_model(&model.clone().unwrap_or_default(), &self.instance)) }),
+                Some(_) => Ok(ResponseBody::Model { model: None }),
+                None => Err("nothing has been solved yet".to_string()),
+            },
+            Request::Stats => match &self.last {
+                Some((_, stats)) => Ok(ResponseBody::Stats { stats: (*stats).into() }),
+                None => Err("nothing has been solved yet".to_string()),
+            },
+            Request::Cancel => {
+                if let Some(cancel) = &self.running {
+                    cancel.store(true, Ordering::Relaxed);
+                }
+                Ok(ResponseBody::Cancelled { running: self.running.is_some() })
+            },
+            // the formula can't change under a running solve
+            _ if self.running.is_some() => Err("a solve is running, cancel it or wait for the result".to_string()),
+            Request::Load { expression, dimacs } => self.load(expression, dimacs),
+            Request::AddClause { literals } => self.add_clause(literals),
+            Request::Solve { assumptions, timeout } => match self.solve(id.clone(), assumptions, timeout) {
+                Ok(()) => return None,
+                Err(err) => Err(err),
+            },
+        };
+
+        Some(match body {
+            Ok(body) => Response { id, body },
+            Err(message) => error(id, message),
+        })
+    }
+
+    fn load(&mut self, expression: Option<String>, dimacs: Option<String>) -> Result<ResponseBody, String> {
+        let instance = match (expression, dimacs) {
+            (Some(expression), None) => parse_str(&expression).map_err(|err| format!("couldn't parse the expression: {}", err))?,
+            (None, Some(dimacs)) => parse_dimacs_str(&dimacs).map_err(|err| format!("couldn't parse the DIMACS input: {}", err))?,
+            _ => return Err("load needs exactly one of `expression` and `dimacs`".to_string()),
+        };
+
+        self.solver = Some(Box::new(IncrementalSolver::new(instance.clone())));
+        self.instance = instance;
+        self.last = None;
+
+        Ok(ResponseBody::Loaded { variables: self.instance.var_to_str.len() })
+    }
+
+    fn add_clause(&mut self, literals: Vec<NamedLiteral>) -> Result<ResponseBody, String> {
+        let mut clause = Vec::new();
+
+        for literal in literals {
+            let var = match self.instance.str_to_var.get(&literal.name) {
+                Some(var) => *var,
+                None => {
+                    let var = self.solver().add_variable();
+                    self.instance.var_to_str.insert(var, literal.name.clone());
+                    self.instance.str_to_var.insert(literal.name, var);
+                    var
+                },
+            };
+
+            clause.push(Literal::new(var, literal.value));
+        }
+
+        self.solver().add_clause(Clause::new(clause));
+
+        Ok(ResponseBody::ClauseAdded { variables: self.instance.var_to_str.len() })
+    }
+
+    // start solving on a worker thread, which sends the result as an event
+    fn solve(&mut self, id: Option<Value>, assumptions: Vec<NamedLiteral>, timeout: Option<f64>) -> Result<(), String> {
+        let assumptions = assumptions.into_iter()
+            .map(|literal| match self.instance.str_to_var.get(&literal.name) {
+                Some(var) => Ok(Literal::new(*var, literal.value)),
+                None => Err(format!("unknown variable '{}' in assumption", literal.name)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let timeout = timeout.map(Duration::try_from_secs_f64).transpose().map_err(|err| format!("invalid timeout: {}", err))?;
+        let cancel = Arc::new(AtomicBool::new(false));
+        let config = SolverConfig { timeout: timeout.or(self.config.timeout), cancel: Some(cancel.clone()), ..self.config.clone() };
+
+        let mut solver = self.solver.take().expect("The solver is only missing while a solve is running");
+        solver.config = config;
+        solver.clear_assumptions();
+        for literal in assumptions {
+            solver.assume(literal);
+        }
+
+        let events = self.events.clone();
+        thread::spawn(move || {
+            let start = Instant::now();
+            let (result, stats) = solver.solve_with_stats();
+            // the connection may be gone by now
+            let _ = events.send(Event::Finished { id, solver, result, stats, time: start.elapsed().as_secs_f64() });
+        });
+
+        self.running = Some(cancel);
+        Ok(())
+    }
+
+    fn finish(&mut self, id: Option<Value>, solver: Box<IncrementalSolver>, result: SolverResult, stats: SolverStats, time: f64) -> Response {
+        self.running = None;
+        self.solver = Some(solver);
+
+        let model = match &result {
+            SolverResult::Sat(model) => Some(json_model(&model.clone().unwrap_or_default(), &self.instance)),
+            _ => None,
+        };
+        let body = ResponseBody::Result { verdict: Verdict::from(&result), model, stats: stats.into(), time };
+        self.last = Some((result, stats));
+
+        Response { id, body }
+    }
+}
+
+impl Drop for Session {
+    // don't keep solving for a client that went away
+    fn drop(&mut self) {
+        if let Some(cancel) = &self.running {
+            cancel.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+fn error(id: Option<Value>, message: String) -> Response {
+    Response { id, body: ResponseBody::Error { message } }
+}
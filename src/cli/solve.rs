@@ -0,0 +1,423 @@
+// The solve subcommand.
+
+use std::{io, path::PathBuf, time::{Duration, Instant}};
+
+use clap::{Args, ValueEnum};
+use sat_solver::{color::{paint, Color}, expression::{expression::{Assignment, Expression, VariableId}, normal::{Clause, Literal, CNF}}, solver::{counting::{approx_count_models, count_models}, dpll::{solve_dpll_with_config, solve_dpll_with_config_and_stats}, enumerate::enumerate_projected_models, heuristics::BranchingHeuristic, instance::{SATInstance, SolverConfig, SolverResult, SolverStats}, two_sat::solve_2sat_with_stats}};
+
+use super::{config::{config_comments, ConfigArgs, ConfigFile}, json::{json_model, JsonResult, Verdict, Verification}, parse_instance, progress::ProgressDisplay, read_input, InputFormat, Outcome};
+
+#[derive(Debug, Args)]
+pub struct SolveArgs {
+    /// Input file, `-` reads from stdin
+    file: PathBuf,
+
+    /// Input format, detected from the content by default
+    #[arg(long, value_enum, default_value_t = InputFormat::Auto)]
+    format: InputFormat,
+
+    /// Initial assignment, e.g. `a=1,-b,c`
+    #[arg(long, value_delimiter = ',', allow_hyphen_values = true)]
+    assume: Vec<String>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+    output: OutputFormat,
+
+    /// SAT competition mode: only `s`, `v` and `c` lines on stdout, and exit code 0 if the solver
+    /// gave up
+    #[arg(long, conflicts_with = "output")]
+    competition: bool,
+
+    /// Print every model on its own line as soon as it is found
+    #[arg(long, conflicts_with_all = ["output", "competition", "count"])]
+    all_models: bool,
+
+    /// Stop after this many models
+    #[arg(long, requires = "all_models")]
+    limit: Option<usize>,
+
+    /// Only enumerate or count models over these variables, e.g. `a,b,c`
+    #[arg(long, value_delimiter = ',')]
+    project: Vec<String>,
+
+    /// Print the number of models
+    #[arg(long, conflicts_with_all = ["output", "competition"])]
+    count: bool,
+
+    /// Count by enumerating all models (the default)
+    #[arg(long, requires = "count", conflicts_with = "approx")]
+    exact: bool,
+
+    /// Count approximately, which is much faster for formulas with many models
+    #[arg(long, requires = "count")]
+    approx: bool,
+
+    /// Maximum relative error of approximate counting
+    #[arg(long, requires = "approx", default_value_t = 0.8)]
+    epsilon: f64,
+
+    /// Probability that an approximate count exceeds the error bound
+    #[arg(long, requires = "approx", default_value_t = 0.2)]
+    delta: f64,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    /// Print the effective configuration as `c` lines
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Print statistics about the search after solving
+    #[arg(long, conflicts_with_all = ["all_models", "count"])]
+    stats: bool,
+
+    /// Print the result as a JSON object, or an array of models with `--all-models`
+    #[arg(long, conflicts_with_all = ["output", "competition", "count", "stats", "verbose"])]
+    json: bool,
+
+    /// Check a model against the original formula, and UNSAT by solving again with another
+    /// heuristic and seed
+    #[arg(long, conflicts_with_all = ["all_models", "count"])]
+    verify: bool,
+
+    /// Show the progress of the search on stderr
+    #[arg(long, conflicts_with_all = ["all_models", "count"])]
+    progress: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// `SAT`/`UNSAT` followed by `name = value` lines
+    Human,
+    /// SAT competition style `s` and `v` lines, exit code 0 if the solver gave up
+    Competition,
+}
+
+pub fn run(args: SolveArgs) -> Result<Outcome, String> {
+    let content = read_input(&args.file)?;
+    let instance = parse_instance(&content, args.format, &args.file)?;
+    let initial_assignment = parse_assumptions(&args.assume, &instance)?;
+    let mut config = args.config.resolve()?;
+
+    if !args.project.is_empty() && !args.all_models && !args.count {
+        return Err("--project requires --all-models or --count".to_string());
+    }
+
+    if args.all_models || args.count {
+        let projection = parse_projection(&args.project, &instance)?;
+        let instance = assume(instance, &initial_assignment);
+
+        if args.all_models {
+            print_models(instance, projection, args.limit, args.json);
+        } else if args.approx {
+            if args.epsilon <= 0.0 || args.delta <= 0.0 || args.delta >= 1.0 {
+                return Err("epsilon has to be positive and delta has to be between 0 and 1".to_string());
+            }
+            println!("{}", approx_count_models(project(instance, &projection), args.epsilon, args.delta, config.seed));
+        } else {
+            println!("{}", count_models(project(instance, &projection)));
+        }
+
+        return Ok(Outcome::Success);
+    }
+
+    if args.competition {
+        println!("c sat-solver {}", env!("CARGO_PKG_VERSION"));
+        println!("c {} variables", instance.var_to_str.len());
+    }
+    if args.verbose {
+        print!("{}", config_comments(&config));
+    }
+
+    let progress = args.progress.then(ProgressDisplay::new);
+    config.progress = progress.as_ref().map(ProgressDisplay::callback);
+
+    let competition = args.competition || args.output == OutputFormat::Competition;
+    let start = Instant::now();
+    let (result, stats) = match solve_if_2sat(&instance, &initial_assignment, &config) {
+        Some(solved) => {
+            if args.verbose {
+                println!("c 2-SAT instance, solved with the implication graph");
+            }
+            solved
+        },
+        None => solve_dpll_with_config_and_stats(instance.clone(), initial_assignment.clone(), config.clone()),
+    };
+    let time = start.elapsed();
+
+    let verification = match args.verify {
+        true => verify(&instance, &result, &initial_assignment, &config)?,
+        false => None,
+    };
+    if let Some(progress) = &progress {
+        progress.clear();
+    }
+
+    if args.json {
+        let json = JsonResult {
+            verdict: Verdict::from(&result),
+            model: match &result {
+                SolverResult::Sat(assignment) => Some(assignment.as_ref().map(|assignment| json_model(assignment, &instance)).unwrap_or_default()),
+                _ => None,
+            },
+            stats: stats.into(),
+            config: ConfigFile::from(&config),
+            time: time.as_secs_f64(),
+            verified: verification,
+        };
+        println!("{}", serde_json::to_string(&json).map_err(|err| err.to_string())?);
+    } else if competition {
+        print_competition(&result);
+    } else {
+        print_human(&result, &instance);
+    }
+
+    if let (Some(verification), false) = (verification, args.json) {
+        println!("c VERIFIED {}", verification);
+    }
+    if args.stats {
+        print_stats(&stats, time, if competition { "c " } else { "" });
+    }
+
+    Ok(match result {
+        SolverResult::Sat(_) => Outcome::Satisfiable,
+        SolverResult::Unsat => Outcome::Unsatisfiable,
+        // benchmark scripts read exit code 0 as unknown
+        SolverResult::Unknown if competition => Outcome::Success,
+        SolverResult::Unknown => Outcome::Unknown,
+    })
+}
+
+// 2-SAT instances are solved in linear time without searching, the assumptions are added as unit
+// clauses
+fn solve_if_2sat(instance: &SATInstance, assumptions: &Assignment, config: &SolverConfig) -> Option<(SolverResult, SolverStats)> {
+    let mut cnf = CNF::new(clauses_2sat(&instance.expression)?);
+    cnf.clauses.extend(assumptions.values.iter().map(|(var, value)| Clause::new(vec![Literal::new(*var, *value)])));
+
+    let (result, stats) = solve_2sat_with_stats(&cnf);
+    let result = match result {
+        SolverResult::Sat(Some(mut model)) => {
+            if config.complete_model {
+                for var in instance.var_to_str.keys() {
+                    model.values.entry(*var).or_insert(false);
+                }
+            }
+            SolverResult::Sat(Some(instance.without_auxiliary(model)))
+        },
+        result => result,
+    };
+
+    Some((result, stats))
+}
+
+// the clauses of an expression that is a conjunction of clauses with at most two literals already,
+// checked on the expression itself because converting to CNF can blow up exponentially
+fn clauses_2sat(expression: &Expression) -> Option<Vec<Clause>> {
+    let literal = |expression: &Expression| match expression {
+        Expression::Variable(var) => Some(Literal::new(*var, true)),
+        Expression::Not(inner) => match **inner {
+            Expression::Variable(var) => Some(Literal::new(var, false)),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    let mut conjuncts = vec![expression];
+    let mut clauses = Vec::new();
+    while let Some(conjunct) = conjuncts.pop() {
+        match conjunct {
+            Expression::And(lhs, rhs) => conjuncts.extend([&**rhs, &**lhs]),
+            Expression::Or(lhs, rhs) => clauses.push(Clause::new(vec![literal(lhs)?, literal(rhs)?])),
+            Expression::Constant(true) => (),
+            Expression::Constant(false) => clauses.push(Clause::new(vec![])),
+            conjunct => clauses.push(Clause::new(vec![literal(conjunct)?])),
+        }
+    }
+
+    Some(clauses)
+}
+
+/// Check `result` independently of the solver. Fails if the model is wrong, and warns and returns
+/// None if UNSAT couldn't be confirmed. There are no proofs to check, so UNSAT is confirmed by
+/// solving again.
+fn verify(instance: &SATInstance, result: &SolverResult, assumptions: &Assignment, config: &SolverConfig) -> Result<Option<Verification>, String> {
+    match result {
+        SolverResult::Sat(Some(model)) => {
+            let respects_assumptions = assumptions.values.iter().all(|(var, value)| model.values.get(var) == Some(value));
+            if !respects_assumptions {
+                return Err("verification failed, the model doesn't respect the assumptions".to_string());
+            }
+            instance.check(model).map_err(|err| format!("verification failed, the model doesn't satisfy the formula: {}", err))?;
+
+            Ok(Some(Verification::Model))
+        },
+        SolverResult::Sat(None) => Err("verification failed, the solver didn't return a model".to_string()),
+        SolverResult::Unsat => {
+            let mut config = config.clone();
+            config.seed = config.seed.wrapping_add(1);
+            config.heuristic = match config.heuristic {
+                BranchingHeuristic::Random => BranchingHeuristic::JeroslowWang,
+                BranchingHeuristic::JeroslowWang => BranchingHeuristic::Vsids,
+                BranchingHeuristic::Vsids => BranchingHeuristic::Random,
+                BranchingHeuristic::FirstUnassigned | BranchingHeuristic::MostFrequent => BranchingHeuristic::Vsids,
+            };
+
+            match solve_dpll_with_config(instance.clone(), assumptions.clone(), config) {
+                SolverResult::Unsat => Ok(Some(Verification::ReSolve)),
+                SolverResult::Sat(_) => {
+                    eprintln!("{}: solving again found a model, the formula may be satisfiable", paint("warning", Color::Yellow).bold());
+                    Ok(None)
+                },
+                SolverResult::Unknown => {
+                    eprintln!("{}: couldn't verify UNSAT, solving again gave up", paint("warning", Color::Yellow).bold());
+                    Ok(None)
+                },
+            }
+        },
+        SolverResult::Unknown => Ok(None),
+    }
+}
+
+// `name`, `-name`, `name=1` or `name=0`
+fn parse_assumptions(assumptions: &[String], instance: &SATInstance) -> Result<Assignment, String> {
+    let mut assignment = Assignment::default();
+
+    for assumption in assumptions.iter().map(|assumption| assumption.trim()).filter(|assumption| !assumption.is_empty()) {
+        let (name, value) = match assumption.split_once('=') {
+            Some((name, "1" | "true")) => (name, true),
+            Some((name, "0" | "false")) => (name, false),
+            Some(_) => return Err(format!("invalid assumption '{}', expected a value of 0 or 1", assumption)),
+            None => match assumption.strip_prefix('-') {
+                Some(name) => (name, false),
+                None => (assumption, true),
+            },
+        };
+
+        let var = instance.str_to_var.get(name.trim()).ok_or_else(|| format!("unknown variable '{}' in assumption", name.trim()))?;
+        assignment.values.insert(*var, value);
+    }
+
+    Ok(assignment)
+}
+
+fn parse_projection(names: &[String], instance: &SATInstance) -> Result<Vec<VariableId>, String> {
+    if names.is_empty() {
+        let mut variables = instance.var_to_str.keys().copied().collect::<Vec<_>>();
+        variables.sort();
+        return Ok(variables);
+    }
+
+    names.iter()
+        .map(|name| instance.str_to_var.get(name.trim()).copied().ok_or_else(|| format!("unknown variable '{}' in projection", name.trim())))
+        .collect()
+}
+
+// conjoin the assumptions to the expression, enumeration has no initial assignment
+fn assume(instance: SATInstance, assignment: &Assignment) -> SATInstance {
+    let literals = assignment.values.iter().map(|(var, value)| match value {
+        true => Expression::Variable(*var),
+        false => Expression::Not(Box::new(Expression::Variable(*var))),
+    });
+    let expression = Expression::conjunction(std::iter::once(instance.expression).chain(literals).collect());
+
+    SATInstance { expression, ..instance }
+}
+
+// counting works on all variables of an instance, so drop the ones not projected onto
+fn project(instance: SATInstance, projection: &[VariableId]) -> SATInstance {
+    let var_to_str = instance.var_to_str.into_iter().filter(|(var, _)| projection.contains(var)).collect();
+
+    SATInstance::new(instance.expression, var_to_str)
+}
+
+// one model per line, e.g. `-a b c`, or one JSON object per line wrapped in an array so models
+// are still printed as soon as they are found
+fn print_models(instance: SATInstance, projection: Vec<VariableId>, limit: Option<usize>, json: bool) {
+    let names = instance.clone();
+
+    if json {
+        println!("[");
+    }
+
+    for (i, model) in enumerate_projected_models(instance, projection).take(limit.unwrap_or(usize::MAX)).enumerate() {
+        let model = json_model(&model, &names);
+
+        if json {
+            let separator = if i == 0 { "" } else { "," };
+            println!("{}{}", separator, serde_json::to_string(&model).expect("Models are serializable"));
+        } else {
+            let literals = model.into_iter().map(|(name, value)| if value { name } else { format!("-{}", name) }).collect::<Vec<_>>();
+            println!("{}", literals.join(" "));
+        }
+    }
+
+    if json {
+        println!("]");
+    }
+}
+
+fn print_stats(stats: &SolverStats, time: Duration, prefix: &str) {
+    println!("{}decisions:     {}", prefix, stats.decisions);
+    println!("{}propagations:  {}", prefix, stats.propagations);
+    println!("{}pure literals: {}", prefix, stats.pure_literals);
+    println!("{}conflicts:     {}", prefix, stats.conflicts);
+    println!("{}restarts:      {}", prefix, stats.restarts);
+    println!("{}since restart: {} conflicts", prefix, stats.conflicts_since_restart);
+    println!("{}max depth:     {}", prefix, stats.max_depth);
+    println!("{}time:          {:.3}s", prefix, time.as_secs_f64());
+}
+
+pub fn print_human(result: &SolverResult, instance: &SATInstance) {
+    match result {
+        SolverResult::Sat(assignment) => {
+            println!("{}", paint("SAT", Color::Green).bold());
+
+            if let Some(assignment) = assignment {
+                print!("{}", instance.name_assignment(assignment));
+            }
+        },
+        SolverResult::Unsat => println!("{}", paint("UNSAT", Color::Red).bold()),
+        SolverResult::Unknown => println!("{}", paint("UNKNOWN", Color::Yellow).bold()),
+    }
+}
+
+fn print_competition(result: &SolverResult) {
+    result.to_competition(&mut io::stdout().lock()).expect("Couldn't write to stdout");
+}
+
+#[cfg(test)]
+use sat_solver::parser::parse_str;
+
+#[test]
+fn test_verify_corrupted_model() {
+    let instance = parse_str("(a | b) & -a").unwrap();
+    let [a, b] = ["a", "b"].map(|name| instance.str_to_var[name]);
+    let config = SolverConfig::default();
+
+    let model = Assignment::from([(a, false), (b, true)]);
+    assert_eq!(verify(&instance, &SolverResult::Sat(Some(model.clone())), &Assignment::default(), &config), Ok(Some(Verification::Model)));
+
+    let corrupted = Assignment::from([(a, true), (b, false)]);
+    let err = verify(&instance, &SolverResult::Sat(Some(corrupted)), &Assignment::default(), &config).unwrap_err();
+    assert!(err.starts_with("verification failed"), "{}", err);
+
+    let assumptions = Assignment::from([(b, false)]);
+    assert!(verify(&instance, &SolverResult::Sat(Some(model)), &assumptions, &config).is_err());
+    assert!(verify(&instance, &SolverResult::Sat(None), &Assignment::default(), &config).is_err());
+}
+
+#[test]
+fn test_clauses_2sat() {
+    let instance = parse_str("(a | -b) & (b | c) & -c & 1").unwrap();
+    let clauses = clauses_2sat(&instance.expression).unwrap();
+    assert_eq!(clauses.iter().map(|clause| clause.literals.len()).collect::<Vec<_>>(), [2, 2, 1]);
+
+    for formula in ["(a | b | c) & -a", "a & -(b & c)", "(a & b) | (c & d)", "a ^ b"] {
+        assert!(clauses_2sat(&parse_str(formula).unwrap().expression).is_none(), "{}", formula);
+    }
+
+    // never converted, a CNF of the parity of 64 variables has 2^63 clauses
+    let parity = (1..64).map(|i| format!("x{}", i)).fold("x0".to_string(), |parity, var| format!("({} ^ {})", parity, var));
+    assert!(clauses_2sat(&parse_str(&parity).unwrap().expression).is_none());
+}
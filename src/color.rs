@@ -0,0 +1,131 @@
+// Colored terminal output. This is the one place deciding whether to emit ANSI escapes at all.
+
+use std::{env, fmt::Display, io::{self, IsTerminal}, sync::{atomic::{AtomicU8, Ordering}, OnceLock}};
+
+// colors are compiled out with the `no-color` feature or without the default `color` feature
+#[cfg(all(feature = "color", not(feature = "no-color")))]
+use colored::Colorize;
+
+/// When to color output.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color if stdout is a terminal and `NO_COLOR` isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Red,
+    Green,
+    Blue,
+    Yellow,
+    Cyan,
+    BrightRed,
+    BrightGreen,
+    BrightBlue,
+    BrightYellow,
+    BrightCyan,
+}
+
+/// Text displayed in a color if colors are enabled, see [paint].
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(not(all(feature = "color", not(feature = "no-color"))), allow(dead_code))]
+pub struct Painted<'a> {
+    text: &'a str,
+    color: Color,
+    bold: bool,
+}
+
+static CHOICE: AtomicU8 = AtomicU8::new(0);
+
+/// Set when to color output for the whole process.
+pub fn set_color_choice(choice: ColorChoice) {
+    CHOICE.store(choice as u8, Ordering::Relaxed);
+
+    // the explicit choices override the environment checks of `colored`
+    #[cfg(all(feature = "color", not(feature = "no-color")))]
+    match choice {
+        ColorChoice::Auto => colored::control::unset_override(),
+        choice => colored::control::set_override(choice == ColorChoice::Always),
+    }
+}
+
+pub fn color_choice() -> ColorChoice {
+    match CHOICE.load(Ordering::Relaxed) {
+        1 => ColorChoice::Always,
+        2 => ColorChoice::Never,
+        _ => ColorChoice::Auto,
+    }
+}
+
+/// Whether output is colored, which is never the case if colors are compiled out.
+pub fn colors_enabled() -> bool {
+    static AUTO: OnceLock<bool> = OnceLock::new();
+
+    cfg!(all(feature = "color", not(feature = "no-color"))) && match color_choice() {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => *AUTO.get_or_init(|| env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && io::stdout().is_terminal()),
+    }
+}
+
+/// Display `text` in `color`, or as plain text if colors are disabled.
+pub fn paint(text: &str, color: Color) -> Painted<'_> {
+    Painted { text, color, bold: false }
+}
+
+impl Painted<'_> {
+    pub fn bold(self) -> Self {
+        Self { bold: true, ..self }
+    }
+}
+
+impl Display for Painted<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        #[cfg(all(feature = "color", not(feature = "no-color")))]
+        if colors_enabled() {
+            let colored = self.text.color(colored::Color::from(self.color));
+            return match self.bold {
+                true => write!(f, "{}", colored.bold()),
+                false => write!(f, "{}", colored),
+            };
+        }
+
+        write!(f, "{}", self.text)
+    }
+}
+
+#[cfg(all(feature = "color", not(feature = "no-color")))]
+impl From<Color> for colored::Color {
+    fn from(color: Color) -> Self {
+        match color {
+            Color::Red => colored::Color::Red,
+            Color::Green => colored::Color::Green,
+            Color::Blue => colored::Color::Blue,
+            Color::Yellow => colored::Color::Yellow,
+            Color::Cyan => colored::Color::Cyan,
+            Color::BrightRed => colored::Color::BrightRed,
+            Color::BrightGreen => colored::Color::BrightGreen,
+            Color::BrightBlue => colored::Color::BrightBlue,
+            Color::BrightYellow => colored::Color::BrightYellow,
+            Color::BrightCyan => colored::Color::BrightCyan,
+        }
+    }
+}
+
+#[cfg(test)]
+use crate::parser::parse_str;
+
+#[test]
+fn test_never_colors() {
+    set_color_choice(ColorChoice::Never);
+
+    let instance = parse_str("(a | -b) & -(c & d) | (a & b)").unwrap();
+    let output = format!("{}\n{}\n{}", instance, instance.display_expression(), paint("SAT", Color::Green).bold());
+
+    assert!(!output.contains('\x1b'), "{:?}", output);
+    assert!(output.ends_with("\nSAT"));
+}
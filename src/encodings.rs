@@ -0,0 +1,3 @@
+pub mod cardinality;
+pub mod graph_coloring;
+pub mod sudoku;
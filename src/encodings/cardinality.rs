@@ -0,0 +1,170 @@
+// Cardinality constraints on variables as clauses.
+//
+// Encodings with auxiliary variables take them from `fresh`, which has to be larger than every
+// variable in use and is advanced past the new ones, so several constraints can share one instance.
+
+use crate::expression::{expression::VariableId, normal::{Clause, Literal}};
+
+fn next_fresh(fresh: &mut VariableId) -> Literal {
+    let id = *fresh;
+    *fresh = id.checked_add(1).expect("Ran out of variable ids");
+    Literal::new(id, true)
+}
+
+/// At least one of `vars` is true, a single clause. Without variables, it's the empty clause.
+pub fn at_least_one(vars: &[VariableId]) -> Vec<Clause> {
+    vec![Clause::new(vars.iter().map(|var| Literal::new(*var, true)).collect())]
+}
+
+/// At most one of `vars` is true, with a clause for every pair of them. No auxiliary variables,
+/// but quadratically many clauses.
+pub fn at_most_one_pairwise(vars: &[VariableId]) -> Vec<Clause> {
+    let mut clauses = Vec::new();
+    for (i, first) in vars.iter().enumerate() {
+        for second in &vars[i + 1..] {
+            clauses.push(Clause::new(vec![Literal::new(*first, false), Literal::new(*second, false)]));
+        }
+    }
+
+    clauses
+}
+
+/// At most one of `vars` is true, with the sequential encoding: linearly many clauses and
+/// `vars.len() - 1` auxiliary variables, see [at_most_k].
+pub fn at_most_one(vars: &[VariableId], fresh: &mut VariableId) -> Vec<Clause> {
+    at_most_k(vars, 1, fresh)
+}
+
+/// Exactly one of `vars` is true, [at_least_one] and [at_most_one].
+pub fn exactly_one(vars: &[VariableId], fresh: &mut VariableId) -> Vec<Clause> {
+    let mut clauses = at_least_one(vars);
+    clauses.extend(at_most_one(vars, fresh));
+    clauses
+}
+
+/// At most `k` of `vars` are true, with the sequential counter encoding (Sinz 2005): auxiliary
+/// variable `counts[i][j]` is implied if at least `j + 1` of the first `i + 1` variables are true.
+/// Uses `k * (vars.len() - 1)` auxiliary variables and O(`k * vars.len()`) clauses.
+pub fn at_most_k(vars: &[VariableId], k: usize, fresh: &mut VariableId) -> Vec<Clause> {
+    if k >= vars.len() {
+        return Vec::new();
+    }
+    if k == 0 {
+        return vars.iter().map(|var| Clause::new(vec![Literal::new(*var, false)])).collect();
+    }
+
+    // the counts after the last variable would never be read
+    let counts = vars[..vars.len() - 1].iter()
+        .map(|_| (0..k).map(|_| next_fresh(fresh)).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    let mut clauses = Vec::new();
+    for (i, var) in vars.iter().enumerate() {
+        let literal = Literal::new(*var, true);
+        if i > 0 {
+            // one more would exceed the maximum
+            clauses.push(Clause::new(vec![literal.not(), counts[i - 1][k - 1].not()]));
+        }
+
+        let Some(count) = counts.get(i) else {
+            continue;
+        };
+        clauses.push(Clause::new(vec![literal.not(), count[0]]));
+        if i == 0 {
+            continue;
+        }
+        for j in 0..k {
+            // counts only grow
+            clauses.push(Clause::new(vec![counts[i - 1][j].not(), count[j]]));
+            if j > 0 {
+                clauses.push(Clause::new(vec![literal.not(), counts[i - 1][j - 1].not(), count[j]]));
+            }
+        }
+    }
+
+    clauses
+}
+
+#[cfg(test)]
+use {std::collections::{HashMap, HashSet}, crate::{expression::{expression::Assignment, normal::CNF}, solver::{dpll::solve_dpll_cnf, enumerate::enumerate_models, instance::{SATInstance, SolverConfig, SolverResult}}}};
+
+// the models of `clauses` over `num_vars` variables, with everything from `num_vars` on auxiliary
+#[cfg(test)]
+fn count_models(clauses: Vec<Clause>, num_vars: VariableId, fresh: VariableId) -> usize {
+    let var_to_str = (0..fresh).map(|var| (var, format!("x{}", var))).collect::<HashMap<_, _>>();
+    let mut instance = SATInstance::new(CNF::new(clauses).to_expression(), var_to_str);
+    instance.auxiliary = (num_vars..fresh).collect::<HashSet<_>>();
+
+    enumerate_models(instance).inspect(|model| {
+        assert!(model.values.keys().all(|var| *var < num_vars), "auxiliary variables in {:?}", model);
+    }).count()
+}
+
+#[cfg(test)]
+fn binomial(n: usize, k: usize) -> usize {
+    (0..k).fold(1, |product, i| product * (n - i) / (i + 1))
+}
+
+#[test]
+fn test_at_most_one() {
+    for n in 1..=6 {
+        let vars = (0..n).collect::<Vec<VariableId>>();
+        let expected = 1 + usize::from(n);
+
+        assert_eq!(count_models(at_most_one_pairwise(&vars), n, n), expected);
+
+        let mut fresh = n;
+        let clauses = at_most_one(&vars, &mut fresh);
+        assert_eq!(fresh, 2 * n - 1);
+        assert_eq!(count_models(clauses, n, fresh), expected);
+    }
+}
+
+#[test]
+fn test_exactly_one() {
+    for n in 1..=6 {
+        let vars = (0..n).collect::<Vec<VariableId>>();
+        let mut fresh = n;
+        let clauses = exactly_one(&vars, &mut fresh);
+        assert_eq!(count_models(clauses, n, fresh), usize::from(n));
+    }
+
+    assert_eq!(count_models(at_least_one(&[0, 1, 2]), 3, 3), 7);
+    assert_eq!(at_least_one(&[]), [Clause::new(vec![])]);
+}
+
+#[test]
+fn test_at_most_k() {
+    for n in 1..=6u16 {
+        let vars = (0..n).collect::<Vec<VariableId>>();
+        for k in 0..=usize::from(n) + 1 {
+            let mut fresh = n;
+            let clauses = at_most_k(&vars, k, &mut fresh);
+            let expected = (0..=k.min(usize::from(n))).map(|i| binomial(usize::from(n), i)).sum::<usize>();
+            assert_eq!(count_models(clauses, n, fresh), expected, "n = {}, k = {}", n, k);
+        }
+    }
+
+    // constraints on the same instance take turns with the fresh variables
+    let mut fresh = 6;
+    let mut clauses = at_most_k(&[0, 1, 2], 2, &mut fresh);
+    let first = fresh;
+    clauses.extend(exactly_one(&[3, 4, 5], &mut fresh));
+    assert!(first > 6 && fresh > first);
+    assert_eq!(count_models(clauses, 6, fresh), 7 * 3);
+}
+
+#[test]
+fn test_at_most_k_assignments() {
+    // every assignment of 4 variables, checked against the number of true ones
+    for k in 0..=4 {
+        let mut fresh = 4;
+        let clauses = at_most_k(&[0, 1, 2, 3], k, &mut fresh);
+
+        for bits in 0..16u16 {
+            let assignment = Assignment::new((0..4).map(|var| (var, bits & (1 << var) != 0)).collect());
+            let result = solve_dpll_cnf(CNF::new(clauses.clone()), assignment, &SolverConfig::default()).0;
+            assert_eq!(matches!(result, SolverResult::Sat(_)), bits.count_ones() as usize <= k, "at most {} of {:04b}", k, bits);
+        }
+    }
+}
@@ -0,0 +1,154 @@
+// Graph coloring encoding.
+//
+// Variable `v<vertex>_c<color>` (both 0-based) is true iff `vertex` has `color`.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{expression::{expression::{Assignment, VariableId}, normal::{Clause, Literal, CNF}}, solver::instance::SATInstance};
+
+use super::cardinality::{at_least_one, at_most_one_pairwise};
+
+fn color_variable(vertex: usize, color: usize, num_colors: usize) -> VariableId {
+    VariableId::try_from(vertex * num_colors + color).expect("Coloring variables fit")
+}
+
+/// Clauses requiring every vertex to have exactly one of `num_colors` colors and the ends of every
+/// edge to have different colors. Without colors, any vertex makes the instance unsatisfiable, and
+/// so does a self-loop. Duplicate edges, in either direction, are encoded once. Panics if an edge
+/// has an end that isn't below `num_vertices`.
+pub fn coloring_cnf(num_vertices: usize, edges: &[(usize, usize)], num_colors: usize) -> CNF {
+    let mut clauses = Vec::new();
+
+    // every vertex has exactly one color, pairwise so there are no auxiliary variables
+    for vertex in 0..num_vertices {
+        let colors = (0..num_colors).map(|color| color_variable(vertex, color, num_colors)).collect::<Vec<_>>();
+        clauses.extend(at_least_one(&colors));
+        clauses.extend(at_most_one_pairwise(&colors));
+    }
+
+    let edges = edges.iter()
+        .map(|(from, to)| {
+            assert!(*from < num_vertices && *to < num_vertices, "Edge ({}, {}) has an unknown vertex", from, to);
+            (*from.min(to), *from.max(to))
+        })
+        .collect::<BTreeSet<_>>();
+
+    // the ends of an edge differ, a self-loop can't be colored at all
+    for (from, to) in edges {
+        for color in 0..num_colors {
+            let literals = if from == to {
+                vec![Literal::new(color_variable(from, color, num_colors), false)]
+            } else {
+                vec![Literal::new(color_variable(from, color, num_colors), false), Literal::new(color_variable(to, color, num_colors), false)]
+            };
+            clauses.push(Clause::new(literals));
+        }
+    }
+
+    CNF::new(clauses)
+}
+
+/// Instance of [coloring_cnf] with the variables named `v<vertex>_c<color>`, e.g. `v3_c1` for
+/// vertex 3 having color 1. Panics on edges with unknown vertices like [coloring_cnf].
+pub fn encode_coloring(num_vertices: usize, edges: &[(usize, usize)], num_colors: usize) -> SATInstance {
+    let mut var_to_str = HashMap::new();
+    for vertex in 0..num_vertices {
+        for color in 0..num_colors {
+            var_to_str.insert(color_variable(vertex, color, num_colors), format!("v{}_c{}", vertex, color));
+        }
+    }
+
+    SATInstance::new(coloring_cnf(num_vertices, edges, num_colors).to_expression(), var_to_str)
+}
+
+/// Read the color of every vertex out of a model of an instance from [encode_coloring]. The
+/// vertices and colors are taken from the variable names, so an instance without colors has no
+/// vertices either. Returns `None` if a vertex doesn't have exactly one color, i.e. the assignment
+/// isn't a model.
+pub fn decode_coloring(instance: &SATInstance, assignment: &Assignment) -> Option<Vec<usize>> {
+    let mut colors = HashMap::new();
+    let mut num_vertices = 0;
+
+    for (var, name) in &instance.var_to_str {
+        let Some((vertex, color)) = parse_color_variable(name) else {
+            continue;
+        };
+        num_vertices = num_vertices.max(vertex + 1);
+
+        if assignment.values.get(var) == Some(&true) && colors.insert(vertex, color).is_some() {
+            return None;
+        }
+    }
+
+    (0..num_vertices).map(|vertex| colors.get(&vertex).copied()).collect()
+}
+
+// vertex and color of a variable named `v<vertex>_c<color>`
+fn parse_color_variable(name: &str) -> Option<(usize, usize)> {
+    let (vertex, color) = name.strip_prefix('v')?.split_once("_c")?;
+    Some((vertex.parse().ok()?, color.parse().ok()?))
+}
+
+#[cfg(test)]
+use crate::solver::{dpll::solve_dpll, instance::SolverResult};
+
+#[cfg(test)]
+fn solve_coloring(num_vertices: usize, edges: &[(usize, usize)], num_colors: usize) -> Option<Vec<usize>> {
+    let instance = encode_coloring(num_vertices, edges, num_colors);
+    match solve_dpll(instance.clone(), Assignment::default()) {
+        SolverResult::Sat(Some(model)) => {
+            let coloring = decode_coloring(&instance, &model).expect("Models decode to a coloring");
+            assert_eq!(coloring.len(), num_vertices);
+            assert!(coloring.iter().all(|color| *color < num_colors));
+            for (from, to) in edges {
+                assert_ne!(coloring[*from], coloring[*to], "Edge ({}, {}) has the same color on both ends", from, to);
+            }
+            Some(coloring)
+        },
+        SolverResult::Unsat => None,
+        result => panic!("Unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn test_triangle() {
+    let triangle = [(0, 1), (1, 2), (2, 0)];
+    assert!(solve_coloring(3, &triangle, 3).is_some());
+    assert!(solve_coloring(3, &triangle, 2).is_none());
+
+    let instance = encode_coloring(3, &triangle, 3);
+    assert_eq!(instance.str_to_var["v2_c1"], color_variable(2, 1, 3));
+}
+
+#[test]
+fn test_petersen() {
+    // outer cycle, spokes and inner pentagram
+    let edges = (0..5).flat_map(|i| [(i, (i + 1) % 5), (i, i + 5), (i + 5, (i + 2) % 5 + 5)]).collect::<Vec<_>>();
+    assert_eq!(edges.len(), 15);
+
+    assert!(solve_coloring(10, &edges, 2).is_none());
+    assert!(solve_coloring(10, &edges, 3).is_some());
+    assert!(solve_coloring(10, &edges, 4).is_some());
+}
+
+#[test]
+fn test_degenerate_colorings() {
+    // no colors
+    assert!(solve_coloring(2, &[], 0).is_none());
+    assert_eq!(solve_coloring(0, &[], 0), Some(vec![]));
+
+    // self-loop
+    assert!(solve_coloring(2, &[(0, 1), (1, 1)], 5).is_none());
+
+    // assignments that aren't models
+    let instance = encode_coloring(2, &[(0, 1)], 2);
+    let var = |name: &str| instance.str_to_var[name];
+    assert_eq!(decode_coloring(&instance, &Assignment::from([(var("v0_c1"), true), (var("v1_c0"), true)])), Some(vec![1, 0]));
+    assert_eq!(decode_coloring(&instance, &Assignment::from([(var("v0_c1"), true)])), None);
+    assert_eq!(decode_coloring(&instance, &Assignment::from([(var("v0_c0"), true), (var("v0_c1"), true), (var("v1_c0"), true)])), None);
+    assert!(std::panic::catch_unwind(|| coloring_cnf(2, &[(0, 2)], 2)).is_err());
+
+    // duplicate edges
+    assert_eq!(coloring_cnf(2, &[(0, 1), (1, 0), (0, 1)], 2).clauses.len(), coloring_cnf(2, &[(0, 1)], 2).clauses.len());
+    assert!(solve_coloring(2, &[(0, 1), (1, 0)], 2).is_some());
+}
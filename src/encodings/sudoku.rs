@@ -0,0 +1,136 @@
+// Sudoku encoding.
+//
+// Variable `r<row>c<col>v<value>` (all 1-based) is true iff the cell in `row` and `col` contains
+// `value`.
+
+use std::collections::HashMap;
+
+use crate::{expression::{expression::{Assignment, VariableId}, normal::{Clause, Literal, CNF}}, solver::instance::SATInstance};
+
+use super::cardinality::{at_least_one, at_most_one_pairwise};
+
+pub type SudokuGrid = [[Option<u8>; 9]; 9];
+
+/// Id of the variable stating that the cell in `row` and `col` (0-based) contains `value` (1-9).
+pub fn cell_variable(row: usize, col: usize, value: u8) -> VariableId {
+    VariableId::try_from(row * 81 + col * 9 + usize::from(value - 1)).expect("Sudoku variables fit")
+}
+
+/// Clauses requiring every cell to contain exactly one value, every row, column and box to
+/// contain every value exactly once, and the given hints.
+pub fn sudoku_cnf(grid: &SudokuGrid) -> CNF {
+    let mut clauses = Vec::new();
+
+    let mut groups = Vec::new();
+    for i in 0..9 {
+        groups.push((0..9).map(|j| (i, j)).collect::<Vec<_>>());
+        groups.push((0..9).map(|j| (j, i)).collect::<Vec<_>>());
+        groups.push((0..9).map(|j| (i / 3 * 3 + j / 3, i % 3 * 3 + j % 3)).collect::<Vec<_>>());
+    }
+
+    // every cell contains exactly one value
+    for row in 0..9 {
+        for col in 0..9 {
+            let values = (1..=9).map(|value| cell_variable(row, col, value)).collect::<Vec<_>>();
+            clauses.extend(exactly_one(&values));
+        }
+    }
+
+    // every row, column and box contains every value exactly once
+    for group in &groups {
+        for value in 1..=9 {
+            let cells = group.iter().map(|(row, col)| cell_variable(*row, *col, value)).collect::<Vec<_>>();
+            clauses.extend(exactly_one(&cells));
+        }
+    }
+
+    // hints
+    for (row, values) in grid.iter().enumerate() {
+        for (col, value) in values.iter().enumerate() {
+            if let Some(value) = value {
+                assert!((1..=9).contains(value), "Sudoku values have to be in 1..=9");
+                clauses.push(Clause::new(vec![Literal::new(cell_variable(row, col, *value), true)]));
+            }
+        }
+    }
+
+    CNF::new(clauses)
+}
+
+pub fn encode_sudoku(grid: &SudokuGrid) -> SATInstance {
+    let mut var_to_str = HashMap::new();
+    for row in 0..9 {
+        for col in 0..9 {
+            for value in 1..=9 {
+                var_to_str.insert(cell_variable(row, col, value), format!("r{}c{}v{}", row + 1, col + 1, value));
+            }
+        }
+    }
+
+    SATInstance::new(sudoku_cnf(grid).to_expression(), var_to_str)
+}
+
+/// Read the grid out of a model of an instance from [encode_sudoku]. Cells without a true value
+/// are 0.
+pub fn decode_sudoku(instance: &SATInstance, assignment: &Assignment) -> [[u8; 9]; 9] {
+    let mut grid = [[0; 9]; 9];
+
+    for (row, values) in grid.iter_mut().enumerate() {
+        for (col, cell) in values.iter_mut().enumerate() {
+            *cell = (1..=9)
+                .find(|value| {
+                    let var = instance.str_to_var.get(&format!("r{}c{}v{}", row + 1, col + 1, value));
+                    var.and_then(|var| assignment.values.get(var)) == Some(&true)
+                })
+                .unwrap_or(0);
+        }
+    }
+
+    grid
+}
+
+// pairwise, so there are no auxiliary variables getting in the way of decoding
+fn exactly_one(vars: &[VariableId]) -> Vec<Clause> {
+    let mut clauses = at_least_one(vars);
+    clauses.extend(at_most_one_pairwise(vars));
+    clauses
+}
+
+#[cfg(test)]
+use crate::solver::{dpll::solve_dpll, instance::SolverResult};
+
+#[cfg(test)]
+fn parse_grid(rows: [&str; 9]) -> SudokuGrid {
+    rows.map(|row| {
+        let mut cells = row.chars().map(|cell| cell.to_digit(10).map(|value| value as u8));
+        std::array::from_fn(|_| cells.next().unwrap())
+    })
+}
+
+#[test]
+fn test_solve_sudoku() {
+    let puzzle = parse_grid([
+        "53..7....", "6..195...", ".98....6.",
+        "8...6...3", "4..8.3..1", "7...2...6",
+        ".6....28.", "...419..5", "....8..79",
+    ]);
+    let solution = parse_grid([
+        "534678912", "672195348", "198342567",
+        "859761423", "426853791", "713924856",
+        "961537284", "287419635", "345286179",
+    ]);
+
+    let instance = encode_sudoku(&puzzle);
+    let SolverResult::Sat(Some(model)) = solve_dpll(instance.clone(), Assignment::default()) else { panic!("puzzle is solvable") };
+    assert_eq!(decode_sudoku(&instance, &model), solution.map(|row| row.map(Option::unwrap)));
+    assert_eq!(instance.str_to_var["r3c5v7"], cell_variable(2, 4, 7));
+}
+
+#[test]
+fn test_unsolvable_sudoku() {
+    let mut puzzle = [[None; 9]; 9];
+    puzzle[0][0] = Some(5);
+    puzzle[0][8] = Some(5);
+
+    assert!(matches!(solve_dpll(encode_sudoku(&puzzle), Assignment::default()), SolverResult::Unsat));
+}
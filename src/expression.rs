@@ -1,3 +1,3 @@
+#[allow(clippy::module_inception)]
 pub mod expression;
 pub mod normal;
-
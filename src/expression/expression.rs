@@ -1,27 +1,38 @@
 // This file contains data structures and functions for expressions, assignments and evaluation.
 
-use std::{collections::HashMap, fmt::Display};
-use rand::seq::SliceRandom;
+use std::{collections::{HashMap, HashSet}, fmt::Display, hash::{DefaultHasher, Hash, Hasher}};
 
-use colored::{Color, Colorize};
+use crate::{color::{paint, Color}, solver::{dpll::solve_dpll, instance::{SATInstance, SolverResult}}};
 
 pub type VariableId = u16;
 
-// arbitrary expressions
-#[derive(Debug, Clone)]
+// arbitrary expressions, equality compares the structure and not the meaning
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum Expression {
     Variable(VariableId),
     Constant(bool),
     And(Box<Expression>, Box<Expression>),
     Or(Box<Expression>, Box<Expression>),
     Not(Box<Expression>),
+    Xor(Box<Expression>, Box<Expression>),
+    Implies(Box<Expression>, Box<Expression>),
+    Iff(Box<Expression>, Box<Expression>),
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Assignment {
     pub values: HashMap<VariableId, bool>
 }
 
+impl Hash for Assignment {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // independent of the iteration order of the map, like equality
+        let mut values = self.values.iter().collect::<Vec<_>>();
+        values.sort();
+        values.hash(state);
+    }
+}
+
 impl Expression {
     /// (Partially) evaluate `self` using the given [Assignment].
     pub fn evaluate(self, assignment: &Assignment) -> Expression {
@@ -79,49 +90,472 @@ impl Expression {
                     Expression::Not(Box::new(value))
                 }
             }
+            Expression::Xor(lhs, rhs) => {
+                match (lhs.evaluate(assignment), rhs.evaluate(assignment)) {
+                    (Expression::Constant(lhs), Expression::Constant(rhs)) => Expression::Constant(lhs != rhs),
+                    (Expression::Constant(val), other) | (other, Expression::Constant(val)) => {
+                        if val {
+                            Expression::Not(Box::new(other))
+                        } else {
+                            other
+                        }
+                    },
+                    (lhs, rhs) => Expression::Xor(Box::new(lhs), Box::new(rhs)),
+                }
+            }
+            Expression::Implies(lhs, rhs) => {
+                match (lhs.evaluate(assignment), rhs.evaluate(assignment)) {
+                    (Expression::Constant(false), _) | (_, Expression::Constant(true)) => Expression::Constant(true),
+                    (Expression::Constant(true), rhs) => rhs,
+                    (lhs, Expression::Constant(false)) => Expression::Not(Box::new(lhs)),
+                    (lhs, rhs) => Expression::Implies(Box::new(lhs), Box::new(rhs)),
+                }
+            }
+            Expression::Iff(lhs, rhs) => {
+                match (lhs.evaluate(assignment), rhs.evaluate(assignment)) {
+                    (Expression::Constant(lhs), Expression::Constant(rhs)) => Expression::Constant(lhs == rhs),
+                    (Expression::Constant(val), other) | (other, Expression::Constant(val)) => {
+                        if val {
+                            other
+                        } else {
+                            Expression::Not(Box::new(other))
+                        }
+                    },
+                    (lhs, rhs) => Expression::Iff(Box::new(lhs), Box::new(rhs)),
+                }
+            }
             _ => self
         }
     }
 
-}
+    /// Rewrite 'Xor', 'Implies' and 'Iff' using only 'And', 'Or' and 'Not'. Operands of 'Xor' and
+    /// 'Iff' are used twice.
+    ///
+    /// # Example
+    ///
+    /// `v0 -> (v1 ^ v2) => -v0 | ((v1 | v2) & (-v1 | -v2))`
+    pub fn desugar(self) -> Expression {
+        let not = |expr: Expression| Expression::Not(Box::new(expr));
+        let and = |lhs, rhs| Expression::And(Box::new(lhs), Box::new(rhs));
+        let or = |lhs, rhs| Expression::Or(Box::new(lhs), Box::new(rhs));
 
-impl Display for Expression {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let colors = [
-            Color::Red,
-            Color::Green,
-            Color::Blue,
-            Color::BrightRed,
-            Color::BrightGreen,
-            Color::BrightBlue,
-            Color::Yellow,
-            Color::BrightYellow,
-            Color::Cyan,
-            Color::BrightCyan
-        ];
+        match self {
+            Expression::Variable(_) | Expression::Constant(_) => self,
+            Expression::And(lhs, rhs) => and(lhs.desugar(), rhs.desugar()),
+            Expression::Or(lhs, rhs) => or(lhs.desugar(), rhs.desugar()),
+            Expression::Not(expr) => not(expr.desugar()),
+            Expression::Xor(lhs, rhs) => {
+                let (lhs, rhs) = (lhs.desugar(), rhs.desugar());
+                and(or(lhs.clone(), rhs.clone()), or(not(lhs), not(rhs)))
+            },
+            Expression::Implies(lhs, rhs) => or(not(lhs.desugar()), rhs.desugar()),
+            Expression::Iff(lhs, rhs) => {
+                let (lhs, rhs) = (lhs.desugar(), rhs.desugar());
+                and(or(not(lhs.clone()), rhs.clone()), or(lhs, not(rhs)))
+            },
+        }
+    }
 
+    /// Simplify `self` bottom-up without changing its truth table, until nothing changes anymore:
+    ///
+    /// - constants are folded away unless the whole expression is constant
+    /// - double negations are removed
+    /// - `a & a` and `a | a` become `a` (idempotence)
+    /// - `a & (a | b)` and `a | (a & b)` become `a` (absorption)
+    /// - `a & -a` and `a | -a` become constants, as do `a ^ a`, `a -> a` and `a <-> a`
+    ///
+    /// # Example
+    ///
+    /// `--(v0 & 1) | (v0 & v1) => v0`
+    pub fn simplify(self) -> Expression {
+        let mut expression = self;
+        loop {
+            let simplified = expression.clone().map_bottom_up(Expression::simplify_node);
+            if simplified == expression {
+                return simplified;
+            }
+            expression = simplified;
+        }
+    }
+
+    // the rules of `simplify` for a single node whose operands are already simplified
+    fn simplify_node(self) -> Expression {
         match self {
-            Expression::Variable(var) => write!(f, "v{}", var),
-            Expression::Constant(val) => write!(f, "{}", val),
-            Expression::And(lhs, rhs) => {
-                let color = *colors.choose(&mut rand::thread_rng()).unwrap();
-                write!(f, "{}{} & {}{}", "(".color(color), lhs, rhs, ")".color(color))
+            Expression::Variable(_) | Expression::Constant(_) => self,
+            Expression::Not(expr) => expr.negated(),
+            Expression::And(lhs, rhs) => match (*lhs, *rhs) {
+                (Expression::Constant(false), _) | (_, Expression::Constant(false)) => Expression::Constant(false),
+                (Expression::Constant(true), other) | (other, Expression::Constant(true)) => other,
+                (lhs, rhs) if lhs == rhs => lhs,
+                (lhs, rhs) if lhs.is_negation_of(&rhs) => Expression::Constant(false),
+                (lhs, Expression::Or(a, b)) if *a == lhs || *b == lhs => lhs,
+                (Expression::Or(a, b), rhs) if *a == rhs || *b == rhs => rhs,
+                (lhs, rhs) => Expression::And(Box::new(lhs), Box::new(rhs)),
             },
-            Expression::Or(lhs, rhs) => {
-                let color = *colors.choose(&mut rand::thread_rng()).unwrap();
-                write!(f, "{}{} | {}{}", "(".color(color), lhs, rhs, ")".color(color))
+            Expression::Or(lhs, rhs) => match (*lhs, *rhs) {
+                (Expression::Constant(true), _) | (_, Expression::Constant(true)) => Expression::Constant(true),
+                (Expression::Constant(false), other) | (other, Expression::Constant(false)) => other,
+                (lhs, rhs) if lhs == rhs => lhs,
+                (lhs, rhs) if lhs.is_negation_of(&rhs) => Expression::Constant(true),
+                (lhs, Expression::And(a, b)) if *a == lhs || *b == lhs => lhs,
+                (Expression::And(a, b), rhs) if *a == rhs || *b == rhs => rhs,
+                (lhs, rhs) => Expression::Or(Box::new(lhs), Box::new(rhs)),
             },
-            Expression::Not(expr) => {
-                write!(f, "-{}", expr)
+            Expression::Xor(lhs, rhs) => match (*lhs, *rhs) {
+                (Expression::Constant(val), other) | (other, Expression::Constant(val)) => if val { other.negated() } else { other },
+                (lhs, rhs) if lhs == rhs => Expression::Constant(false),
+                (lhs, rhs) => Expression::Xor(Box::new(lhs), Box::new(rhs)),
+            },
+            Expression::Implies(lhs, rhs) => match (*lhs, *rhs) {
+                (Expression::Constant(false), _) | (_, Expression::Constant(true)) => Expression::Constant(true),
+                (Expression::Constant(true), rhs) => rhs,
+                (lhs, Expression::Constant(false)) => lhs.negated(),
+                (lhs, rhs) if lhs == rhs => Expression::Constant(true),
+                (lhs, rhs) => Expression::Implies(Box::new(lhs), Box::new(rhs)),
+            },
+            Expression::Iff(lhs, rhs) => match (*lhs, *rhs) {
+                (Expression::Constant(val), other) | (other, Expression::Constant(val)) => if val { other } else { other.negated() },
+                (lhs, rhs) if lhs == rhs => Expression::Constant(true),
+                (lhs, rhs) => Expression::Iff(Box::new(lhs), Box::new(rhs)),
+            },
+        }
+    }
+
+    // negation that folds constants and doesn't stack up 'Not's
+    fn negated(self) -> Expression {
+        match self {
+            Expression::Constant(val) => Expression::Constant(!val),
+            Expression::Not(expr) => *expr,
+            expr => Expression::Not(Box::new(expr)),
+        }
+    }
+
+    fn is_negation_of(&self, other: &Expression) -> bool {
+        matches!(self, Expression::Not(expr) if **expr == *other) || matches!(other, Expression::Not(expr) if **expr == *self)
+    }
+
+    /// Replace every occurrence of the variable `var` with `replacement`.
+    pub fn substitute(self, var: VariableId, replacement: &Expression) -> Expression {
+        self.map_bottom_up(|expression| match expression {
+            Expression::Variable(v) if v == var => replacement.clone(),
+            expression => expression,
+        })
+    }
+
+    /// Rebuild the tree, applying `f` to every node after it has been applied to the operands of
+    /// the node. `f` only has to handle a single node, its operands are already rewritten.
+    ///
+    /// # Example
+    ///
+    /// Replacing `v0` with `1`: `-v0 | v1 => -1 | v1`
+    pub fn map_bottom_up<F: Fn(Expression) -> Expression>(self, f: F) -> Expression {
+        self.map_bottom_up_with(&f)
+    }
+
+    // takes `f` by reference so the recursion doesn't instantiate a new closure type per level
+    fn map_bottom_up_with<F: Fn(Expression) -> Expression>(self, f: &F) -> Expression {
+        let map = |expr: Box<Expression>| Box::new(expr.map_bottom_up_with(f));
+
+        let mapped = match self {
+            Expression::Variable(_) | Expression::Constant(_) => self,
+            Expression::Not(expr) => Expression::Not(map(expr)),
+            Expression::And(lhs, rhs) => Expression::And(map(lhs), map(rhs)),
+            Expression::Or(lhs, rhs) => Expression::Or(map(lhs), map(rhs)),
+            Expression::Xor(lhs, rhs) => Expression::Xor(map(lhs), map(rhs)),
+            Expression::Implies(lhs, rhs) => Expression::Implies(map(lhs), map(rhs)),
+            Expression::Iff(lhs, rhs) => Expression::Iff(map(lhs), map(rhs)),
+        };
+
+        f(mapped)
+    }
+
+    /// Every node of the tree in pre-order: a node comes before its operands and the left operand
+    /// before the right one. Doesn't recurse, so arbitrarily deep trees can be traversed.
+    pub fn subexpressions(&self) -> impl Iterator<Item = &Expression> + '_ {
+        let mut stack = vec![self];
+
+        std::iter::from_fn(move || {
+            let expression = stack.pop()?;
+            stack.extend(expression.children().rev());
+            Some(expression)
+        })
+    }
+
+    /// The variable `id`.
+    pub fn var(id: VariableId) -> Expression {
+        Expression::Variable(id)
+    }
+
+    /// `-expression`, without boxing it by hand.
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(expression: Expression) -> Expression {
+        Expression::Not(Box::new(expression))
+    }
+
+    /// `lhs & rhs`, without boxing the operands by hand.
+    pub fn and(lhs: Expression, rhs: Expression) -> Expression {
+        Expression::And(Box::new(lhs), Box::new(rhs))
+    }
+
+    /// `lhs | rhs`, without boxing the operands by hand.
+    pub fn or(lhs: Expression, rhs: Expression) -> Expression {
+        Expression::Or(Box::new(lhs), Box::new(rhs))
+    }
+
+    /// Conjunction of all `expressions`, `Constant(true)` if there are none. The tree is
+    /// balanced so large conjunctions don't produce deep (stack-hungry) expressions.
+    pub fn conjunction(expressions: Vec<Expression>) -> Expression {
+        balanced_tree(expressions, |lhs, rhs| Expression::And(Box::new(lhs), Box::new(rhs))).unwrap_or(Expression::Constant(true))
+    }
+
+    /// Disjunction of all `expressions`, `Constant(false)` if there are none. The tree is
+    /// balanced like in [Expression::conjunction].
+    pub fn disjunction(expressions: Vec<Expression>) -> Expression {
+        balanced_tree(expressions, |lhs, rhs| Expression::Or(Box::new(lhs), Box::new(rhs))).unwrap_or(Expression::Constant(false))
+    }
+
+    /// Hash of the tree shape and variable ids of `self`. Expressions that are equivalent but
+    /// written differently (e.g. `a & b` and `b & a`) hash differently.
+    pub fn structural_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Number of nodes in the tree, including the leaves.
+    pub fn size(&self) -> usize {
+        self.subexpressions().count()
+    }
+
+    /// Number of nodes on the longest path from the root to a leaf.
+    pub fn depth(&self) -> usize {
+        let mut depth = 0;
+        let mut stack = vec![(self, 1)];
+
+        while let Some((expression, level)) = stack.pop() {
+            depth = depth.max(level);
+            stack.extend(expression.children().map(|child| (child, level + 1)));
+        }
+
+        depth
+    }
+
+    /// Every variable occurring in the tree, negated or not.
+    pub fn variables(&self) -> HashSet<VariableId> {
+        self.subexpressions().filter_map(|expression| match expression {
+            Expression::Variable(var) => Some(*var),
+            _ => None,
+        }).collect()
+    }
+
+    /// Whether `self` is true under every assignment, i.e. its negation is a contradiction.
+    pub fn is_tautology(&self) -> bool {
+        Expression::not(self.clone()).is_contradiction()
+    }
+
+    /// Whether `self` is false under every assignment. Expressions that [Expression::simplify]
+    /// folds to a constant are decided right away, the others by solving their Tseitin encoding.
+    pub fn is_contradiction(&self) -> bool {
+        let simplified = match self.clone().simplify() {
+            Expression::Constant(value) => return !value,
+            simplified => simplified,
+        };
+
+        // the names only keep the auxiliary variables apart from the ones in use
+        let names = simplified.variables().into_iter().map(|var| (var, format!("v{}", var))).collect();
+        let instance = SATInstance::from_expression_tseitin(simplified, names);
+        solve_dpll(instance, Assignment::default()) == SolverResult::Unsat
+    }
+
+    /// Whether `self` and `other` have the same value under every assignment of the variables
+    /// `0..num_vars`, which have to be the only ones occurring in them. Takes time exponential in
+    /// `num_vars`.
+    pub fn equivalent(&self, other: &Expression, num_vars: VariableId) -> bool {
+        assert!(self.variables().into_iter().chain(other.variables()).all(|var| var < num_vars), "Variables have to be below num_vars");
+
+        let variables = (0..num_vars).collect::<Vec<_>>();
+        let mut assignment = Assignment::new(variables.iter().map(|var| (*var, false)).collect());
+
+        loop {
+            if assignment.satisfies_expression(self) != assignment.satisfies_expression(other) {
+                return false;
+            }
+            if !assignment.advance(&variables) {
+                return true;
             }
         }
     }
+
+    fn children(&self) -> impl DoubleEndedIterator<Item = &Expression> {
+        let (lhs, rhs) = match self {
+            Expression::Variable(_) | Expression::Constant(_) => (None, None),
+            Expression::Not(expr) => (Some(&**expr), None),
+            Expression::And(lhs, rhs) | Expression::Or(lhs, rhs) | Expression::Xor(lhs, rhs) | Expression::Implies(lhs, rhs) | Expression::Iff(lhs, rhs) => {
+                (Some(&**lhs), Some(&**rhs))
+            },
+        };
+
+        lhs.into_iter().chain(rhs)
+    }
+}
+
+/// Combine `items` pairwise until only one is left, producing a tree of logarithmic depth.
+pub(crate) fn balanced_tree<T>(mut items: Vec<T>, combine: impl Fn(T, T) -> T) -> Option<T> {
+    while items.len() > 1 {
+        let mut combined = Vec::with_capacity(items.len().div_ceil(2));
+        let mut remaining = items.into_iter();
+        while let Some(lhs) = remaining.next() {
+            match remaining.next() {
+                Some(rhs) => combined.push(combine(lhs, rhs)),
+                None => combined.push(lhs),
+            }
+        }
+        items = combined;
+    }
+
+    items.pop()
+}
+
+/// Displays an expression in the syntax accepted by the parser, see [Expression::display_with_names].
+/// Variables without a name are called `v<id>`.
+pub struct ExpressionDisplay<'a> {
+    expression: &'a Expression,
+    var_to_str: Option<&'a HashMap<VariableId, String>>,
+    /// Color parentheses by how deeply they are nested
+    colored: bool,
+}
+
+// colors of parentheses, cycling with the nesting depth
+const PARENTHESIS_COLORS: [Color; 10] = [
+    Color::Red,
+    Color::Green,
+    Color::Blue,
+    Color::BrightRed,
+    Color::BrightGreen,
+    Color::BrightBlue,
+    Color::Yellow,
+    Color::BrightYellow,
+    Color::Cyan,
+    Color::BrightCyan,
+];
+
+impl Expression {
+    /// Display `self` with the names in `var_to_str` instead of ids.
+    pub fn display_with_names<'a>(&'a self, var_to_str: &'a HashMap<VariableId, String>) -> ExpressionDisplay<'a> {
+        ExpressionDisplay { expression: self, var_to_str: Some(var_to_str), colored: false }
+    }
+}
+
+impl ExpressionDisplay<'_> {
+    /// Color the parentheses depending on their nesting depth if colors are enabled, see
+    /// [crate::color::colors_enabled]. The output stays the same otherwise.
+    pub fn colored(self) -> Self {
+        Self { colored: true, ..self }
+    }
+
+    // `depth` is the number of parentheses around `expression`
+    fn write(&self, f: &mut std::fmt::Formatter<'_>, expression: &Expression, depth: usize) -> std::fmt::Result {
+        // operands of a different binary operator get parentheses, chains of the same one don't,
+        // except on the left of '->' which groups to the right
+        let write_binary = |f: &mut std::fmt::Formatter<'_>, lhs: &Expression, operator: &str, rhs: &Expression| {
+            let is_implies = matches!(expression, Expression::Implies(_, _));
+            if is_binary(lhs) && (is_implies || std::mem::discriminant(lhs) != std::mem::discriminant(expression)) {
+                self.write_parenthesized(f, lhs, depth)?;
+            } else {
+                self.write(f, lhs, depth)?;
+            }
+            write!(f, " {} ", operator)?;
+            if is_binary(rhs) && std::mem::discriminant(rhs) != std::mem::discriminant(expression) {
+                self.write_parenthesized(f, rhs, depth)
+            } else {
+                self.write(f, rhs, depth)
+            }
+        };
+
+        match expression {
+            Expression::Variable(var) => match self.var_to_str.and_then(|var_to_str| var_to_str.get(var)) {
+                Some(name) => write!(f, "{}", name),
+                None => write!(f, "v{}", var),
+            },
+            Expression::Constant(val) => write!(f, "{}", u8::from(*val)),
+            Expression::And(lhs, rhs) => write_binary(f, lhs, "&", rhs),
+            Expression::Or(lhs, rhs) => write_binary(f, lhs, "|", rhs),
+            Expression::Xor(lhs, rhs) => write_binary(f, lhs, "^", rhs),
+            Expression::Implies(lhs, rhs) => write_binary(f, lhs, "->", rhs),
+            Expression::Iff(lhs, rhs) => write_binary(f, lhs, "<->", rhs),
+            Expression::Not(expr) => {
+                write!(f, "-")?;
+                if is_binary(expr) {
+                    self.write_parenthesized(f, expr, depth)
+                } else {
+                    self.write(f, expr, depth)
+                }
+            },
+        }
+    }
+
+    fn write_parenthesized(&self, f: &mut std::fmt::Formatter<'_>, expression: &Expression, depth: usize) -> std::fmt::Result {
+        let color = PARENTHESIS_COLORS[depth % PARENTHESIS_COLORS.len()];
+        let parenthesis = |f: &mut std::fmt::Formatter<'_>, text| match self.colored {
+            true => write!(f, "{}", paint(text, color)),
+            false => write!(f, "{}", text),
+        };
+
+        parenthesis(f, "(")?;
+        self.write(f, expression, depth + 1)?;
+        parenthesis(f, ")")
+    }
+}
+
+fn is_binary(expression: &Expression) -> bool {
+    !matches!(expression, Expression::Variable(_) | Expression::Constant(_) | Expression::Not(_))
+}
+
+impl Display for ExpressionDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write(f, self.expression, 0)
+    }
+}
+
+/// In the syntax accepted by the parser, with variables called `v<id>`.
+impl Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        ExpressionDisplay { expression: self, var_to_str: None, colored: false }.fmt(f)
+    }
 }
 
 impl Assignment {
     pub fn new(values: HashMap<VariableId, bool>) -> Self {
         Self { values }
     }
+
+    /// Whether `expr` evaluates to true under `self`. False if the value depends on variables
+    /// without a value.
+    pub fn satisfies_expression(&self, expr: &Expression) -> bool {
+        matches!(expr.clone().evaluate(self), Expression::Constant(true))
+    }
+
+    /// Count up in binary, with `variables` as the digits from least to most significant. Returns
+    /// false once all of them were true and wrapped around to false. Used to go through all
+    /// assignments of a few variables without allocating a new one every time.
+    pub(crate) fn advance(&mut self, variables: &[VariableId]) -> bool {
+        for var in variables {
+            let value = self.values.entry(*var).or_insert(false);
+            *value = !*value;
+            if *value {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Set every variable up to `max_id` that has no value yet to false.
+    pub fn complete(&mut self, max_id: VariableId) {
+        for var in 0..=max_id {
+            self.values.entry(var).or_insert(false);
+        }
+    }
 }
 
 impl<const N: usize> From<[(VariableId, bool); N]> for Assignment {
@@ -129,3 +563,177 @@ impl<const N: usize> From<[(VariableId, bool); N]> for Assignment {
         Self::new(HashMap::from(value))
     }
 }
+
+#[cfg(test)]
+use {crate::{expression::normal::random_expression, parser::parse_str}, rand::{rngs::StdRng, SeedableRng}};
+
+#[test]
+fn test_size_and_depth() {
+    let size_and_depth = |expression: &str| {
+        let expression = parse_str(expression).unwrap().expression;
+        (expression.size(), expression.depth())
+    };
+
+    assert_eq!(size_and_depth("a"), (1, 1));
+    assert_eq!(size_and_depth("a & b"), (3, 2));
+    assert_eq!(size_and_depth("a & (b | c)"), (5, 3));
+    assert_eq!(size_and_depth("-(a -> b) <-> c"), (6, 4));
+
+    // deeper than any recursive traversal could go
+    let mut deep = Expression::Variable(0);
+    for _ in 0..100000 {
+        deep = Expression::Not(Box::new(deep));
+    }
+    assert_eq!((deep.size(), deep.depth()), (100001, 100001));
+    // dropping it is recursive though
+    std::mem::forget(deep);
+
+    assert!(parse_str("a & b").unwrap().to_string().contains("Expression of size 3 and depth 2\n"));
+}
+
+#[test]
+fn test_variables() {
+    let instance = parse_str("(a & -b) | c").unwrap();
+    let ids = ["a", "b", "c"].map(|name| instance.str_to_var[name]);
+    assert_eq!(instance.expression.variables(), HashSet::from(ids));
+
+    assert_eq!(parse_str("-a ^ (a -> 1)").unwrap().expression.variables().len(), 1);
+    assert!(Expression::Constant(false).variables().is_empty());
+
+    let mut deep = Expression::Variable(3);
+    for _ in 0..100000 {
+        deep = Expression::Not(Box::new(deep));
+    }
+    assert_eq!(deep.variables(), HashSet::from([3]));
+    std::mem::forget(deep);
+}
+
+#[test]
+fn test_equivalent() {
+    let [a, b] = [0, 1].map(Expression::Variable);
+    let implication = Expression::Implies(Box::new(a.clone()), Box::new(b.clone()));
+    let disjunction = Expression::Or(Box::new(Expression::Not(Box::new(a.clone()))), Box::new(b.clone()));
+
+    assert!(implication.equivalent(&disjunction, 2));
+    assert!(!implication.equivalent(&Expression::Implies(Box::new(b), Box::new(a.clone())), 2));
+    assert!(Expression::Or(Box::new(a.clone()), Box::new(Expression::Not(Box::new(a)))).equivalent(&Expression::Constant(true), 1));
+    assert!(Expression::Constant(false).equivalent(&Expression::Constant(false), 0));
+}
+
+#[test]
+fn test_simplify() {
+    let simplified = |expression: &str| parse_str(expression).unwrap().expression.simplify();
+    let expression = |expression: &str| parse_str(expression).unwrap().expression;
+
+    assert_eq!(simplified("--(a & 1) | (a & b)"), expression("a"));
+    assert_eq!(simplified("a & a"), expression("a"));
+    assert_eq!(simplified("(a | b) & a"), expression("a"));
+    assert_eq!(simplified("a | (b & a)"), expression("a"));
+    assert_eq!(simplified("(a | 0) & (a | b)"), expression("a"));
+    assert_eq!(simplified("a -> 0"), expression("-a"));
+    assert_eq!(simplified("(a <-> 1) ^ 1"), expression("-a"));
+    assert_eq!(simplified("a & -a"), Expression::Constant(false));
+    assert_eq!(simplified("-(a ^ a) & (b -> b)"), Expression::Constant(true));
+    assert_eq!(simplified("a & (b | c)"), expression("a & (b | c)"));
+}
+
+#[test]
+fn test_simplify_preserves_truth_table() {
+    let mut rng = StdRng::seed_from_u64(0);
+
+    for _ in 0..500 {
+        let expression = random_expression(&mut rng, 5);
+        let simplified = expression.clone().simplify();
+
+        assert!(simplified.equivalent(&expression, 4), "{:?}", expression);
+        assert!(simplified.size() <= expression.size(), "{:?}", expression);
+        assert_eq!(simplified.clone().simplify(), simplified);
+    }
+}
+
+#[test]
+fn test_subexpressions() {
+    let expression = parse_str("a & (b | c)").unwrap().expression;
+    let nodes = expression.subexpressions().collect::<Vec<_>>();
+    assert_eq!(nodes.len(), 5);
+    assert!(matches!(nodes[..], [Expression::And(..), Expression::Variable(0), Expression::Or(..), Expression::Variable(1), Expression::Variable(2)]));
+
+    assert_eq!(Expression::Constant(true).subexpressions().collect::<Vec<_>>(), [&Expression::Constant(true)]);
+}
+
+#[test]
+fn test_map_bottom_up() {
+    let replace = |expression: Expression| match expression {
+        Expression::Variable(0) => Expression::Constant(true),
+        expression => expression,
+    };
+
+    for formula in ["a", "-a & b", "(a | b) -> (c ^ -a)", "(a <-> b) & (a | 1)", "b | c"] {
+        let expression = parse_str(formula).unwrap().expression;
+        let mapped = expression.clone().map_bottom_up(replace);
+        let evaluated = expression.evaluate(&Assignment::from([(0, true)]));
+        assert!(mapped.variables().iter().all(|var| *var != 0), "{}", formula);
+        assert!(mapped.equivalent(&evaluated, 3), "{}", formula);
+        assert_eq!(mapped.simplify(), evaluated.simplify(), "{}", formula);
+    }
+
+    // the operands are rewritten before their parent sees them
+    let mapped = parse_str("-(a & b)").unwrap().expression.map_bottom_up(|expression| match replace(expression) {
+        Expression::And(lhs, rhs) if *lhs == Expression::Constant(true) => *rhs,
+        expression => expression,
+    });
+    assert_eq!(mapped, Expression::Not(Box::new(Expression::Variable(1))));
+}
+
+#[test]
+fn test_tautology_and_contradiction() {
+    let expression = |input: &str| parse_str(input).unwrap().expression;
+
+    assert!(expression("a | -a").is_tautology());
+    assert!(expression("a & -a").is_contradiction());
+    assert!(!expression("a & b").is_tautology());
+    assert!(!expression("a | b").is_contradiction());
+    assert!(!expression("a & b").is_contradiction());
+    assert!(!expression("a | b").is_tautology());
+
+    // not decided by simplifying
+    assert!(expression("((a -> b) & (b -> c)) -> (a -> c)").is_tautology());
+    assert!(expression("(a ^ b) & (a <-> b)").is_contradiction());
+    assert!(!expression("(a -> b) -> (b -> a)").is_tautology());
+    assert!(Expression::Constant(true).is_tautology() && Expression::Constant(false).is_contradiction());
+}
+
+#[test]
+fn test_substitute() {
+    let (b, c) = (Expression::Variable(1), Expression::Variable(2));
+    let replacement = Expression::Or(Box::new(b.clone()), Box::new(c.clone()));
+
+    // a & -a with a = v0
+    let expression = parse_str("a & -a").unwrap().expression.substitute(0, &replacement);
+    let expected = Expression::And(Box::new(replacement.clone()), Box::new(Expression::Not(Box::new(replacement.clone()))));
+    assert_eq!(expression, expected);
+
+    for values in [[false, false], [false, true], [true, false], [true, true]] {
+        let assignment = Assignment::from([(1, values[0]), (2, values[1])]);
+        assert!(matches!(expression.clone().evaluate(&assignment), Expression::Constant(false)));
+    }
+
+    // other variables and operators are left alone
+    let expression = Expression::Implies(Box::new(b.clone()), Box::new(Expression::Variable(0))).substitute(0, &Expression::Constant(true));
+    assert_eq!(expression, Expression::Implies(Box::new(b), Box::new(Expression::Constant(true))));
+}
+
+#[test]
+fn test_equality() {
+    let expression = parse_str("a & (b | -c)").unwrap().expression;
+    assert_eq!(expression, parse_str("a & (b | -c)").unwrap().expression);
+    // equal structure, equal variable ids
+    assert_eq!(parse_str("a & b").unwrap().expression, parse_str("c & d").unwrap().expression);
+    assert_ne!(parse_str("a & (a | b)").unwrap().expression, parse_str("a & (b | a)").unwrap().expression);
+    assert_ne!(parse_str("a & b").unwrap().expression, parse_str("a | b").unwrap().expression);
+
+    let assignments = [Assignment::from([(0, true), (1, false)]), Assignment::from([(1, false), (0, true)]), Assignment::from([(0, true)])];
+    assert_eq!(assignments[0], assignments[1]);
+    assert_ne!(assignments[0], assignments[2]);
+    assert_eq!(assignments.iter().collect::<std::collections::HashSet<_>>().len(), 2);
+}
@@ -1,6 +1,8 @@
 // This file contains data structures and functions for transforming expressions into normal forms.
 
-use std::collections::HashSet;
+use std::{collections::{HashMap, HashSet}, io::{self, Write}};
+
+use crate::solver::preprocess::{forward_subsume, self_subsume};
 
 use super::expression::{Assignment, Expression, VariableId};
 
@@ -9,7 +11,7 @@ pub struct Clause {
     pub literals: Vec<Literal>,
 }
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Literal {
     pub var_id: VariableId,
     pub value: bool,
@@ -25,6 +27,18 @@ pub struct CNF {
     pub clauses: Vec<Clause>
 }
 
+/// Memoizes CNF conversion of expressions, keyed by [Expression::structural_hash].
+///
+/// Expressions are converted using the Tseitin transformation, so the clauses contain auxiliary
+/// variables starting at `next_tseitin_id`. Converting the same expression again returns the same
+/// clauses (including the same auxiliary variables) without redoing the conversion.
+#[derive(Debug, Default, Clone)]
+pub struct CnfCache {
+    cache: HashMap<u64, Vec<Clause>>,
+    first_tseitin_id: VariableId,
+    next_tseitin_id: VariableId,
+}
+
 impl Literal {
     pub fn new(var_id: VariableId, value: bool) -> Self {
         Self { var_id, value }
@@ -33,6 +47,19 @@ impl Literal {
     pub fn not(&self) -> Self {
         Self::new(self.var_id, !self.value)
     }
+
+    /// The number of `self` in DIMACS, which starts counting variables at 1: variable `v` is
+    /// written as `v + 1`, negative if the literal is negated.
+    pub fn to_dimacs(&self) -> i64 {
+        let number = i64::from(self.var_id) + 1;
+        if self.value { number } else { -number }
+    }
+
+    /// The literal written as `number` in DIMACS, `None` for 0 and numbers without a variable id.
+    pub fn from_dimacs(number: i64) -> Option<Self> {
+        let var_id = VariableId::try_from(number.unsigned_abs().checked_sub(1)?).ok()?;
+        Some(Self::new(var_id, number > 0))
+    }
 }
 
 impl Clause {
@@ -45,7 +72,7 @@ impl Clause {
 
 impl From<Literal> for Expression {
     fn from(value: Literal) -> Self {
-        if value.value == true {
+        if value.value {
             Expression::Variable(value.var_id)
         } else {
             Expression::Not(Box::new(Expression::Variable(value.var_id)))
@@ -57,38 +84,188 @@ impl DNF {
     pub fn new(clauses: Vec<Clause>) -> Self {
         Self { clauses }
     }
+
+    /// Disjunction of the conjunctions of `self`, like [CNF::to_expression]. Empty conjunctions
+    /// are `Constant(true)` and no conjunctions at all are `Constant(false)`.
+    pub fn to_expression(self) -> Expression {
+        let conjunctions = self.clauses.into_iter().map(|clause| {
+            Expression::conjunction(clause.literals.into_iter().map(Expression::from).collect())
+        });
+
+        Expression::disjunction(conjunctions.collect())
+    }
 }
 
 impl CNF {
     pub fn new(clauses: Vec<Clause>) -> Self {
         Self { clauses }
     }
+
+    /// Convert `expression` using `cache`, see [CnfCache::convert].
+    pub fn from_expression_with_cache(expression: &Expression, cache: &mut CnfCache) -> Option<Self> {
+        cache.convert(expression).map(Self::new)
+    }
+
+    /// Like [CNF::from], but the top-level conjuncts of `expression` are converted one by one, so
+    /// the clauses keep their order and contradicting ones like in `a & -a` aren't merged into a
+    /// single empty clause.
+    pub fn from_conjuncts(expression: Expression) -> Self {
+        let mut conjuncts = vec![expression];
+        let mut clauses = Vec::new();
+        while let Some(conjunct) = conjuncts.pop() {
+            match conjunct {
+                Expression::And(lhs, rhs) => conjuncts.extend([*rhs, *lhs]),
+                conjunct => clauses.extend(CNF::from(conjunct).clauses),
+            }
+        }
+
+        Self::new(clauses)
+    }
+
+    /// Conjunction of the clauses of `self` as a balanced expression tree. Clauses with a single
+    /// literal are just the literal, empty clauses are `Constant(false)` and no clauses at all are
+    /// `Constant(true)`.
+    pub fn to_expression(self) -> Expression {
+        let clauses = self.clauses.into_iter().map(|clause| {
+            Expression::disjunction(clause.literals.into_iter().map(Expression::from).collect())
+        });
+
+        Expression::conjunction(clauses.collect())
+    }
+
+    /// Remove redundant literals and clauses without changing the models:
+    ///
+    /// - duplicate literals in a clause
+    /// - clauses containing a literal and its negation
+    /// - duplicate clauses
+    /// - clauses containing all literals of another clause (subsumption, see [forward_subsume])
+    /// - `-l` from clauses `-l | c | d` if there is a clause `l | c` (self-subsuming resolution, see
+    ///   [self_subsume])
+    ///
+    /// If an empty clause comes up, it's the only clause left. Converting an expression with
+    /// [CNF::from] already does everything but self-subsuming resolution.
+    pub fn simplify(&mut self) {
+        self.clauses = normalize_clauses(std::mem::take(&mut self.clauses));
+
+        // strengthening only removes literals, and can make clauses subsume others
+        loop {
+            forward_subsume(self);
+
+            let num_literals = self.num_literals();
+            self_subsume(self);
+            if self.num_literals() == num_literals {
+                break;
+            }
+        }
+    }
+
+    fn num_literals(&self) -> usize {
+        self.clauses.iter().map(|clause| clause.literals.len()).sum()
+    }
+
+    /// Whether every clause is a Horn clause, i.e. has at most one positive literal. These can be
+    /// solved in linear time, see [crate::solver::horn::solve_horn].
+    pub fn is_horn(&self) -> bool {
+        self.clauses.iter().all(|clause| clause.literals.iter().filter(|literal| literal.value).count() <= 1)
+    }
+
+    /// Whether every clause has at most two literals. These can be solved in linear time, see
+    /// [crate::solver::two_sat::solve_2sat].
+    pub fn is_2sat(&self) -> bool {
+        self.clauses.iter().all(|clause| clause.literals.len() <= 2)
+    }
+
+    /// Write `self` in DIMACS CNF format, see [Literal::to_dimacs].
+    pub fn to_dimacs(&self, w: &mut impl Write) -> io::Result<()> {
+        let num_vars = self.clauses.iter()
+            .flat_map(|clause| &clause.literals)
+            .map(|literal| literal.to_dimacs().unsigned_abs())
+            .max()
+            .unwrap_or(0);
+
+        writeln!(w, "p cnf {} {}", num_vars, self.clauses.len())?;
+        for clause in &self.clauses {
+            for literal in &clause.literals {
+                write!(w, "{} ", literal.to_dimacs())?;
+            }
+            writeln!(w, "0")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Assignment {
+    /// Whether every clause of `cnf` has a literal that is true under `self`. Variables without a
+    /// value don't make any literal true.
+    pub fn satisfies_cnf(&self, cnf: &CNF) -> bool {
+        cnf.clauses.iter().all(|clause| clause.literals.iter().any(|literal| self.values.get(&literal.var_id) == Some(&literal.value)))
+    }
+}
+
+impl CnfCache {
+    /// Create an empty cache whose auxiliary variables start at `first_tseitin_id`, which has to
+    /// be larger than every variable id of the converted expressions.
+    pub fn new(first_tseitin_id: VariableId) -> Self {
+        Self { cache: HashMap::new(), first_tseitin_id, next_tseitin_id: first_tseitin_id }
+    }
+
+    /// Clauses of `expression`, converted or taken from the cache. Returns `None` and leaves the
+    /// cache unchanged if the auxiliary variable ids run out. Only [CnfCache::clear] reuses ids,
+    /// so clauses returned before and after an overflow can be conjoined.
+    pub fn convert(&mut self, expression: &Expression) -> Option<Vec<Clause>> {
+        let hash = expression.structural_hash();
+        if let Some(clauses) = self.cache.get(&hash) {
+            return Some(clauses.clone());
+        }
+
+        let mut next_tseitin_id = self.next_tseitin_id;
+        let clauses = expression.tseitin_clauses(&mut next_tseitin_id)?;
+        self.next_tseitin_id = next_tseitin_id;
+
+        self.cache.insert(hash, clauses.clone());
+        Some(clauses)
+    }
+
+    /// Forget all cached conversions. Auxiliary variable ids will be reused, so clauses returned
+    /// before must not be conjoined with ones returned afterwards.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        self.next_tseitin_id = self.first_tseitin_id;
+    }
+
+    pub fn next_tseitin_id(&self) -> VariableId {
+        self.next_tseitin_id
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
 }
 
 impl From<CNF> for Expression {
     fn from(value: CNF) -> Self {
-        let clause_expressions = value.clauses.into_iter().map(|clause| {
-            clause.literals.into_iter().fold(Expression::Constant(false), |acc, literal| {
-                Expression::Or(Box::new(acc), Box::new(literal.into()))
-            })
-        });
-
-        clause_expressions.into_iter().fold(Expression::Constant(true), |acc, clause| {
-                Expression::And(Box::new(acc), Box::new(clause.into()))
-                })
+        value.to_expression()
     }
 }
 
 impl From<Expression> for DNF {
     fn from(value: Expression) -> Self {
-        // convert to dnf
-        let dnf_expr = value.to_dnf_expr();
+        // constants are either all that's left or gone, they would become empty clauses otherwise
+        let dnf_expr = match value.to_dnf_expr() {
+            Expression::Constant(true) => return Self::new(vec![Clause::new(vec![])]),
+            Expression::Constant(false) => return Self::new(vec![]),
+            dnf_expr => dnf_expr,
+        };
 
         // extract clauses
         let mut clauses = HashSet::new();
         let mut remaining = vec![dnf_expr];
-        while !remaining.is_empty() {
-            let top = remaining.pop().unwrap();
+        while let Some(top) = remaining.pop() {
             if let Expression::Or(lhs, rhs) = top {
                 remaining.push(*lhs);
                 remaining.push(*rhs);
@@ -104,14 +281,17 @@ impl From<Expression> for DNF {
 
 impl From<Expression> for CNF {
     fn from(value: Expression) -> Self {
-        let cnf_expr = value.to_cnf_expr();
-        // eprintln!("CNF expression = {}", cnf_expr);
+        // constants are either all that's left or gone, they would become empty clauses otherwise
+        let cnf_expr = match value.to_cnf_expr() {
+            Expression::Constant(true) => return Self::new(vec![]),
+            Expression::Constant(false) => return Self::new(vec![Clause::new(vec![])]),
+            cnf_expr => cnf_expr,
+        };
 
         // extract clauses
         let mut clauses = Vec::new();
         let mut remaining = vec![cnf_expr];
-        while !remaining.is_empty() {
-            let top = remaining.pop().unwrap();
+        while let Some(top) = remaining.pop() {
             if let Expression::And(lhs, rhs) = top {
                 remaining.push(*lhs);
                 remaining.push(*rhs);
@@ -121,7 +301,10 @@ impl From<Expression> for CNF {
             }
         }
 
-        Self::new(clauses)
+        // the cheap part of `simplify`, which leaves the remaining clauses as they are
+        let mut cnf = Self::new(normalize_clauses(clauses));
+        forward_subsume(&mut cnf);
+        cnf
     }
 }
 
@@ -133,23 +316,8 @@ impl Expression {
     /// `(v0 | v1) & v2 => (v0 & v2) | (v1 & v2)`
     fn distribute_and_over_or(self) -> Expression {
         match self {
-            Expression::And(lhs, rhs) => {
-                if let Expression::Or(inner_lhs, inner_rhs) = *rhs {
-                    // (lhs & (inner_lhs | inner_rhs) => (lhs & inner_lhs) | (lhs & inner_rhs))
-                    Expression::Or(
-                        Box::new(Expression::And(lhs.clone(), inner_lhs.clone())),
-                        Box::new(Expression::And(lhs.clone(), inner_rhs.clone())),
-                    ).distribute_and_over_or()
-                } else if let Expression::Or(inner_lhs, inner_rhs) = *lhs {
-                    // ((inner_lhs | inner_rhs) & rhs) => (inner_lhs & rhs) | (inner_rhs & rhs)
-                    Expression::Or(
-                        Box::new(Expression::And(inner_lhs.clone(), rhs.clone())),
-                        Box::new(Expression::And(inner_rhs.clone(), rhs.clone())),
-                    ).distribute_and_over_or()
-                } else {
-                    Expression::And(Box::new(lhs.distribute_and_over_or()), Box::new(rhs.distribute_and_over_or()))
-                }
-            },
+            // distributing the operands can turn them into disjunctions, so they go first
+            Expression::And(lhs, rhs) => and_of_dnfs(lhs.distribute_and_over_or(), rhs.distribute_and_over_or()),
             Expression::Or(lhs, rhs) => Expression::Or(Box::new(lhs.distribute_and_over_or()), Box::new(rhs.distribute_and_over_or())),
             Expression::Not(expr) => Expression::Not(Box::new(expr.distribute_and_over_or())),
             _ => self,
@@ -162,11 +330,14 @@ impl Expression {
     /// # Example
     ///
     /// (v0 | v1) & v2 => (v0 & v2) | (v1 | v2)
+    ///
+    /// The result is either constant or contains no constants.
+    #[allow(clippy::wrong_self_convention)]
     fn to_dnf_expr(self) -> Expression {
-        let reduced = self.evaluate(&Assignment::default());
-        let nnf = reduced.recursive_demorgan();
-        let distributed = nnf.distribute_and_over_or();
-        distributed
+        let reduced = self.simplify().desugar();
+        // desugaring duplicates operands, simplifying again keeps the distribution from blowing up
+        let nnf = reduced.recursive_demorgan().simplify();
+        nnf.distribute_and_over_or()
     }
 
     /// Move 'Not' expressions inside
@@ -223,12 +394,97 @@ impl Expression {
     /// # Example
     ///
     /// (v0 & v1) | v2 => (v0 | v2) & (v1 | v2)
+    ///
+    /// The result is either constant or contains no constants.
+    #[allow(clippy::wrong_self_convention)]
     fn to_cnf_expr(self) -> Expression {
         // 1. negate and convert to dnf
         let negated_dnf = Expression::Not(Box::new(self)).to_dnf_expr();
 
         // 2. negate again and move 'not' inwards using DeMorgan
-        Expression::Not(Box::new(negated_dnf)).recursive_demorgan()
+        match negated_dnf {
+            Expression::Constant(val) => Expression::Constant(!val),
+            negated_dnf => Expression::Not(Box::new(negated_dnf)).recursive_demorgan(),
+        }
+    }
+
+    /// Tseitin transformation: introduce a fresh variable for every binary node and emit
+    /// clauses defining it, so the result grows linearly instead of exponentially. Returns `None`
+    /// if `next_id` would overflow.
+    ///
+    /// # Example
+    ///
+    /// `(v0 & v1) | v2 => (v3 | -v0 | -v1) & (-v3 | v0) & (-v3 | v1) & (-v4 | v3 | v2) & (v4 | -v3) & (v4 | -v2) & v4`
+    fn tseitin_clauses(&self, next_id: &mut VariableId) -> Option<Vec<Clause>> {
+        let mut clauses = Vec::new();
+
+        match self.clone().evaluate(&Assignment::default()) {
+            Expression::Constant(true) => {},
+            Expression::Constant(false) => clauses.push(Clause::default()),
+            reduced => {
+                let root = reduced.tseitin_literal(next_id, &mut clauses)?;
+                clauses.push(Clause::new(vec![root]));
+            }
+        }
+
+        Some(clauses)
+    }
+
+    /// Returns the literal representing `self`, adding the clauses defining it to `clauses`. `self`
+    /// must not contain constants.
+    fn tseitin_literal(&self, next_id: &mut VariableId, clauses: &mut Vec<Clause>) -> Option<Literal> {
+        match self {
+            Expression::Variable(var) => Some(Literal::new(*var, true)),
+            Expression::Constant(_) => unreachable!("constants are folded before the transformation"),
+            Expression::Not(expr) => Some(expr.tseitin_literal(next_id, clauses)?.not()),
+            Expression::And(lhs, rhs) => {
+                let lhs = lhs.tseitin_literal(next_id, clauses)?;
+                let rhs = rhs.tseitin_literal(next_id, clauses)?;
+                let aux = Literal::new(*next_id, true);
+                *next_id = next_id.checked_add(1)?;
+
+                // aux <-> (lhs & rhs)
+                clauses.push(Clause::new(vec![aux, lhs.not(), rhs.not()]));
+                clauses.push(Clause::new(vec![aux.not(), lhs]));
+                clauses.push(Clause::new(vec![aux.not(), rhs]));
+                Some(aux)
+            },
+            Expression::Or(lhs, rhs) => {
+                let lhs = lhs.tseitin_literal(next_id, clauses)?;
+                let rhs = rhs.tseitin_literal(next_id, clauses)?;
+                let aux = Literal::new(*next_id, true);
+                *next_id = next_id.checked_add(1)?;
+
+                // aux <-> (lhs | rhs)
+                clauses.push(Clause::new(vec![aux.not(), lhs, rhs]));
+                clauses.push(Clause::new(vec![aux, lhs.not()]));
+                clauses.push(Clause::new(vec![aux, rhs.not()]));
+                Some(aux)
+            },
+            Expression::Xor(lhs, rhs) => {
+                let lhs = lhs.tseitin_literal(next_id, clauses)?;
+                let rhs = rhs.tseitin_literal(next_id, clauses)?;
+                tseitin_xor(lhs, rhs, next_id, clauses)
+            },
+            Expression::Implies(lhs, rhs) => {
+                let lhs = lhs.tseitin_literal(next_id, clauses)?;
+                let rhs = rhs.tseitin_literal(next_id, clauses)?;
+                let aux = Literal::new(*next_id, true);
+                *next_id = next_id.checked_add(1)?;
+
+                // aux <-> (-lhs | rhs)
+                clauses.push(Clause::new(vec![aux.not(), lhs.not(), rhs]));
+                clauses.push(Clause::new(vec![aux, lhs]));
+                clauses.push(Clause::new(vec![aux, rhs.not()]));
+                Some(aux)
+            },
+            Expression::Iff(lhs, rhs) => {
+                let lhs = lhs.tseitin_literal(next_id, clauses)?;
+                let rhs = rhs.tseitin_literal(next_id, clauses)?;
+                // lhs <-> rhs is -(lhs ^ rhs)
+                Some(tseitin_xor(lhs, rhs, next_id, clauses)?.not())
+            },
+        }
     }
 
     /// Collect all literals of `self`
@@ -245,7 +501,7 @@ impl Expression {
                 literals_lhs.extend(rhs.collect_literals());
                 literals_lhs
             },
-            Expression::Or(lhs, rhs) => {
+            Expression::Or(lhs, rhs) | Expression::Xor(lhs, rhs) | Expression::Implies(lhs, rhs) | Expression::Iff(lhs, rhs) => {
                 let mut literals_lhs = lhs.collect_literals();
                 literals_lhs.extend(rhs.collect_literals());
                 literals_lhs
@@ -258,4 +514,381 @@ impl Expression {
             },
         }
     }
+
+    /// Whether `self` is a conjunction of clauses, i.e. of disjunctions of literals and constants.
+    /// [CNF::from] only blows up on expressions that aren't.
+    pub fn is_clausal(&self) -> bool {
+        let mut stack = vec![(self, true)];
+        while let Some((expr, top_level)) = stack.pop() {
+            match expr {
+                Expression::And(lhs, rhs) if top_level => stack.extend([(&**lhs, true), (&**rhs, true)]),
+                Expression::Or(lhs, rhs) => stack.extend([(&**lhs, false), (&**rhs, false)]),
+                Expression::Variable(_) | Expression::Constant(_) => {},
+                Expression::Not(inner) if matches!(**inner, Expression::Variable(_)) => {},
+                _ => return false,
+            }
+        }
+
+        true
+    }
+}
+
+// conjunction of two expressions in DNF as an expression in DNF
+fn and_of_dnfs(lhs: Expression, rhs: Expression) -> Expression {
+    match (lhs, rhs) {
+        // lhs & (inner_lhs | inner_rhs) => (lhs & inner_lhs) | (lhs & inner_rhs)
+        (lhs, Expression::Or(inner_lhs, inner_rhs)) => Expression::Or(
+            Box::new(and_of_dnfs(lhs.clone(), *inner_lhs)),
+            Box::new(and_of_dnfs(lhs, *inner_rhs)),
+        ),
+        // (inner_lhs | inner_rhs) & rhs => (inner_lhs & rhs) | (inner_rhs & rhs)
+        (Expression::Or(inner_lhs, inner_rhs), rhs) => Expression::Or(
+            Box::new(and_of_dnfs(*inner_lhs, rhs.clone())),
+            Box::new(and_of_dnfs(*inner_rhs, rhs)),
+        ),
+        (lhs, rhs) => Expression::And(Box::new(lhs), Box::new(rhs)),
+    }
+}
+
+// sort the literals of every clause and remove duplicate literals, tautologies and duplicate clauses
+fn normalize_clauses(clauses: Vec<Clause>) -> Vec<Clause> {
+    let mut seen = HashSet::new();
+
+    clauses.into_iter()
+        .filter_map(|mut clause| {
+            clause.literals.sort();
+            clause.literals.dedup();
+            // sorted, so a literal and its negation are next to each other
+            let tautology = clause.literals.windows(2).any(|pair| pair[0].var_id == pair[1].var_id);
+            (!tautology && seen.insert(clause.clone())).then_some(clause)
+        })
+        .collect()
+}
+
+// fresh literal for lhs ^ rhs, adding the clauses defining it to `clauses`
+fn tseitin_xor(lhs: Literal, rhs: Literal, next_id: &mut VariableId, clauses: &mut Vec<Clause>) -> Option<Literal> {
+    let aux = Literal::new(*next_id, true);
+    *next_id = next_id.checked_add(1)?;
+
+    // aux <-> (lhs ^ rhs)
+    clauses.push(Clause::new(vec![aux.not(), lhs, rhs]));
+    clauses.push(Clause::new(vec![aux.not(), lhs.not(), rhs.not()]));
+    clauses.push(Clause::new(vec![aux, lhs.not(), rhs]));
+    clauses.push(Clause::new(vec![aux, lhs, rhs.not()]));
+    Some(aux)
+}
+
+/// Equisatisfiable CNF of `expr` using the Tseitin transformation, with O(1) clauses per node.
+/// Auxiliary variables are allocated from `next_id`, which has to be larger than every variable of
+/// `expr` and is advanced past them. Also returns names `_t0`, `_t1`, ... for the auxiliary
+/// variables. Falls back to [CNF::from] without auxiliary variables if the ids run out.
+pub fn to_cnf_tseitin(expr: &Expression, next_id: &mut VariableId) -> (CNF, HashMap<VariableId, String>) {
+    let first_id = *next_id;
+
+    match expr.tseitin_clauses(next_id) {
+        Some(clauses) => {
+            let names = (first_id..*next_id).enumerate().map(|(index, var)| (var, format!("_t{}", index))).collect();
+            (CNF::new(clauses), names)
+        },
+        None => {
+            *next_id = first_id;
+            (CNF::from(expr.clone()), HashMap::new())
+        },
+    }
+}
+
+#[cfg(test)]
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+#[cfg(test)]
+use crate::{generator::random_ksat_cnf, parser::{parse_dimacs_cnf, parse_str}, solver::{bruteforce::solve_bruteforce, dpll::solve_dpll, instance::{SATInstance, SolverResult}}};
+
+#[cfg(test)]
+fn cached_instance(expression: &Expression, cache: &mut CnfCache) -> SATInstance {
+    let cnf = CNF::from_expression_with_cache(expression, cache).unwrap();
+    let var_to_str = (0..cache.next_tseitin_id()).map(|var| (var, format!("v{}", var))).collect();
+    SATInstance::new(cnf.into(), var_to_str)
+}
+
+#[test]
+fn test_cnf_cache_hit() {
+    let a = Box::new(Expression::Variable(0));
+    let b = Box::new(Expression::Variable(1));
+    let c = Box::new(Expression::Variable(2));
+
+    let mut cache = CnfCache::new(3);
+    let expression = Expression::Or(Box::new(Expression::And(a.clone(), b)), c.clone());
+
+    let first = cache.convert(&expression).unwrap();
+    assert_eq!(cache.next_tseitin_id(), 5);
+    assert_eq!(cache.len(), 1);
+
+    // a hit doesn't allocate new auxiliary variables
+    let second = cache.convert(&expression.clone()).unwrap();
+    assert_eq!(first, second);
+    assert_eq!(cache.next_tseitin_id(), 5);
+    assert_eq!(cache.len(), 1);
+
+    // a different expression gets its own auxiliary variables
+    let other = cache.convert(&Expression::And(a, c)).unwrap();
+    assert!(other.iter().flat_map(|clause| &clause.literals).any(|literal| literal.var_id == 5));
+    assert_eq!(cache.len(), 2);
+
+    cache.clear();
+    assert!(cache.is_empty());
+    assert_eq!(cache.next_tseitin_id(), 3);
+    assert_eq!(cache.convert(&expression), Some(first));
+}
+
+#[test]
+fn test_cnf_cache_overflow() {
+    let a = Box::new(Expression::Variable(0));
+    let b = Box::new(Expression::Variable(1));
+    let auxiliary = |clauses: &[Clause]| clauses.iter().flat_map(|clause| &clause.literals).map(|literal| literal.var_id).filter(|var| *var > 1).collect::<HashSet<_>>();
+
+    let mut cache = CnfCache::new(VariableId::MAX - 2);
+    let before = cache.convert(&Expression::Or(a.clone(), b.clone())).unwrap();
+    assert_eq!(cache.next_tseitin_id(), VariableId::MAX - 1);
+
+    // needs three auxiliary variables, the cache is left as it was
+    let big = Expression::And(Box::new(Expression::Or(a.clone(), b.clone())), Box::new(Expression::Xor(a.clone(), b.clone())));
+    assert_eq!(cache.convert(&big), None);
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.next_tseitin_id(), VariableId::MAX - 1);
+
+    // later conversions don't reuse the ids of earlier ones
+    let negated = Expression::Or(Box::new(Expression::Not(a.clone())), Box::new(Expression::Not(b.clone())));
+    let after = cache.convert(&negated).unwrap();
+    assert!(auxiliary(&before).is_disjoint(&auxiliary(&after)));
+
+    let clauses = before.into_iter().chain(after).collect::<Vec<_>>();
+    let var_to_str = [0, 1].into_iter().chain(auxiliary(&clauses)).map(|var| (var, format!("v{}", var))).collect();
+    let SolverResult::Sat(Some(model)) = solve_dpll(SATInstance::new(CNF::new(clauses).into(), var_to_str), Assignment::default()) else {
+        panic!("a ^ b is satisfiable");
+    };
+    assert!(model.satisfies_expression(&Expression::Xor(a, b)));
+}
+
+#[test]
+fn test_cnf_cache_satisfiability() {
+    let a = Box::new(Expression::Variable(0));
+    let b = Box::new(Expression::Variable(1));
+    let c = Box::new(Expression::Variable(2));
+
+    let mut cache = CnfCache::new(3);
+    let expression = Expression::And(
+        Box::new(Expression::Or(Box::new(Expression::And(a.clone(), b.clone())), c.clone())),
+        Box::new(Expression::Not(c)),
+    );
+
+    let SolverResult::Sat(Some(assignment)) = solve_dpll(cached_instance(&expression, &mut cache), Assignment::default()) else {
+        panic!("expression is satisfiable");
+    };
+    assert!(assignment.satisfies_expression(&expression));
+
+    let contradiction = Expression::And(a.clone(), Box::new(Expression::Not(a)));
+    assert!(matches!(solve_dpll(cached_instance(&contradiction, &mut cache), Assignment::default()), SolverResult::Unsat));
+}
+
+#[test]
+fn test_to_cnf_tseitin_linear() {
+    // (v0 & v1) | (v2 & v3) | ... distributes into 2^20 clauses
+    let pairs = (0..20).map(|pair| Expression::And(Box::new(Expression::Variable(2 * pair)), Box::new(Expression::Variable(2 * pair + 1))));
+    let expression = Expression::disjunction(pairs.collect());
+    let operators = 20 + 19;
+
+    let mut next_id = 40;
+    let (cnf, names) = to_cnf_tseitin(&expression, &mut next_id);
+    assert_eq!(next_id, 40 + operators);
+    assert_eq!(cnf.clauses.len(), 3 * usize::from(operators) + 1);
+    assert_eq!(names.len(), usize::from(operators));
+    assert_eq!(names[&40], "_t0");
+
+    // the root is asserted by a unit clause
+    let root = Literal::new(next_id - 1, true);
+    assert!(cnf.clauses.contains(&Clause::new(vec![root])));
+
+    // no ids left, so the expression is distributed instead
+    let mut next_id = VariableId::MAX;
+    let (cnf, names) = to_cnf_tseitin(&Expression::Or(Box::new(Expression::Variable(0)), Box::new(Expression::Variable(1))), &mut next_id);
+    assert_eq!((cnf.clauses.len(), names.len(), next_id), (1, 0, VariableId::MAX));
+}
+
+#[test]
+fn test_satisfies() {
+    // (a | b) & -c
+    let cnf = CNF::new(vec![Clause::new(vec![Literal::new(0, true), Literal::new(1, true)]), Clause::new(vec![Literal::new(2, false)])]);
+    let expression = cnf.clone().to_expression();
+
+    let partial = Assignment::from([(0, true)]);
+    assert!(!partial.satisfies_cnf(&cnf));
+    assert!(!partial.satisfies_expression(&expression));
+
+    let full = Assignment::from([(0, true), (2, false)]);
+    assert!(full.satisfies_cnf(&cnf));
+    assert!(full.satisfies_expression(&expression));
+
+    let wrong = Assignment::from([(0, false), (1, false), (2, false)]);
+    assert!(!wrong.satisfies_cnf(&cnf));
+    assert!(!wrong.satisfies_expression(&expression));
+    assert!(Assignment::default().satisfies_cnf(&CNF::new(vec![])));
+}
+
+#[cfg(test)]
+fn simplified(clauses: &[&[(VariableId, bool)]]) -> Vec<Vec<(VariableId, bool)>> {
+    let clauses = clauses.iter().map(|clause| Clause::new(clause.iter().map(|(var, value)| Literal::new(*var, *value)).collect()));
+    let mut cnf = CNF::new(clauses.collect());
+    cnf.simplify();
+
+    cnf.clauses.into_iter().map(|clause| clause.literals.into_iter().map(|literal| (literal.var_id, literal.value)).collect()).collect()
+}
+
+#[test]
+fn test_simplify() {
+    let (a, b, c) = (0, 1, 2);
+
+    // duplicate literals and clauses
+    assert_eq!(simplified(&[&[(b, true), (a, true), (b, true)], &[(a, true), (b, true)]]), [[(a, true), (b, true)]]);
+    // subsumption
+    assert_eq!(simplified(&[&[(a, true), (b, true), (c, false)], &[(a, true)], &[(a, true), (b, true)], &[(b, true)]]), [vec![(a, true)], vec![(b, true)]]);
+    // self-subsuming resolution
+    assert_eq!(simplified(&[&[(a, true), (b, true)], &[(a, false), (b, true), (c, true)]]), [[(a, true), (b, true)], [(b, true), (c, true)]]);
+    // resolving a | b with a | -b gives a, which subsumes both
+    assert_eq!(simplified(&[&[(a, true), (b, true)], &[(a, true), (b, false)], &[(c, true)]]), [[(a, true)], [(c, true)]]);
+
+    // only tautologies
+    assert!(simplified(&[&[(a, true), (a, false)], &[(b, true), (c, true), (b, false), (b, true)]]).is_empty());
+
+    // (a | b) & (a | -b) & (-a | c) & (-a | -c) & (b | c) resolves to a and -a
+    let unsat: &[&[(VariableId, bool)]] = &[&[(a, true), (b, true)], &[(a, true), (b, false)], &[(a, false), (c, true)], &[(a, false), (c, false)], &[(b, true), (c, true)]];
+    assert_eq!(simplified(unsat), [Vec::<(VariableId, bool)>::new()]);
+}
+
+#[test]
+fn test_to_expression() {
+    let (a, b) = (Literal::new(0, true), Literal::new(1, false));
+
+    assert_eq!(CNF::new(vec![]).to_expression(), Expression::Constant(true));
+    assert_eq!(CNF::new(vec![Clause::new(vec![])]).to_expression(), Expression::Constant(false));
+    assert_eq!(CNF::new(vec![Clause::new(vec![a])]).to_expression(), Expression::Variable(0));
+    assert_eq!(
+        Expression::from(CNF::new(vec![Clause::new(vec![a, b]), Clause::new(vec![b])])),
+        Expression::And(Box::new(Expression::Or(Box::new(a.into()), Box::new(b.into()))), Box::new(b.into())),
+    );
+}
+
+#[cfg(test)]
+pub(crate) fn random_expression(rng: &mut StdRng, depth: usize) -> Expression {
+    let child = |rng: &mut StdRng| Box::new(random_expression(rng, depth - 1));
+
+    match rng.gen_range(0..if depth == 0 { 2 } else { 8 }) {
+        0 => Expression::Variable(rng.gen_range(0..4)),
+        1 => Expression::Constant(rng.gen()),
+        2 => Expression::Not(child(rng)),
+        3 => Expression::And(child(rng), child(rng)),
+        4 => Expression::Or(child(rng), child(rng)),
+        5 => Expression::Xor(child(rng), child(rng)),
+        6 => Expression::Implies(child(rng), child(rng)),
+        _ => Expression::Iff(child(rng), child(rng)),
+    }
+}
+
+#[test]
+fn test_cnf_roundtrip() {
+    let mut rng = StdRng::seed_from_u64(0);
+
+    for _ in 0..200 {
+        let expression = random_expression(&mut rng, 4);
+
+        assert!(CNF::from(expression.clone()).to_expression().equivalent(&expression, 4), "{:?}", expression);
+        assert!(DNF::from(expression.clone()).to_expression().equivalent(&expression, 4), "{:?}", expression);
+    }
+
+    // constants don't turn into empty clauses
+    assert!(CNF::from(Expression::Constant(true)).clauses.is_empty());
+    assert!(DNF::from(Expression::Constant(false)).clauses.is_empty());
+    let instance = SATInstance::new(Expression::Constant(true), HashMap::from([(0, "a".to_string())]));
+    assert!(matches!(solve_dpll(instance, Assignment::default()), SolverResult::Sat(_)));
+}
+
+#[test]
+fn test_normal_forms_preserve_satisfiability() {
+    let mut rng = StdRng::seed_from_u64(1);
+
+    for _ in 0..200 {
+        let expression = random_expression(&mut rng, 5);
+        let instance = SATInstance::new(expression.clone(), (0..4).map(|var| (var, format!("v{}", var))).collect());
+        let satisfiable = |instance: &SATInstance| matches!(solve_bruteforce(instance), Ok(SolverResult::Sat(_)));
+        let expected = satisfiable(&instance);
+
+        for converted in [CNF::from(expression.clone()).to_expression(), DNF::from(expression.clone()).to_expression()] {
+            assert_eq!(satisfiable(&SATInstance::new(converted, instance.var_to_str.clone())), expected, "{:?}", expression);
+        }
+        // Tseitin only preserves satisfiability, the auxiliary variables come on top
+        assert_eq!(satisfiable(&instance.to_tseitin()), expected, "{:?}", expression);
+    }
+}
+
+#[test]
+fn test_is_horn() {
+    let clause = |literals: &[(VariableId, bool)]| Clause::new(literals.iter().map(|(var, value)| Literal::new(*var, *value)).collect());
+
+    assert!(CNF::new(vec![]).is_horn());
+    assert!(CNF::new(vec![clause(&[(0, true), (1, false), (2, false)]), clause(&[(1, false)]), clause(&[])]).is_horn());
+    assert!(!CNF::new(vec![clause(&[(0, false)]), clause(&[(0, true), (1, true)])]).is_horn());
+}
+
+#[test]
+fn test_is_2sat() {
+    let clause = |literals: &[(VariableId, bool)]| Clause::new(literals.iter().map(|(var, value)| Literal::new(*var, *value)).collect());
+
+    assert!(CNF::new(vec![]).is_2sat());
+    assert!(CNF::new(vec![clause(&[(0, true), (1, true)]), clause(&[(1, false)]), clause(&[])]).is_2sat());
+    assert!(!CNF::new(vec![clause(&[(0, false), (1, true), (2, true)])]).is_2sat());
+}
+
+#[test]
+fn test_is_clausal() {
+    for formula in ["a", "-a & 1", "(a | -b | c) & -c & (b | 0)", "a | b"] {
+        assert!(parse_str(formula).unwrap().expression.is_clausal(), "{}", formula);
+    }
+    for formula in ["a ^ b", "--a", "(a & b) | c", "-(a | b)", "a -> b"] {
+        assert!(!parse_str(formula).unwrap().expression.is_clausal(), "{}", formula);
+    }
+
+    let random = random_ksat_cnf(20, 50, 3, 0).to_expression();
+    assert!(random.is_clausal());
+}
+
+#[test]
+fn test_from_expression_simplifies() {
+    let clauses = |formula: &str| {
+        let instance = parse_str(formula).unwrap();
+        let name = |literal: &Literal| format!("{}{}", if literal.value { "" } else { "-" }, instance.var_to_str[&literal.var_id]);
+        let mut clauses = CNF::from(instance.expression.clone()).clauses.iter()
+            .map(|clause| clause.literals.iter().map(name).collect::<Vec<_>>().join(" | "))
+            .collect::<Vec<_>>();
+        clauses.sort();
+        clauses
+    };
+
+    assert_eq!(clauses("(a | -a | b) & c"), ["c"]);
+    assert_eq!(clauses("(a | b | a) & (b | -c)"), ["a | b", "b | -c"]);
+    assert_eq!(clauses("a & (b | a | c) & (c | -a) & (c | -a)"), ["-a | c", "a"]);
+    // only self-subsuming resolution is left to simplify
+    assert_eq!(clauses("(a | b) & (-a | b)"), ["-a | b", "a | b"]);
+}
+
+#[test]
+fn test_dimacs_round_trip() {
+    assert_eq!((Literal::new(0, true).to_dimacs(), Literal::new(4, false).to_dimacs()), (1, -5));
+    assert_eq!(Literal::from_dimacs(-5), Some(Literal::new(4, false)));
+    assert_eq!(Literal::from_dimacs(i64::from(VariableId::MAX) + 1), Some(Literal::new(VariableId::MAX, true)));
+    assert_eq!(Literal::from_dimacs(0), None);
+    assert_eq!(Literal::from_dimacs(i64::from(VariableId::MAX) + 2), None);
+
+    let cnf = random_ksat_cnf(20, 50, 3, 0);
+    let mut dimacs = Vec::new();
+    cnf.to_dimacs(&mut dimacs).unwrap();
+    assert_eq!(parse_dimacs_cnf(&String::from_utf8(dimacs).unwrap()).unwrap().clauses, cnf.clauses);
 }
@@ -0,0 +1,182 @@
+// Generators for benchmark instances. All randomized generators are seeded so instances are
+// reproducible, the random k-SAT ones can also draw from a given random number generator.
+
+use std::collections::{HashMap, HashSet};
+
+use rand::{rngs::StdRng, seq::{index, SliceRandom}, Rng, SeedableRng};
+
+use crate::{encodings::sudoku::SudokuGrid, expression::{expression::VariableId, normal::{Clause, Literal, CNF}}, solver::instance::SATInstance};
+
+/// Random k-SAT: `num_clauses` distinct clauses, each containing `k` distinct variables out of
+/// `num_vars` with random polarity.
+pub fn random_ksat_cnf(num_vars: usize, num_clauses: usize, k: usize, seed: u64) -> CNF {
+    random_ksat_cnf_with_rng(num_vars, num_clauses, k, &mut StdRng::seed_from_u64(seed))
+}
+
+/// Like [random_ksat_cnf], drawing from `rng` instead of a seeded generator.
+pub fn random_ksat_cnf_with_rng(num_vars: usize, num_clauses: usize, k: usize, rng: &mut impl Rng) -> CNF {
+    assert!(k > 0 && k <= num_vars, "k has to be in 1..=num_vars");
+    assert!(VariableId::try_from(num_vars).is_ok(), "Too many variables");
+
+    assert!(num_clauses as u128 <= max_ksat_clauses(num_vars, k), "Not enough distinct clauses");
+
+    let mut seen = HashSet::new();
+    let mut clauses = Vec::with_capacity(num_clauses);
+
+    while clauses.len() < num_clauses {
+        let mut literals = index::sample(rng, num_vars, k)
+            .into_iter()
+            .map(|var| Literal::new(var as VariableId, rng.gen()))
+            .collect::<Vec<_>>();
+        literals.sort();
+
+        let clause = Clause::new(literals);
+        if seen.insert(clause.clone()) {
+            clauses.push(clause);
+        }
+    }
+
+    CNF::new(clauses)
+}
+
+/// Number of distinct clauses with `k` distinct variables out of `num_vars` (saturating).
+pub fn max_ksat_clauses(num_vars: usize, k: usize) -> u128 {
+    if k > num_vars {
+        return 0;
+    }
+
+    let choices = (0..k).fold(1u128, |acc, i| acc.saturating_mul((num_vars - i) as u128) / (i as u128 + 1));
+    choices.saturating_mul(1u128.checked_shl(k as u32).unwrap_or(u128::MAX))
+}
+
+/// Random k-SAT instance with variables named `x0`, `x1`, ...
+pub fn random_ksat(num_vars: usize, num_clauses: usize, k: usize, seed: u64) -> SATInstance {
+    random_ksat_with_rng(num_vars, num_clauses, k, &mut StdRng::seed_from_u64(seed))
+}
+
+/// Like [random_ksat], drawing from `rng` instead of a seeded generator.
+pub fn random_ksat_with_rng(num_vars: usize, num_clauses: usize, k: usize, rng: &mut impl Rng) -> SATInstance {
+    let cnf = random_ksat_cnf_with_rng(num_vars, num_clauses, k, rng);
+    instance_from_cnf(cnf, num_vars, |var| format!("x{}", var))
+}
+
+/// Pigeonhole principle: `holes + 1` pigeons don't fit into `holes` holes. Unsatisfiable, but hard
+/// for resolution based solvers.
+pub fn pigeonhole_cnf(holes: usize) -> CNF {
+    let pigeons = holes + 1;
+    let var = |pigeon: usize, hole: usize| VariableId::try_from(pigeon * holes + hole).expect("Too many variables");
+
+    let mut clauses = Vec::new();
+
+    // every pigeon sits in a hole
+    for pigeon in 0..pigeons {
+        clauses.push(Clause::new((0..holes).map(|hole| Literal::new(var(pigeon, hole), true)).collect()));
+    }
+
+    // no two pigeons share a hole
+    for hole in 0..holes {
+        for first in 0..pigeons {
+            for second in first + 1..pigeons {
+                clauses.push(Clause::new(vec![Literal::new(var(first, hole), false), Literal::new(var(second, hole), false)]));
+            }
+        }
+    }
+
+    CNF::new(clauses)
+}
+
+/// Pigeonhole instance with variables named `p<pigeon>h<hole>`.
+pub fn pigeonhole(holes: usize) -> SATInstance {
+    let cnf = pigeonhole_cnf(holes);
+    instance_from_cnf(cnf, (holes + 1) * holes, |var| format!("p{}h{}", var / holes.max(1), var % holes.max(1)))
+}
+
+/// A random solved sudoku with `blank` cells removed.
+pub fn sudoku_puzzle(blank: usize, seed: u64) -> SudokuGrid {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    // shuffle digits, rows within bands, bands, columns within stacks and stacks of a valid grid
+    let mut digits = (1..=9).collect::<Vec<u8>>();
+    digits.shuffle(&mut rng);
+    let rows = shuffled_lines(&mut rng);
+    let cols = shuffled_lines(&mut rng);
+
+    let mut grid = [[None; 9]; 9];
+    for (row, values) in grid.iter_mut().enumerate() {
+        for (col, value) in values.iter_mut().enumerate() {
+            let (r, c) = (rows[row], cols[col]);
+            *value = Some(digits[(r % 3 * 3 + r / 3 + c) % 9]);
+        }
+    }
+
+    for cell in index::sample(&mut rng, 81, blank.min(81)) {
+        grid[cell / 9][cell % 9] = None;
+    }
+
+    grid
+}
+
+// permutation of 0..9 that keeps lines of the same band (or stack) together
+fn shuffled_lines(rng: &mut StdRng) -> Vec<usize> {
+    let mut bands = [0, 1, 2];
+    bands.shuffle(rng);
+
+    bands.iter().flat_map(|band| {
+        let mut lines = [0, 1, 2];
+        lines.shuffle(rng);
+        lines.map(|line| band * 3 + line)
+    }).collect()
+}
+
+fn instance_from_cnf(cnf: CNF, num_vars: usize, name: impl Fn(usize) -> String) -> SATInstance {
+    let var_to_str = (0..num_vars).map(|var| (var as VariableId, name(var))).collect::<HashMap<_, _>>();
+    SATInstance::new(cnf.to_expression(), var_to_str)
+}
+
+#[cfg(test)]
+use crate::{expression::expression::Assignment, solver::{cdcl::solve_cdcl, dpll::solve_dpll, instance::SolverResult}};
+
+#[test]
+fn test_random_ksat() {
+    let cnf = random_ksat_cnf(10, 40, 3, 7);
+    assert_eq!(cnf.clauses, random_ksat_cnf(10, 40, 3, 7).clauses);
+    assert_ne!(cnf.clauses, random_ksat_cnf(10, 40, 3, 8).clauses);
+
+    assert_eq!(cnf.clauses.len(), 40);
+    assert_eq!(cnf.clauses.iter().collect::<HashSet<_>>().len(), 40);
+    for clause in &cnf.clauses {
+        assert_eq!(clause.literals.iter().map(|literal| literal.var_id).collect::<HashSet<_>>().len(), 3);
+        assert!(clause.literals.iter().all(|literal| literal.var_id < 10));
+    }
+
+    let instance = random_ksat(10, 40, 3, 7);
+    assert_eq!(instance.var_to_str.len(), 10);
+    assert_eq!(instance.str_to_var["x3"], 3);
+}
+
+#[test]
+fn test_random_ksat_with_rng() {
+    // one generator for several instances gives different ones, seeding it the same way the same
+    let mut rng = StdRng::seed_from_u64(7);
+    let first = random_ksat_cnf_with_rng(10, 40, 3, &mut rng);
+    assert_ne!(first.clauses, random_ksat_cnf_with_rng(10, 40, 3, &mut rng).clauses);
+    assert_eq!(first.clauses, random_ksat_cnf(10, 40, 3, 7).clauses);
+
+    // at the phase transition of about 4.27 clauses per variable
+    let instance = random_ksat_with_rng(20, 85, 3, &mut rng);
+    assert_eq!(instance.var_to_str.len(), 20);
+    assert!(instance.var_to_str.values().all(|name| name.starts_with('x')));
+}
+
+#[test]
+fn test_random_ksat_models() {
+    // around the satisfiability threshold, so both answers come up
+    for seed in 0..100 {
+        let instance = random_ksat(20, 85, 3, seed);
+
+        match solve_dpll(instance.clone(), Assignment::default()) {
+            SolverResult::Sat(Some(model)) => assert_eq!(instance.check(&model), Ok(()), "seed {}", seed),
+            result => assert!(matches!(solve_cdcl(instance, Assignment::default()), SolverResult::Unsat), "seed {}: {:?}", seed, result),
+        }
+    }
+}
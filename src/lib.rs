@@ -0,0 +1,26 @@
+//! A SAT solver for propositional formulas in a small expression language or DIMACS CNF.
+//!
+//! The types and functions needed to parse and solve a formula are re-exported here, the modules
+//! contain the rest: other solvers, encodings, generators and puzzles.
+
+pub mod parser;
+pub mod solver;
+pub mod expression;
+pub mod puzzles;
+pub mod encodings;
+pub mod generator;
+pub mod color;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "python")]
+pub mod python;
+
+pub use expression::{expression::{Assignment, Expression, VariableId}, normal::{Clause, Literal, CNF}};
+pub use parser::{parse_dimacs, parse_dimacs_str, parse_expression, parse_file, parse_str, DimacsParseError, ParseError};
+pub use solver::instance::{SATInstance, SolverResult};
+
+/// Solve `instance` with DPLL and the default configuration, leaving auxiliary variables out of
+/// the model. See [solver::dpll::solve_dpll_with_config] for more options.
+pub fn solve(instance: &SATInstance) -> SolverResult {
+    solver::dpll::solve_dpll(instance.clone(), Assignment::default())
+}
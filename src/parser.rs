@@ -1,11 +1,37 @@
-use std::{collections::HashMap, error::Error, fs, path::Path};
+use std::{collections::HashMap, error::Error, fmt::Display, fs, ops::Range, path::{Path, PathBuf}};
 
-use chumsky::{container::Seq, error::Simple, pratt::{infix, prefix, right}, primitive::{choice, just}, recursive::recursive, text, Parser};
+use chumsky::{error::{Rich, RichPattern}, extra, primitive::{choice, just}, recursive::recursive, text, IterParser, Parser};
 
-use crate::{expression::expression::{Expression, VariableId}, solver::instance::SATInstance};
+use crate::{expression::{expression::{balanced_tree, Expression, VariableId}, normal::{Clause, Literal, CNF}}, solver::instance::SATInstance};
 
 // pub type ParseResult<T = ()> = Result<T, Simple<char>>;
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DimacsParseError {
+    MissingHeader,
+    InvalidHeader { line: usize },
+    InvalidLiteral { line: usize, token: String },
+    VariableOutOfRange { line: usize, literal: i64, num_vars: usize },
+    UnterminatedClause,
+    ClauseCountMismatch { declared: usize, found: usize },
+    /// The file couldn't be read
+    Io { path: PathBuf, message: String },
+}
+
+/// Why [parse_str] or [parse_file] failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The content isn't an expression in the infix syntax, `found` at `byte_offset` is none of the
+    /// `expected` tokens.
+    UnexpectedToken { byte_offset: usize, found: char, expected: Vec<String> },
+    /// The formula ends at `byte_offset` before it is complete
+    UnexpectedEof { byte_offset: usize },
+    /// There is nothing but whitespace
+    Empty,
+    /// The file couldn't be read
+    Io { path: PathBuf, message: String },
+}
+
 // arbitrary expressions
 #[derive(Debug, Clone)]
 pub enum ParsedExpression {
@@ -14,6 +40,9 @@ pub enum ParsedExpression {
     And(Box<ParsedExpression>, Box<ParsedExpression>),
     Or(Box<ParsedExpression>, Box<ParsedExpression>),
     Not(Box<ParsedExpression>),
+    Xor(Box<ParsedExpression>, Box<ParsedExpression>),
+    Implies(Box<ParsedExpression>, Box<ParsedExpression>),
+    Iff(Box<ParsedExpression>, Box<ParsedExpression>),
 }
 
 impl ParsedExpression {
@@ -45,10 +74,89 @@ impl ParsedExpression {
                 let interned = expr.intern_to_expression(interned_variables);
                 Expression::Not(Box::new(interned))
             },
+            ParsedExpression::Xor(lhs, rhs) => {
+                let expr_lhs = lhs.intern_to_expression(interned_variables);
+                let expr_rhs = rhs.intern_to_expression(interned_variables);
+                Expression::Xor(Box::new(expr_lhs), Box::new(expr_rhs))
+            },
+            ParsedExpression::Implies(lhs, rhs) => {
+                let expr_lhs = lhs.intern_to_expression(interned_variables);
+                let expr_rhs = rhs.intern_to_expression(interned_variables);
+                Expression::Implies(Box::new(expr_lhs), Box::new(expr_rhs))
+            },
+            ParsedExpression::Iff(lhs, rhs) => {
+                let expr_lhs = lhs.intern_to_expression(interned_variables);
+                let expr_rhs = rhs.intern_to_expression(interned_variables);
+                Expression::Iff(Box::new(expr_lhs), Box::new(expr_rhs))
+            },
         }
     }
 }
 
+/// Build a [ParsedExpression] at compile time, with the operators and precedence of the runtime
+/// parser: `-`, `&`, `^`, `|`, `->` (grouping to the right) and `<->`, from strongest to weakest
+/// binding. Identifiers are variables, `0` and `1` constants.
+///
+/// ```
+/// use sat_solver::{prop_expr, solver::instance::SATInstance};
+///
+/// let instance = SATInstance::from(prop_expr!((a & (b | c)) -> -d <-> 1));
+/// assert_eq!(instance.var_to_str.len(), 4);
+/// ```
+///
+/// Malformed input is a compile error naming the offending tokens:
+///
+/// ```compile_fail
+/// // expected a variable, 0, 1 or an expression in parentheses, found ``
+/// let expression = sat_solver::prop_expr!(a & & b);
+/// ```
+#[macro_export]
+macro_rules! prop_expr {
+    // every level splits at the first operator of its own outside of parentheses, parenthesized
+    // groups are a single token tree
+    (@iff [$($lhs:tt)*] <-> $($rest:tt)*) => {
+        $crate::parser::ParsedExpression::Iff(Box::new($crate::prop_expr!(@implies [] $($lhs)*)), Box::new($crate::prop_expr!(@iff [] $($rest)*)))
+    };
+    (@iff [$($lhs:tt)*] $next:tt $($rest:tt)*) => { $crate::prop_expr!(@iff [$($lhs)* $next] $($rest)*) };
+    (@iff [$($lhs:tt)*]) => { $crate::prop_expr!(@implies [] $($lhs)*) };
+
+    (@implies [$($lhs:tt)*] -> $($rest:tt)*) => {
+        $crate::parser::ParsedExpression::Implies(Box::new($crate::prop_expr!(@or [] $($lhs)*)), Box::new($crate::prop_expr!(@implies [] $($rest)*)))
+    };
+    (@implies [$($lhs:tt)*] $next:tt $($rest:tt)*) => { $crate::prop_expr!(@implies [$($lhs)* $next] $($rest)*) };
+    (@implies [$($lhs:tt)*]) => { $crate::prop_expr!(@or [] $($lhs)*) };
+
+    (@or [$($lhs:tt)*] | $($rest:tt)*) => {
+        $crate::parser::ParsedExpression::Or(Box::new($crate::prop_expr!(@xor [] $($lhs)*)), Box::new($crate::prop_expr!(@or [] $($rest)*)))
+    };
+    (@or [$($lhs:tt)*] $next:tt $($rest:tt)*) => { $crate::prop_expr!(@or [$($lhs)* $next] $($rest)*) };
+    (@or [$($lhs:tt)*]) => { $crate::prop_expr!(@xor [] $($lhs)*) };
+
+    (@xor [$($lhs:tt)*] ^ $($rest:tt)*) => {
+        $crate::parser::ParsedExpression::Xor(Box::new($crate::prop_expr!(@and [] $($lhs)*)), Box::new($crate::prop_expr!(@xor [] $($rest)*)))
+    };
+    (@xor [$($lhs:tt)*] $next:tt $($rest:tt)*) => { $crate::prop_expr!(@xor [$($lhs)* $next] $($rest)*) };
+    (@xor [$($lhs:tt)*]) => { $crate::prop_expr!(@and [] $($lhs)*) };
+
+    (@and [$($lhs:tt)*] & $($rest:tt)*) => {
+        $crate::parser::ParsedExpression::And(Box::new($crate::prop_expr!(@not $($lhs)*)), Box::new($crate::prop_expr!(@and [] $($rest)*)))
+    };
+    (@and [$($lhs:tt)*] $next:tt $($rest:tt)*) => { $crate::prop_expr!(@and [$($lhs)* $next] $($rest)*) };
+    (@and [$($lhs:tt)*]) => { $crate::prop_expr!(@not $($lhs)*) };
+
+    (@not - $($rest:tt)+) => { $crate::parser::ParsedExpression::Not(Box::new($crate::prop_expr!(@not $($rest)+))) };
+    (@not 0) => { $crate::parser::ParsedExpression::Constant(false) };
+    (@not 1) => { $crate::parser::ParsedExpression::Constant(true) };
+    (@not $name:ident) => { $crate::parser::ParsedExpression::Variable(stringify!($name).to_string()) };
+    (@not ($($inner:tt)+)) => { $crate::prop_expr!($($inner)+) };
+    (@not $($other:tt)*) => {
+        compile_error!(concat!("expected a variable, 0, 1 or an expression in parentheses, found `", stringify!($($other)*), "`"))
+    };
+
+    () => { compile_error!("expected an expression") };
+    ($($tokens:tt)+) => { $crate::prop_expr!(@iff [] $($tokens)+) };
+}
+
 impl From<ParsedExpression> for SATInstance {
     fn from(value: ParsedExpression) -> Self {
         let mut interned_variables = HashMap::new();
@@ -57,9 +165,9 @@ impl From<ParsedExpression> for SATInstance {
     }
 }
 
-fn parser<'a>() -> impl Parser<'a, &'a str, ParsedExpression> {
+fn parser<'a>() -> impl Parser<'a, &'a str, ParsedExpression, extra::Err<Rich<'a, char>>> {
     recursive(|expr| {
-        let variable = text::ascii::ident().map(|s: &str| ParsedExpression::Variable(s.to_string()));
+        let variable = text::ascii::ident().map(|s: &str| ParsedExpression::Variable(s.to_string())).labelled("identifier");
         let constant = choice((
                 just('0').to(ParsedExpression::Constant(false)),
                 just('1').to(ParsedExpression::Constant(true)),
@@ -72,22 +180,450 @@ fn parser<'a>() -> impl Parser<'a, &'a str, ParsedExpression> {
 
         let atom = literal.or(expr.delimited_by(just('('), just(')'))).padded();
 
+        // operator chains are parsed iteratively and combined into balanced trees, so long
+        // formulas neither overflow the parser's nor later passes' stacks
+        // from strongest to weakest binding: '-', '&', '^', '|', '->', '<->'
         let op = |c| just(c).padded();
-        atom.pratt((
-                prefix(10, op('-'), |expr| ParsedExpression::Not(Box::new(expr))),
-                infix(right(5), op('&'), |lhs, rhs| ParsedExpression::And(Box::new(lhs), Box::new(rhs))),
-                infix(right(2), op('|'), |lhs, rhs| ParsedExpression::Or(Box::new(lhs), Box::new(rhs))),
-        ))
+        let not = op('-').repeated().foldr(atom, |_, expr| ParsedExpression::Not(Box::new(expr)));
+        let and = not.separated_by(op('&')).at_least(1).collect::<Vec<_>>()
+            .map(|operands| balanced_tree(operands, |lhs, rhs| ParsedExpression::And(Box::new(lhs), Box::new(rhs))).expect("At least one operand"));
+        let xor = and.separated_by(op('^')).at_least(1).collect::<Vec<_>>()
+            .map(|operands| balanced_tree(operands, |lhs, rhs| ParsedExpression::Xor(Box::new(lhs), Box::new(rhs))).expect("At least one operand"));
+        let or = xor.separated_by(op('|')).at_least(1).collect::<Vec<_>>()
+            .map(|operands| balanced_tree(operands, |lhs, rhs| ParsedExpression::Or(Box::new(lhs), Box::new(rhs))).expect("At least one operand"));
+        // '->' isn't associative and groups to the right
+        let implies = or.separated_by(just("->").labelled("'->'").padded()).at_least(1).collect::<Vec<_>>()
+            .map(|operands| operands.into_iter().rev().reduce(|rhs, lhs| ParsedExpression::Implies(Box::new(lhs), Box::new(rhs))).expect("At least one operand"));
+        implies.separated_by(just("<->").labelled("'<->'").padded()).at_least(1).collect::<Vec<_>>()
+            .map(|operands| balanced_tree(operands, |lhs, rhs| ParsedExpression::Iff(Box::new(lhs), Box::new(rhs))).expect("At least one operand"))
     })
 }
 
-pub fn parse_file(file: &Path) -> SATInstance {
-    let content = fs::read_to_string(file).unwrap();
+/// Parse a single expression in the infix syntax, e.g. `(a | -b) & c`.
+pub fn parse_expression(input: &str) -> Result<ParsedExpression, ParseError> {
+    if input.trim().is_empty() {
+        return Err(ParseError::Empty);
+    }
+
+    parser().parse(input).into_result().map_err(|errors| {
+        // the parser stops at the first error anyway
+        let error = errors.into_iter().next().expect("Failed parses have an error");
+        let mut expected = Vec::new();
+        for pattern in error.expected() {
+            let pattern = match pattern {
+                RichPattern::EndOfInput => "end of formula".to_string(),
+                pattern => pattern.to_string(),
+            };
+            if !expected.contains(&pattern) {
+                expected.push(pattern);
+            }
+        }
+
+        let byte_offset = error.span().start;
+        match error.found() {
+            Some(found) => ParseError::UnexpectedToken { byte_offset, found: *found, expected },
+            None => ParseError::UnexpectedEof { byte_offset },
+        }
+    })
+}
+
+/// Parse a whole file in the infix syntax. It contains formulas separated by newlines or `;`, which
+/// are conjoined, and `#` starts a comment until the end of the line. Inside parentheses, a formula
+/// can span several lines. Without any formulas, the instance is constant true.
+pub fn parse_str(input: &str) -> Result<SATInstance, ParseError> {
+    let (content, formulas) = split_formulas(input);
+
+    let expressions = formulas.into_iter()
+        .map(|range| parse_expression(&content[range.clone()]).map_err(|err| err.offset(range.start)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let expression = balanced_tree(expressions, |lhs, rhs| ParsedExpression::And(Box::new(lhs), Box::new(rhs)));
+
+    Ok(SATInstance::from(expression.unwrap_or(ParsedExpression::Constant(true))))
+}
+
+// `input` with the comments blanked out, so byte offsets stay the same, and the byte ranges of the
+// formulas in it that aren't empty
+fn split_formulas(input: &str) -> (String, Vec<Range<usize>>) {
+    let mut content = String::with_capacity(input.len());
+    let mut in_comment = false;
+    for c in input.chars() {
+        in_comment = (in_comment || c == '#') && c != '\n';
+        match in_comment {
+            true => content.extend(std::iter::repeat_n(' ', c.len_utf8())),
+            false => content.push(c),
+        }
+    }
+
+    let mut formulas = Vec::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    for (index, c) in content.char_indices() {
+        match c {
+            '(' => depth += 1,
+            // unbalanced parentheses are left to the parser
+            ')' => depth = depth.saturating_sub(1),
+            '\n' | ';' if depth == 0 => {
+                if !content[start..index].trim().is_empty() {
+                    formulas.push(start..index);
+                }
+                start = index + 1;
+            },
+            _ => {},
+        }
+    }
+    if !content[start..].trim().is_empty() {
+        formulas.push(start..content.len());
+    }
+
+    (content, formulas)
+}
+
+/// Parse a formula in DIMACS CNF format. Variable `n` is interned as the name `"n"`.
+///
+/// The header has to match the formula, see [parse_dimacs_str_lenient] for files that miscount.
+/// A line starting with `%` ends the formula, like in the SATLIB benchmarks.
+pub fn parse_dimacs_str(input: &str) -> Result<SATInstance, DimacsParseError> {
+    parse_dimacs_with(input, false).map(instance_from_clauses)
+}
+
+/// Like [parse_dimacs_str], but ignores the number of clauses in the header and adds variables
+/// exceeding the declared number.
+pub fn parse_dimacs_str_lenient(input: &str) -> Result<SATInstance, DimacsParseError> {
+    parse_dimacs_with(input, true).map(instance_from_clauses)
+}
+
+/// Read a DIMACS CNF file, see [parse_dimacs_str].
+pub fn parse_dimacs(path: &Path) -> Result<SATInstance, DimacsParseError> {
+    let content = fs::read_to_string(path).map_err(|err| DimacsParseError::Io { path: path.to_path_buf(), message: err.to_string() })?;
+
+    parse_dimacs_str(&content)
+}
+
+/// Parse a formula in DIMACS CNF format straight into clauses, without building an expression.
+/// Variable `n` gets the id `n - 1`.
+pub fn parse_dimacs_cnf(input: &str) -> Result<CNF, DimacsParseError> {
+    parse_dimacs_with(input, false).map(|(_, clauses)| CNF::new(clauses))
+}
+
+// the number of variables and the clauses
+fn parse_dimacs_with(input: &str, lenient: bool) -> Result<(usize, Vec<Clause>), DimacsParseError> {
+    let mut header = None;
+    let mut clauses = Vec::new();
+    let mut current_clause = Vec::new();
+
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+
+        if line.starts_with('%') {
+            break;
+        }
+
+        if line.is_empty() || line.starts_with('c') {
+            continue;
+        }
+
+        if line.starts_with('p') {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            match fields.as_slice() {
+                ["p", "cnf", vars, num_clauses] if header.is_none() => {
+                    let vars = vars.parse::<usize>().map_err(|_| DimacsParseError::InvalidHeader { line: line_number })?;
+                    let num_clauses = num_clauses.parse::<usize>().map_err(|_| DimacsParseError::InvalidHeader { line: line_number })?;
+                    if VariableId::try_from(vars).is_err() {
+                        return Err(DimacsParseError::InvalidHeader { line: line_number });
+                    }
+                    header = Some((vars, num_clauses));
+                },
+                _ => return Err(DimacsParseError::InvalidHeader { line: line_number }),
+            }
+            continue;
+        }
+
+        let (num_vars, _) = header.as_mut().ok_or(DimacsParseError::MissingHeader)?;
+
+        for token in line.split_whitespace() {
+            let literal = token.parse::<i64>()
+                .ok()
+                // only a plain `0` terminates a clause, `-0` or `00` are most likely typos
+                .filter(|literal| *literal != 0 || token == "0")
+                .ok_or_else(|| DimacsParseError::InvalidLiteral { line: line_number, token: token.to_string() })?;
+            let var = literal.unsigned_abs() as usize;
+
+            if literal == 0 {
+                clauses.push(Clause::new(std::mem::take(&mut current_clause)));
+                continue;
+            }
+            if var > *num_vars {
+                if !lenient || Literal::from_dimacs(literal).is_none() {
+                    return Err(DimacsParseError::VariableOutOfRange { line: line_number, literal, num_vars: *num_vars });
+                }
+                *num_vars = var;
+            }
+
+            current_clause.push(Literal::from_dimacs(literal).expect("Checked against header"));
+        }
+    }
+
+    let (num_vars, num_clauses) = header.ok_or(DimacsParseError::MissingHeader)?;
+    if !current_clause.is_empty() {
+        return Err(DimacsParseError::UnterminatedClause);
+    }
+    if !lenient && clauses.len() != num_clauses {
+        return Err(DimacsParseError::ClauseCountMismatch { declared: num_clauses, found: clauses.len() });
+    }
+
+    Ok((num_vars, clauses))
+}
+
+fn instance_from_clauses((num_vars, clauses): (usize, Vec<Clause>)) -> SATInstance {
+    let expression = Expression::conjunction(clauses.into_iter()
+        .map(|clause| Expression::disjunction(clause.literals.into_iter().map(Expression::from).collect()))
+        .collect());
+
+    let var_to_str = (0..num_vars).map(|var| (var as VariableId, (var + 1).to_string())).collect();
+    SATInstance::new(expression, var_to_str)
+}
+
+/// Read a formula in the infix syntax, see [parse_str].
+pub fn parse_file(path: &Path) -> Result<SATInstance, ParseError> {
+    let content = fs::read_to_string(path).map_err(|err| ParseError::Io { path: path.to_path_buf(), message: err.to_string() })?;
+
+    parse_str(&content)
+}
+
+impl ParseError {
+    // move the span of a syntax error in a formula starting at byte `start` of the input
+    fn offset(self, start: usize) -> Self {
+        match self {
+            ParseError::UnexpectedToken { byte_offset, found, expected } => ParseError::UnexpectedToken { byte_offset: byte_offset + start, found, expected },
+            ParseError::UnexpectedEof { byte_offset } => ParseError::UnexpectedEof { byte_offset: byte_offset + start },
+            err => err,
+        }
+    }
+
+    /// The error followed by the line of `input` it occurred in and a caret pointing at the
+    /// position, e.g.
+    ///
+    /// ```text
+    /// line 1: unexpected '&' at byte 4, expected '-', identifier, '0', '1', or '('
+    ///   a & & b
+    ///       ^
+    /// ```
+    pub fn report(&self, input: &str) -> String {
+        let (ParseError::UnexpectedToken { byte_offset, .. } | ParseError::UnexpectedEof { byte_offset }) = self else {
+            return self.to_string();
+        };
+
+        let line_start = input[..*byte_offset].rfind('\n').map_or(0, |index| index + 1);
+        let line_end = input[*byte_offset..].find('\n').map_or(input.len(), |index| byte_offset + index);
+        let line_number = input[..line_start].matches('\n').count() + 1;
+        let line = &input[line_start..line_end];
+        let column = input[line_start..*byte_offset].chars().count();
+
+        format!("line {}: {}\n  {}\n  {}^", line_number, self, line, " ".repeat(column))
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnexpectedToken { byte_offset, found, expected } => {
+                write!(f, "unexpected '{}' at byte {}", found, byte_offset)?;
+                match expected.as_slice() {
+                    [] => Ok(()),
+                    [only] => write!(f, ", expected {}", only),
+                    [first, second] => write!(f, ", expected {} or {}", first, second),
+                    [rest @ .., last] => write!(f, ", expected {}, or {}", rest.join(", "), last),
+                }
+            },
+            ParseError::UnexpectedEof { byte_offset } => write!(f, "unexpected end of formula at byte {}", byte_offset),
+            ParseError::Empty => write!(f, "no expression found"),
+            ParseError::Io { path, message } => write!(f, "couldn't read '{}': {}", path.display(), message),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+impl Display for DimacsParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DimacsParseError::MissingHeader => write!(f, "missing 'p cnf <variables> <clauses>' header"),
+            DimacsParseError::InvalidHeader { line } => write!(f, "line {}: invalid header, expected 'p cnf <variables> <clauses>'", line),
+            DimacsParseError::InvalidLiteral { line, token } => write!(f, "line {}: invalid literal '{}'", line, token),
+            DimacsParseError::VariableOutOfRange { line, literal, num_vars } => write!(f, "line {}: literal {} exceeds the declared {} variables", line, literal, num_vars),
+            DimacsParseError::UnterminatedClause => write!(f, "last clause isn't terminated by 0"),
+            DimacsParseError::ClauseCountMismatch { declared, found } => write!(f, "header declares {} clauses, but there are {}", declared, found),
+            DimacsParseError::Io { path, message } => write!(f, "couldn't read '{}': {}", path.display(), message),
+        }
+    }
+}
+
+impl Error for DimacsParseError {}
+
+#[cfg(test)]
+use crate::{expression::expression::Assignment, solver::{dpll::solve_dpll, enumerate::enumerate_models, instance::SolverResult}};
+
+#[test]
+fn test_parse_dimacs() {
+    let instance = parse_dimacs_str("c example\np cnf 3 2\n1 -3 0\n2 3\n-1 0\n").unwrap();
+    assert_eq!(instance.var_to_str.len(), 3);
+    assert_eq!(instance.str_to_var["3"], 2);
+
+    let clauses = crate::expression::normal::CNF::from(instance.expression).clauses;
+    assert_eq!(clauses.len(), 2);
+    assert!(clauses.iter().any(|clause| clause.literals.len() == 3));
+}
+
+#[test]
+fn test_parse_dimacs_errors() {
+    assert_eq!(parse_dimacs_str("1 2 0\n").unwrap_err(), DimacsParseError::MissingHeader);
+    assert_eq!(parse_dimacs_str("p cnf x 1\n").unwrap_err(), DimacsParseError::InvalidHeader { line: 1 });
+    assert_eq!(parse_dimacs_str("p cnf 2 1\n1 a 0\n").unwrap_err(), DimacsParseError::InvalidLiteral { line: 2, token: "a".to_string() });
+    assert_eq!(parse_dimacs_str("p cnf 2 1\n1 3 0\n").unwrap_err(), DimacsParseError::VariableOutOfRange { line: 2, literal: 3, num_vars: 2 });
+    assert_eq!(parse_dimacs_str("p cnf 2 1\n1 2\n").unwrap_err(), DimacsParseError::UnterminatedClause);
+    assert_eq!(parse_dimacs_str("p cnf 2 2\n1 2 0\n").unwrap_err(), DimacsParseError::ClauseCountMismatch { declared: 2, found: 1 });
+}
 
-    let parser = parser();
-    let parsed_expression = parser.parse(&content).into_result().unwrap();
+#[test]
+fn test_parse_dimacs_file() {
+    let instance = parse_dimacs(Path::new("tests/fixtures/simple.cnf")).unwrap();
+    assert_eq!(instance.var_to_str.len(), 2);
 
-    let mut interned_variables = HashMap::new();
-    let expression = parsed_expression.intern_to_expression(&mut interned_variables);
-    SATInstance::new(expression, interned_variables)
+    let cnf = parse_dimacs_cnf(&fs::read_to_string("tests/fixtures/simple.cnf").unwrap()).unwrap();
+    assert_eq!(cnf.clauses, [
+        Clause::new(vec![Literal::new(0, true), Literal::new(1, true)]),
+        Clause::new(vec![Literal::new(0, false)]),
+    ]);
+
+    let err = parse_dimacs(Path::new("tests/fixtures/missing.cnf")).unwrap_err();
+    assert!(matches!(err, DimacsParseError::Io { path, .. } if path == Path::new("tests/fixtures/missing.cnf")));
+}
+
+#[test]
+fn test_parse_dimacs_edge_cases() {
+    let instance = parse_dimacs_str("p cnf 2 2  \n1 -2 0   \n2 0\t\n").unwrap();
+    assert_eq!(instance.var_to_str.len(), 2);
+
+    assert_eq!(parse_dimacs_str("p cnf 2 1\n1 -0 2 0\n").unwrap_err(), DimacsParseError::InvalidLiteral { line: 2, token: "-0".to_string() });
+    assert_eq!(parse_dimacs_str("p cnf 2 1\n1 2 00\n").unwrap_err(), DimacsParseError::InvalidLiteral { line: 2, token: "00".to_string() });
+
+    // more variables than declared
+    assert_eq!(parse_dimacs_str("p cnf 1 1\n1 -2 0\n").unwrap_err(), DimacsParseError::VariableOutOfRange { line: 2, literal: -2, num_vars: 1 });
+    assert_eq!(parse_dimacs_str_lenient("p cnf 1 1\n1 -2 0\n").unwrap().str_to_var["2"], 1);
+}
+
+#[test]
+fn test_parse_file() {
+    let instance = parse_file(Path::new("tests/fixtures/simple.sat")).unwrap();
+    assert!(!instance.var_to_str.is_empty());
+
+    let invalid = parse_file(Path::new("tests/fixtures/invalid.sat")).unwrap_err();
+    assert!(matches!(invalid, ParseError::UnexpectedToken { byte_offset: 4, found: '&', .. }));
+    let missing = parse_file(Path::new("tests/fixtures/missing.sat")).unwrap_err();
+    assert!(matches!(&missing, ParseError::Io { path, .. } if path == Path::new("tests/fixtures/missing.sat")));
+    assert!(missing.to_string().starts_with("couldn't read 'tests/fixtures/missing.sat': "));
+}
+
+#[test]
+fn test_parse_error_spans() {
+    let position = |input| match parse_str(input).unwrap_err() {
+        ParseError::UnexpectedToken { byte_offset, found, .. } => (byte_offset, Some(found)),
+        ParseError::UnexpectedEof { byte_offset } => (byte_offset, None),
+        err => panic!("expected a syntax error, got {:?}", err),
+    };
+
+    assert_eq!(position("a & & b"), (4, Some('&')));
+    assert_eq!(position("(a | b"), (6, None));
+    assert_eq!(position("a b"), (2, Some('b')));
+    assert_eq!(position("a | (b & )"), (9, Some(')')));
+    assert_eq!(position("a ->\nb"), (4, None));
+    assert_eq!(position("a; b &; c"), (6, None));
+    assert_eq!(position("x # comment\ny z"), (14, Some('z')));
+
+    let expected = ["'-'", "identifier", "'0'", "'1'", "'('"].map(String::from).to_vec();
+    assert_eq!(parse_str("a & & b").unwrap_err(), ParseError::UnexpectedToken { byte_offset: 4, found: '&', expected });
+    assert_eq!(parse_str("a &").unwrap_err().to_string(), "unexpected end of formula at byte 3");
+
+    assert_eq!(parse_expression("").unwrap_err(), ParseError::Empty);
+    assert_eq!(parse_expression(" \n\t ").unwrap_err(), ParseError::Empty);
+}
+
+#[test]
+fn test_parse_error_report() {
+    let err = parse_str("a & 3").unwrap_err();
+    assert_eq!(err.to_string(), "unexpected '3' at byte 4, expected '-', identifier, '0', '1', or '('");
+
+    let input = "((a | b) &\n  (c | | d))";
+    assert_eq!(parse_str(input).unwrap_err().report(input), [
+        "line 2: unexpected '|' at byte 18, expected '-', identifier, '0', '1', or '('",
+        "    (c | | d))",
+        "         ^",
+    ].join("\n"));
+    assert_eq!(ParseError::Empty.report(""), "no expression found");
+}
+
+#[test]
+fn test_parse_multiple_formulas() {
+    let instance = parse_str("a | b; -a\n\n# comment ( with a parenthesis\n(b ->\n  c) # and another one\n").unwrap();
+    assert_eq!(instance.var_to_str.len(), 3);
+    let [a, b, c] = ["a", "b", "c"].map(|name| instance.str_to_var[name]);
+    assert_eq!(solve_dpll(instance, Assignment::default()), SolverResult::Sat(Some(Assignment::from([(a, false), (b, true), (c, true)]))));
+
+    // variables are shared between formulas
+    assert!(matches!(solve_dpll(parse_str("a\n-a").unwrap(), Assignment::default()), SolverResult::Unsat));
+
+    for empty in ["", " \n\t ", "# nothing\n\n  # at all", ";;\n;"] {
+        let instance = parse_str(empty).unwrap();
+        assert_eq!(instance.expression, Expression::Constant(true));
+        assert!(instance.var_to_str.is_empty());
+    }
+}
+
+#[test]
+fn test_parse_schedule() {
+    let instance = parse_file(Path::new("tests/fixtures/schedule.sat")).unwrap();
+    assert_eq!(instance.var_to_str.len(), 6);
+
+    let models = enumerate_models(instance.clone()).collect::<Vec<_>>();
+    assert_eq!(models.len(), 1);
+    for (name, value) in [("review_am", true), ("standup_am", true), ("planning_pm", true), ("planning_am", false)] {
+        assert_eq!(models[0].values[&instance.str_to_var[name]], value, "{}", name);
+    }
+}
+
+#[cfg(test)]
+fn assert_equivalent(lhs: ParsedExpression, rhs: ParsedExpression) {
+    // interned together, so the variables get the same ids
+    let instance = SATInstance::from(ParsedExpression::Iff(Box::new(lhs), Box::new(rhs)));
+    let Expression::Iff(lhs, rhs) = instance.expression else { unreachable!() };
+    let variables = instance.var_to_str.keys().copied().collect::<Vec<_>>();
+
+    for bits in 0..1u32 << variables.len() {
+        let assignment = Assignment::new(variables.iter().enumerate().map(|(index, var)| (*var, bits & (1 << index) != 0)).collect());
+        assert_eq!(lhs.clone().evaluate(&assignment), rhs.clone().evaluate(&assignment), "{:?}", assignment);
+    }
+}
+
+#[test]
+fn test_prop_expr() {
+    assert_equivalent(prop_expr!(a -> b), prop_expr!((-a) | b));
+    assert_equivalent(prop_expr!(a <-> b), prop_expr!((a -> b) & (b -> a)));
+    assert_equivalent(prop_expr!(a -> b -> c), prop_expr!(a -> (b -> c)));
+    assert!(matches!(prop_expr!(a -> b), ParsedExpression::Implies(..)));
+
+    for (macro_expression, input) in [
+        (prop_expr!((a & (b | c)) & (-d)), "(a & (b | c)) & (-d)"),
+        (prop_expr!(-a & b | c ^ d -> e <-> 1), "-a & b | c ^ d -> e <-> 1"),
+        (prop_expr!(a | - -b & 0 <-> (c -> d) -> e), "a | - -b & 0 <-> (c -> d) -> e"),
+    ] {
+        let parsed = parse_expression(input).unwrap();
+        assert_eq!(format!("{:?}", macro_expression), format!("{:?}", parsed), "{}", input);
+
+        // same variable ids as reading the formula from a file
+        let instance = SATInstance::from(macro_expression);
+        let from_file = parse_str(input).unwrap();
+        assert_eq!(instance.expression, from_file.expression, "{}", input);
+        assert_eq!(instance.var_to_str, from_file.var_to_str, "{}", input);
+    }
 }
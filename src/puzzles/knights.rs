@@ -0,0 +1,250 @@
+// Knights-and-knaves logic puzzles.
+//
+// Every inhabitant is a variable meaning "is a knight". Knights always tell the truth and knaves
+// always lie, so a statement `F` made by `A` is encoded as `A <-> F`.
+
+use std::fmt::Display;
+
+use crate::{expression::expression::Assignment, parser::{parse_expression, ParsedExpression}, solver::{enumerate::enumerate_models, instance::SATInstance}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Knight,
+    Knave,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KnightsParseError {
+    /// The line doesn't have the form `Name: <formula>`.
+    MissingSpeaker { line: usize },
+    /// The formula part of the line couldn't be parsed.
+    InvalidStatement { line: usize },
+}
+
+/// One role per inhabitant, in order of first appearance in the puzzle.
+pub type KnightsAssignment = Vec<(String, Role)>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KnightsSolution {
+    Unique(KnightsAssignment),
+    /// At least two different assignments are consistent with the statements.
+    Ambiguous(KnightsAssignment, KnightsAssignment),
+    /// No assignment is consistent with the statements.
+    Contradictory,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct KnightsPuzzle {
+    statements: Vec<(String, ParsedExpression)>,
+}
+
+impl KnightsPuzzle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the statement `speaker` makes.
+    pub fn says(mut self, speaker: &str, statement: ParsedExpression) -> Self {
+        self.statements.push((speaker.to_string(), statement));
+        self
+    }
+
+    /// Parse a puzzle consisting of one `Name: <formula>` statement per line. Blank lines are
+    /// ignored.
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// Zoey: -Mel
+    /// Mel: Zoey & Mel
+    /// ```
+    pub fn parse(input: &str) -> Result<Self, KnightsParseError> {
+        let mut puzzle = Self::new();
+
+        for (index, line) in input.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let line_number = index + 1;
+            let (speaker, statement) = line.split_once(':').ok_or(KnightsParseError::MissingSpeaker { line: line_number })?;
+
+            let speaker = speaker.trim();
+            if !is_name(speaker) {
+                return Err(KnightsParseError::MissingSpeaker { line: line_number });
+            }
+
+            let statement = parse_expression(statement).map_err(|_| KnightsParseError::InvalidStatement { line: line_number })?;
+            puzzle = puzzle.says(speaker, statement);
+        }
+
+        Ok(puzzle)
+    }
+
+    /// All inhabitants mentioned in the puzzle, in order of first appearance.
+    pub fn inhabitants(&self) -> Vec<String> {
+        let mut inhabitants = Vec::new();
+
+        for (speaker, statement) in &self.statements {
+            if !inhabitants.contains(speaker) {
+                inhabitants.push(speaker.clone());
+            }
+
+            collect_names(statement, &mut inhabitants);
+        }
+
+        inhabitants
+    }
+
+    /// Build the instance `(A <-> F_A) & (B <-> F_B) & ...` for all statements.
+    pub fn to_instance(&self) -> SATInstance {
+        let expression = self.statements.iter().fold(ParsedExpression::Constant(true), |acc, (speaker, statement)| {
+            let speaker = ParsedExpression::Variable(speaker.clone());
+            ParsedExpression::And(Box::new(acc), Box::new(ParsedExpression::Iff(Box::new(speaker), Box::new(statement.clone()))))
+        });
+
+        SATInstance::from(expression)
+    }
+
+    /// Solve the puzzle and check whether the solution is unique.
+    pub fn solve(&self) -> KnightsSolution {
+        let inhabitants = self.inhabitants();
+        let instance = self.to_instance();
+
+        let roles = |model: Assignment| -> KnightsAssignment {
+            inhabitants.iter()
+                .map(|name| {
+                    let is_knight = instance.str_to_var.get(name).and_then(|var| model.values.get(var)) == Some(&true);
+                    (name.clone(), if is_knight { Role::Knight } else { Role::Knave })
+                })
+                .collect()
+        };
+
+        // a second model means the puzzle is ambiguous
+        let mut solutions = enumerate_models(instance.clone()).take(2).map(roles);
+        match (solutions.next(), solutions.next()) {
+            (None, _) => KnightsSolution::Contradictory,
+            (Some(first), None) => KnightsSolution::Unique(first),
+            (Some(first), Some(second)) => KnightsSolution::Ambiguous(first, second),
+        }
+    }
+}
+
+fn collect_names(expression: &ParsedExpression, names: &mut Vec<String>) {
+    match expression {
+        ParsedExpression::Variable(name) => {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        },
+        ParsedExpression::Constant(_) => {},
+        ParsedExpression::And(lhs, rhs) | ParsedExpression::Or(lhs, rhs) | ParsedExpression::Xor(lhs, rhs) | ParsedExpression::Implies(lhs, rhs) | ParsedExpression::Iff(lhs, rhs) => {
+            collect_names(lhs, names);
+            collect_names(rhs, names);
+        },
+        ParsedExpression::Not(expr) => collect_names(expr, names),
+    }
+}
+
+fn is_name(s: &str) -> bool {
+    s.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Role::Knight => write!(f, "knight"),
+            Role::Knave => write!(f, "knave"),
+        }
+    }
+}
+
+impl Display for KnightsParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KnightsParseError::MissingSpeaker { line } => write!(f, "line {}: expected 'Name: <statement>'", line),
+            KnightsParseError::InvalidStatement { line } => write!(f, "line {}: invalid statement", line),
+        }
+    }
+}
+
+impl Display for KnightsSolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let write_roles = |f: &mut std::fmt::Formatter<'_>, roles: &KnightsAssignment| -> std::fmt::Result {
+            for (name, role) in roles {
+                writeln!(f, "{} is a {}", name, role)?;
+            }
+            Ok(())
+        };
+
+        match self {
+            KnightsSolution::Unique(roles) => write_roles(f, roles),
+            KnightsSolution::Ambiguous(first, second) => {
+                writeln!(f, "Ambiguous puzzle, multiple solutions exist, e.g.")?;
+                write_roles(f, first)?;
+                writeln!(f, "and")?;
+                write_roles(f, second)
+            },
+            KnightsSolution::Contradictory => writeln!(f, "Contradictory puzzle, no solution exists"),
+        }
+    }
+}
+
+#[test]
+fn test_both_knaves() {
+    // A: "We are both knaves."
+    let puzzle = KnightsPuzzle::parse("A: -A & -B").unwrap();
+
+    assert_eq!(puzzle.solve(), KnightsSolution::Unique(vec![
+        ("A".to_string(), Role::Knave),
+        ("B".to_string(), Role::Knight),
+    ]));
+}
+
+#[test]
+fn test_zoey_and_mel() {
+    // Zoey: "Mel is a knave." Mel: "Neither Zoey nor I are knaves."
+    let puzzle = KnightsPuzzle::parse("Zoey: -Mel\n\nMel: Zoey & Mel\n").unwrap();
+
+    let solution = puzzle.solve();
+    assert_eq!(solution, KnightsSolution::Unique(vec![
+        ("Zoey".to_string(), Role::Knight),
+        ("Mel".to_string(), Role::Knave),
+    ]));
+    assert_eq!(solution.to_string(), "Zoey is a knight\nMel is a knave\n");
+}
+
+#[test]
+fn test_ambiguous() {
+    // A: "B is a knight." B: "A is a knight."
+    let puzzle = KnightsPuzzle::new()
+        .says("A", ParsedExpression::Variable("B".to_string()))
+        .says("B", ParsedExpression::Variable("A".to_string()));
+
+    let KnightsSolution::Ambiguous(first, second) = puzzle.solve() else {
+        panic!("puzzle should be ambiguous");
+    };
+    assert_ne!(first, second);
+}
+
+#[test]
+fn test_contradictory() {
+    // A: "I am a knave."
+    let puzzle = KnightsPuzzle::parse("A: -A").unwrap();
+
+    assert_eq!(puzzle.solve(), KnightsSolution::Contradictory);
+}
+
+#[test]
+fn test_unmentioned_speaker() {
+    // A: "B is a knight or a knave." B says nothing, so B can be either
+    let puzzle = KnightsPuzzle::parse("A: B | -B").unwrap();
+    let KnightsSolution::Ambiguous(first, second) = puzzle.solve() else {
+        panic!("puzzle should be ambiguous");
+    };
+    assert!(first.iter().chain(&second).all(|(name, role)| name != "A" || *role == Role::Knight));
+
+    assert_eq!(KnightsPuzzle::new().solve(), KnightsSolution::Unique(Vec::new()));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(KnightsPuzzle::parse("A: B\nA B").unwrap_err(), KnightsParseError::MissingSpeaker { line: 2 });
+    assert_eq!(KnightsPuzzle::parse("A: B &").unwrap_err(), KnightsParseError::InvalidStatement { line: 1 });
+}
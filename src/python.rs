@@ -0,0 +1,138 @@
+// Python bindings, built as an extension module with maturin.
+
+use std::{collections::BTreeMap, sync::{atomic::{AtomicBool, Ordering}, mpsc::{self, RecvTimeoutError}, Arc, Mutex}, thread, time::Duration};
+
+use pyo3::{exceptions::{PyTimeoutError, PyValueError}, prelude::*};
+
+use crate::{expression::{expression::{Assignment, Expression, VariableId}, normal::CNF}, parser::{parse_dimacs_str, parse_str}, solver::{counting::count_models, dpll::solve_dpll_with_config, instance::{SATInstance, SolverConfig, SolverResult}}};
+
+// how often a running solve checks for KeyboardInterrupt
+const SIGNAL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A formula to solve. Integer literals use the DIMACS numbering of [Formula::cnf_dimacs], so
+/// `k` refers to the variable with id `k - 1` and `-k` to its negation.
+#[pyclass(module = "sat_solver")]
+pub struct Formula {
+    instance: SATInstance,
+}
+
+#[pymethods]
+impl Formula {
+    /// Parse a formula in the expression syntax, e.g. `(a | -b) & c`.
+    #[staticmethod]
+    fn parse(s: &str) -> PyResult<Self> {
+        parse_str(s)
+            .map(|instance| Self { instance })
+            .map_err(|err| PyValueError::new_err(err.report(s)))
+    }
+
+    #[staticmethod]
+    fn from_dimacs(text: &str) -> PyResult<Self> {
+        parse_dimacs_str(text)
+            .map(|instance| Self { instance })
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    /// Solve the formula, returning a dict mapping variable names to values, or None if it is
+    /// unsatisfiable. Raises TimeoutError if `timeout` seconds pass without an answer.
+    #[pyo3(signature = (assumptions = None, timeout = None))]
+    fn solve(&self, py: Python<'_>, assumptions: Option<Vec<i64>>, timeout: Option<f64>) -> PyResult<Option<BTreeMap<String, bool>>> {
+        let mut assignment = Assignment::default();
+        for literal in assumptions.unwrap_or_default() {
+            let var = self.variable(literal)?;
+            assignment.values.insert(var, literal > 0);
+        }
+
+        let timeout = timeout.map(Duration::try_from_secs_f64).transpose().map_err(|err| PyValueError::new_err(err.to_string()))?;
+        let cancel = Arc::new(AtomicBool::new(false));
+        let config = SolverConfig { timeout, cancel: Some(cancel.clone()), ..Default::default() };
+
+        // solve on another thread, so this one can check for KeyboardInterrupt in between
+        let instance = self.instance.clone();
+        let (sender, receiver) = mpsc::channel();
+        // waiting happens without the GIL, which needs a receiver that can be shared
+        let receiver = Mutex::new(receiver);
+        let worker = thread::spawn(move || sender.send(solve_dpll_with_config(instance, assignment, config)));
+
+        let result = loop {
+            match py.detach(|| receiver.lock().expect("Only this thread receives").recv_timeout(SIGNAL_INTERVAL)) {
+                Ok(result) => break result,
+                Err(RecvTimeoutError::Timeout) => {
+                    if let Err(err) = py.check_signals() {
+                        cancel.store(true, Ordering::Relaxed);
+                        let _ = py.detach(|| worker.join());
+                        return Err(err);
+                    }
+                },
+                Err(RecvTimeoutError::Disconnected) => panic!("The solver thread panicked"),
+            }
+        };
+
+        match result {
+            SolverResult::Sat(assignment) => Ok(Some(assignment.unwrap_or_default().values.iter().map(|(var, value)| (self.instance.var_to_str[var].clone(), *value)).collect())),
+            SolverResult::Unsat => Ok(None),
+            SolverResult::Unknown => Err(PyTimeoutError::new_err("the solver timed out")),
+        }
+    }
+
+    /// Add a clause of integer literals. Literals of new variables add variables named after their
+    /// number.
+    fn add_clause(&mut self, literals: Vec<i64>) -> PyResult<()> {
+        let mut clause = Vec::new();
+
+        for literal in literals {
+            let var = literal_variable(literal)?;
+
+            // keep ids contiguous, the solver expects variables 0..n
+            while self.instance.var_to_str.len() <= usize::from(var) {
+                let id = self.instance.var_to_str.len() as VariableId;
+                let name = (u32::from(id) + 1).to_string();
+                self.instance.var_to_str.insert(id, name.clone());
+                self.instance.str_to_var.insert(name, id);
+            }
+
+            let variable = Expression::Variable(var);
+            clause.push(if literal > 0 { variable } else { Expression::Not(Box::new(variable)) });
+        }
+
+        let expression = std::mem::replace(&mut self.instance.expression, Expression::Constant(true));
+        self.instance.expression = Expression::And(Box::new(expression), Box::new(Expression::disjunction(clause)));
+
+        Ok(())
+    }
+
+    /// The number of satisfying assignments of all variables.
+    fn count_models(&self, py: Python<'_>) -> u128 {
+        let instance = self.instance.clone();
+        py.detach(|| count_models(instance))
+    }
+
+    /// The formula converted to CNF in DIMACS format.
+    fn cnf_dimacs(&self) -> String {
+        let mut output = Vec::new();
+        CNF::from(self.instance.expression.clone()).to_dimacs(&mut output).expect("Writing to a Vec doesn't fail");
+
+        String::from_utf8(output).expect("DIMACS output is ASCII")
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Formula('{}')", self.instance.display_expression())
+    }
+}
+
+impl Formula {
+    fn variable(&self, literal: i64) -> PyResult<VariableId> {
+        Some(literal_variable(literal)?)
+            .filter(|var| self.instance.var_to_str.contains_key(var))
+            .ok_or_else(|| PyValueError::new_err(format!("unknown variable in literal {}", literal)))
+    }
+}
+
+fn literal_variable(literal: i64) -> PyResult<VariableId> {
+    VariableId::try_from(literal.unsigned_abs().wrapping_sub(1)).map_err(|_| PyValueError::new_err(format!("invalid literal {}", literal)))
+}
+
+#[pymodule]
+fn sat_solver(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Formula>()
+}
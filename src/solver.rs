@@ -1,2 +1,17 @@
 pub mod instance;
 pub mod dpll;
+pub mod cdcl;
+pub mod heuristics;
+pub mod restart;
+pub mod cache;
+pub mod enumerate;
+pub mod counting;
+pub mod external;
+pub mod harness;
+pub mod incremental;
+pub mod maxsat;
+pub mod horn;
+pub mod preprocess;
+pub mod two_sat;
+pub mod bruteforce;
+pub mod portfolio;
@@ -0,0 +1,92 @@
+// Trying every assignment, a ground truth for testing the other solvers on small instances.
+
+use std::{error::Error, fmt::Display};
+
+use crate::expression::expression::Assignment;
+
+use super::instance::{SATInstance, Solver, SolverResult};
+
+/// Instances with more variables are refused by [solve_bruteforce], there are already 16 million
+/// assignments at this size.
+pub const MAX_BRUTEFORCE_VARIABLES: usize = 24;
+
+/// The brute-force solver as a [Solver] backend.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BruteforceSolver;
+
+/// The instance has more than [MAX_BRUTEFORCE_VARIABLES] variables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooManyVariables(pub usize);
+
+/// Evaluate the expression of `instance` under every assignment of its variables until one
+/// satisfies it. The model assigns every variable occurring in the expression, except the
+/// auxiliary ones.
+pub fn solve_bruteforce(instance: &SATInstance) -> Result<SolverResult, TooManyVariables> {
+    let mut variables = instance.expression.variables().into_iter().collect::<Vec<_>>();
+    if variables.len() > MAX_BRUTEFORCE_VARIABLES {
+        return Err(TooManyVariables(variables.len()));
+    }
+    variables.sort();
+
+    // the same assignment is counted up for every row of the truth table
+    let mut assignment = Assignment::new(variables.iter().map(|var| (*var, false)).collect());
+    loop {
+        if assignment.satisfies_expression(&instance.expression) {
+            return Ok(SolverResult::Sat(Some(instance.without_auxiliary(assignment))));
+        }
+        if !assignment.advance(&variables) {
+            return Ok(SolverResult::Unsat);
+        }
+    }
+}
+
+impl Solver for BruteforceSolver {
+    type Error = TooManyVariables;
+
+    fn solve(&mut self, instance: &SATInstance) -> Result<SolverResult, Self::Error> {
+        solve_bruteforce(instance)
+    }
+}
+
+impl Display for TooManyVariables {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} variables are too many to try every assignment, at most {} are supported", self.0, MAX_BRUTEFORCE_VARIABLES)
+    }
+}
+
+impl Error for TooManyVariables {}
+
+#[cfg(test)]
+use crate::{generator::{pigeonhole, random_ksat}, parser::parse_str, solver::dpll::solve_dpll};
+
+#[test]
+fn test_solve_bruteforce() {
+    let instance = parse_str("(a | b) & (-a | c) & -c").unwrap();
+    let SolverResult::Sat(Some(model)) = solve_bruteforce(&instance).unwrap() else { panic!("expected a model") };
+    assert_eq!(model.values.len(), 3);
+    assert!(model.satisfies_expression(&instance.expression));
+
+    assert_eq!(solve_bruteforce(&pigeonhole(2)), Ok(SolverResult::Unsat));
+    assert_eq!(BruteforceSolver.solve(&parse_str("1").unwrap()), Ok(SolverResult::Sat(Some(Assignment::default()))));
+    assert_eq!(solve_bruteforce(&parse_str("a & 0").unwrap()), Ok(SolverResult::Unsat));
+
+    // auxiliary variables are left out
+    let SolverResult::Sat(Some(model)) = solve_bruteforce(&instance.to_tseitin()).unwrap() else { panic!("expected a model") };
+    assert_eq!(model.values.len(), 3);
+}
+
+#[test]
+fn test_solve_bruteforce_too_many_variables() {
+    let instance = parse_str(&(0..25).map(|var| format!("x{}", var)).collect::<Vec<_>>().join(" & ")).unwrap();
+    assert_eq!(solve_bruteforce(&instance), Err(TooManyVariables(25)));
+    assert_eq!(TooManyVariables(25).to_string(), "25 variables are too many to try every assignment, at most 24 are supported");
+}
+
+#[test]
+fn test_solve_bruteforce_agrees_with_dpll() {
+    for seed in 0..30 {
+        let instance = random_ksat(10, 43, 3, seed);
+        let dpll = solve_dpll(instance.clone(), Assignment::default());
+        assert_eq!(matches!(solve_bruteforce(&instance), Ok(SolverResult::Sat(_))), matches!(dpll, SolverResult::Sat(_)), "seed {}", seed);
+    }
+}
@@ -0,0 +1,106 @@
+// Caching of solver results for applications that solve the same instances repeatedly.
+
+use std::{collections::HashMap, hash::{DefaultHasher, Hash, Hasher}};
+
+use crate::expression::expression::Assignment;
+
+use super::{dpll::solve_dpll, instance::{SATInstance, SolverResult}};
+
+/// Maps instance hashes to solver results. The hash is purely structural, so the same formula
+/// with variables interned in a different order is a different entry.
+#[derive(Debug, Default, Clone)]
+pub struct SolverCache(HashMap<u64, SolverResult>);
+
+impl SolverCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the cached result for `instance`, solving it on a miss.
+    pub fn solve(&mut self, instance: SATInstance) -> &SolverResult {
+        let hash = instance_hash(&instance);
+        self.0.entry(hash).or_insert_with(|| solve_dpll(instance, Assignment::default()))
+    }
+
+    pub fn contains(&self, instance: &SATInstance) -> bool {
+        self.0.contains_key(&instance_hash(instance))
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+fn instance_hash(instance: &SATInstance) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    instance.expression.structural_hash().hash(&mut hasher);
+
+    let mut variables = instance.var_to_str.iter().collect::<Vec<_>>();
+    variables.sort();
+    variables.hash(&mut hasher);
+
+    // auxiliary variables are left out of models
+    let mut auxiliary = instance.auxiliary.iter().collect::<Vec<_>>();
+    auxiliary.sort();
+    auxiliary.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+#[cfg(test)]
+use crate::parser::parse_str;
+
+#[test]
+fn test_solver_cache() {
+    let mut cache = SolverCache::new();
+    let instance = parse_str("(a | b) & -a").unwrap();
+
+    assert!(!cache.contains(&instance));
+    assert!(matches!(cache.solve(instance.clone()), SolverResult::Sat(_)));
+    assert!(cache.contains(&instance));
+
+    // hit
+    assert!(matches!(cache.solve(instance.clone()), SolverResult::Sat(_)));
+    assert_eq!(cache.len(), 1);
+
+    assert!(matches!(cache.solve(parse_str("a & -a").unwrap()), SolverResult::Unsat));
+    assert_eq!(cache.len(), 2);
+
+    cache.clear();
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn test_solver_cache_variable_order() {
+    let mut cache = SolverCache::new();
+
+    // same formula, variables interned in a different order
+    cache.solve(parse_str("a & -b").unwrap());
+    assert!(!cache.contains(&parse_str("-b & a").unwrap()));
+
+    // same expression tree, different names
+    assert!(!cache.contains(&parse_str("x & -y").unwrap()));
+}
+
+#[test]
+fn test_solver_cache_auxiliary() {
+    let mut cache = SolverCache::new();
+    let instance = parse_str("a & b").unwrap();
+    let mut auxiliary = instance.clone();
+    auxiliary.auxiliary.insert(instance.str_to_var["b"]);
+
+    assert_eq!(cache.solve(instance.clone()).model().unwrap().values.len(), 2);
+    assert!(!cache.contains(&auxiliary));
+
+    let model = cache.solve(auxiliary).model().unwrap();
+    assert_eq!(model.values.keys().collect::<Vec<_>>(), [&instance.str_to_var["a"]]);
+    assert_eq!(cache.len(), 2);
+}
@@ -0,0 +1,355 @@
+// Conflict-driven clause learning (CDCL) solver.
+
+use std::{convert::Infallible, sync::atomic::Ordering, time::Instant};
+
+use crate::expression::{expression::{Assignment, Expression, VariableId}, normal::{Literal, CNF}};
+
+use super::{dpll::{DpllCNF, DpllClause}, heuristics::VsidsHeap, instance::{SATInstance, Solver, SolverConfig, SolverResult, SolverStats}};
+
+const VSIDS_DECAY_INTERVAL: u64 = 100;
+
+/// The CDCL solver as a [Solver] backend.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CdclSolver;
+
+/// Why and when every assigned variable got its value.
+#[derive(Debug)]
+struct ImplicationGraph {
+    /// Indexed by variable id, `None` if unassigned
+    nodes: Vec<Option<ImplicationNode>>,
+    /// Assigned literals in the order of assignment
+    trail: Vec<Literal>,
+    /// Index into `trail` where each decision level starts
+    level_starts: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ImplicationNode {
+    /// Index of the clause that became unit and implied the value, `None` for decisions and the
+    /// initial assignment
+    antecedent: Option<usize>,
+    level: usize,
+}
+
+impl ImplicationGraph {
+    fn new(num_vars: usize) -> Self {
+        Self { nodes: vec![None; num_vars], trail: Vec::new(), level_starts: Vec::new() }
+    }
+
+    fn level(&self) -> usize {
+        self.level_starts.len()
+    }
+
+    fn node(&self, var: VariableId) -> ImplicationNode {
+        self.nodes[usize::from(var)].expect("Only assigned variables are part of the graph")
+    }
+
+    fn assign(&mut self, literal: Literal, antecedent: Option<usize>, assignment: &mut Assignment) {
+        self.nodes[usize::from(literal.var_id)] = Some(ImplicationNode { antecedent, level: self.level() });
+        self.trail.push(literal);
+        assignment.values.insert(literal.var_id, literal.value);
+    }
+
+    fn decide(&mut self, literal: Literal, assignment: &mut Assignment) {
+        self.level_starts.push(self.trail.len());
+        self.assign(literal, None, assignment);
+    }
+
+    /// Undo all assignments above `level`.
+    fn backjump(&mut self, level: usize, assignment: &mut Assignment) {
+        let Some(&start) = self.level_starts.get(level) else {
+            return;
+        };
+
+        for literal in self.trail.drain(start..) {
+            self.nodes[usize::from(literal.var_id)] = None;
+            assignment.values.remove(&literal.var_id);
+        }
+        self.level_starts.truncate(level);
+    }
+}
+
+// assign the remaining literal of every unit clause until there are none left, returning the index
+// of a falsified clause if there is a conflict
+fn propagate(cnf: &DpllCNF, graph: &mut ImplicationGraph, assignment: &mut Assignment, stats: &mut SolverStats) -> Option<usize> {
+    let mut keep_going = true;
+
+    while keep_going {
+        keep_going = false;
+
+        for (index, clause) in cnf.clauses.iter().enumerate() {
+            let mut unassigned = None;
+            let mut unassigned_count = 0;
+            let mut satisfied = false;
+
+            for literal in &clause.literals {
+                match assignment.values.get(&literal.var_id) {
+                    Some(value) if *value == literal.value => {
+                        satisfied = true;
+                        break;
+                    },
+                    Some(_) => {},
+                    None => {
+                        unassigned = Some(*literal);
+                        unassigned_count += 1;
+                    },
+                }
+            }
+
+            match (satisfied, unassigned_count, unassigned) {
+                (true, _, _) => {},
+                (false, 0, _) => return Some(index),
+                (false, 1, Some(literal)) => {
+                    graph.assign(literal, Some(index), assignment);
+                    stats.propagations += 1;
+                    keep_going = true;
+                },
+                _ => {},
+            }
+        }
+    }
+
+    None
+}
+
+/// Learn a clause from the falsified clause `conflict` using the first unique implication point
+/// (1-UIP) cut. Returns the clause, with the asserting literal first, and the level to backjump to.
+fn analyze(cnf: &DpllCNF, graph: &ImplicationGraph, conflict: usize) -> (Vec<Literal>, usize) {
+    let level = graph.level();
+    let mut seen = vec![false; graph.nodes.len()];
+    let mut learned = vec![];
+    // literals of the current level that weren't resolved yet
+    let mut pending = 0;
+    let mut clause = conflict;
+    let mut index = graph.trail.len();
+
+    // resolve with antecedents of the current level, latest first, until only one literal of the
+    // current level is left
+    let uip = loop {
+        for literal in &cnf.clauses[clause].literals {
+            let var = usize::from(literal.var_id);
+            if seen[var] {
+                continue;
+            }
+            seen[var] = true;
+
+            // level 0 literals are false for good and can be dropped
+            match graph.node(literal.var_id).level {
+                node_level if node_level == level => pending += 1,
+                0 => {},
+                _ => learned.push(*literal),
+            }
+        }
+
+        let literal = loop {
+            index -= 1;
+            if seen[usize::from(graph.trail[index].var_id)] {
+                break graph.trail[index];
+            }
+        };
+
+        pending -= 1;
+        if pending == 0 {
+            break literal.not();
+        }
+        clause = graph.node(literal.var_id).antecedent.expect("Only the first literal of a level is a decision");
+    };
+
+    let backjump_level = learned.iter().map(|literal| graph.node(literal.var_id).level).max().unwrap_or(0);
+    learned.insert(0, uip);
+
+    (learned, backjump_level)
+}
+
+pub fn solve_cdcl(instance: SATInstance, initial_assignment: Assignment) -> SolverResult {
+    solve_cdcl_with_stats(instance, initial_assignment).0
+}
+
+/// Like [solve_cdcl], but also returns statistics about the search.
+pub fn solve_cdcl_with_stats(instance: SATInstance, initial_assignment: Assignment) -> (SolverResult, SolverStats) {
+    solve_cdcl_with_config(instance, initial_assignment, &SolverConfig::default())
+}
+
+/// Like [solve_cdcl_with_stats], but gives up with [SolverResult::Unknown] according to the
+/// `max_decisions`, `timeout` and `cancel` fields of `config`. The other fields are ignored.
+pub fn solve_cdcl_with_config(instance: SATInstance, initial_assignment: Assignment, config: &SolverConfig) -> (SolverResult, SolverStats) {
+    let instance = instance.into_clausal();
+
+    // without variables there is nothing to branch on
+    let Some(max_id) = instance.max_variable_id() else {
+        let result = match instance.expression.evaluate(&initial_assignment) {
+            Expression::Constant(true) => SolverResult::Sat(Some(initial_assignment)),
+            _ => SolverResult::Unsat,
+        };
+        return (result, SolverStats::default());
+    };
+
+    let num_vars = usize::from(max_id) + 1;
+    let mut cnf = DpllCNF::from(CNF::from(instance.expression.clone()));
+
+    let mut stats = SolverStats::default();
+    let mut vsids = VsidsHeap::new(cnf.variables());
+    let mut graph = ImplicationGraph::new(num_vars);
+    let mut assignment = initial_assignment.clone();
+    let deadline = config.timeout.map(|timeout| Instant::now() + timeout);
+
+    // the initial assignment is fixed at level 0, like everything following from it
+    for (var, value) in &initial_assignment.values {
+        if usize::from(*var) < num_vars {
+            graph.nodes[usize::from(*var)] = Some(ImplicationNode { antecedent: None, level: 0 });
+            graph.trail.push(Literal::new(*var, *value));
+        }
+    }
+
+    let result = loop {
+        if let Some(conflict) = propagate(&cnf, &mut graph, &mut assignment, &mut stats) {
+            stats.conflicts += 1;
+            // never restarts
+            stats.conflicts_since_restart += 1;
+            if graph.level() == 0 {
+                break SolverResult::Unsat;
+            }
+
+            let (learned, backjump_level) = analyze(&cnf, &graph, conflict);
+            for literal in &learned {
+                vsids.bump(literal.var_id);
+            }
+            if stats.conflicts.is_multiple_of(VSIDS_DECAY_INTERVAL) {
+                vsids.decay_all();
+            }
+
+            // after backjumping the learned clause is unit, so it implies its first literal
+            graph.backjump(backjump_level, &mut assignment);
+            let asserting = learned[0];
+            cnf.add_clause(DpllClause::new(learned));
+            graph.assign(asserting, Some(cnf.clauses.len() - 1), &mut assignment);
+            stats.propagations += 1;
+            continue;
+        }
+
+        // checked before every decision
+        let cancelled = config.cancel.as_ref().is_some_and(|cancel| cancel.load(Ordering::Relaxed));
+        let exhausted = config.max_decisions.is_some_and(|max_decisions| stats.decisions >= max_decisions);
+        if cancelled || exhausted || deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            break SolverResult::Unknown;
+        }
+
+        match vsids.pick_unassigned(&assignment) {
+            Some(var) => {
+                stats.decisions += 1;
+                graph.decide(Literal::new(var, true), &mut assignment);
+                stats.max_depth = stats.max_depth.max(graph.level() as u64);
+            },
+            None => {
+                debug_assert!(assignment.satisfies_expression(&instance.expression), "CDCL returned an invalid model");
+                break SolverResult::Sat(Some(instance.without_auxiliary(assignment)));
+            },
+        }
+    };
+
+    (result, stats)
+}
+
+impl Solver for CdclSolver {
+    type Error = Infallible;
+
+    fn solve(&mut self, instance: &SATInstance) -> Result<SolverResult, Self::Error> {
+        Ok(solve_cdcl(instance.clone(), Assignment::default()))
+    }
+}
+
+#[cfg(test)]
+use std::{sync::{atomic::AtomicBool, Arc}, time::Duration};
+
+#[cfg(test)]
+use crate::{expression::normal::Clause, generator::{pigeonhole, pigeonhole_cnf, random_ksat, random_ksat_cnf}, parser::parse_str, solver::dpll::{solve_dpll, solve_dpll_with_stats}};
+
+#[cfg(test)]
+fn assert_model(instance: &SATInstance, result: &SolverResult) {
+    let SolverResult::Sat(Some(model)) = result else { panic!("expected a model, got {:?}", result) };
+    assert!(model.satisfies_expression(&instance.expression), "{:?}", model);
+}
+
+#[test]
+fn test_cdcl() {
+    let instance = parse_str("(a | -b) & (b | c) & -c & (-a | d | e) & (-d | -e)").unwrap();
+    assert_model(&instance, &solve_cdcl(instance.clone(), Assignment::default()));
+
+    let unsat = parse_str("(a | b) & (-a | b) & (a | -b) & (-a | -b)").unwrap();
+    assert!(matches!(solve_cdcl(unsat, Assignment::default()), SolverResult::Unsat));
+    assert!(matches!(solve_cdcl(parse_str("a & -a").unwrap(), Assignment::default()), SolverResult::Unsat));
+}
+
+#[test]
+fn test_cdcl_initial_assignment() {
+    let instance = parse_str("(a | b) & (-a | c)").unwrap();
+
+    let assignment = Assignment::from([(instance.str_to_var["a"], true), (instance.str_to_var["c"], false)]);
+    assert!(matches!(solve_cdcl(instance.clone(), assignment), SolverResult::Unsat));
+
+    let a = instance.str_to_var["a"];
+    let result = solve_cdcl(instance.clone(), Assignment::from([(a, true)]));
+    assert_model(&instance, &result);
+    assert!(matches!(&result, SolverResult::Sat(Some(model)) if model.values[&a]));
+}
+
+#[test]
+fn test_cdcl_learns() {
+    let (result, stats) = solve_cdcl_with_stats(pigeonhole(5), Assignment::default());
+
+    assert!(matches!(result, SolverResult::Unsat));
+    assert!(stats.conflicts > 0 && stats.decisions > 0 && stats.propagations > 0);
+    assert!(stats.max_depth > 0 && stats.max_depth <= stats.decisions);
+}
+
+#[test]
+fn test_cdcl_agrees_with_dpll() {
+    // around the satisfiability threshold, so both answers come up
+    for seed in 0..20 {
+        let instance = random_ksat(20, 85, 3, seed);
+        let cdcl = solve_cdcl(instance.clone(), Assignment::default());
+
+        match solve_dpll(instance.clone(), Assignment::default()) {
+            SolverResult::Sat(_) => assert_model(&instance, &cdcl),
+            _ => assert!(matches!(cdcl, SolverResult::Unsat), "seed {}: {:?}", seed, cdcl),
+        }
+    }
+}
+
+#[test]
+fn test_cdcl_backjumps_over_unrelated_decisions() {
+    // a small unsatisfiable core next to many unrelated variables, DPLL refutes the core again
+    // for every combination of unrelated decisions it backtracks through
+    let noise = random_ksat_cnf(30, 60, 3, 1);
+    let core = pigeonhole_cnf(3).clauses.into_iter()
+        .map(|clause| Clause::new(clause.literals.into_iter().map(|literal| Literal::new(literal.var_id + 30, literal.value)).collect()));
+    let cnf = CNF::new(noise.clauses.into_iter().chain(core).collect());
+    let instance = SATInstance::new(cnf.to_expression(), (0..42).map(|var| (var, format!("x{}", var))).collect());
+
+    let (result, dpll) = solve_dpll_with_stats(instance.clone(), Assignment::default());
+    assert!(matches!(result, SolverResult::Unsat));
+    let (result, cdcl) = solve_cdcl_with_stats(instance.clone(), Assignment::default());
+    assert!(matches!(result, SolverResult::Unsat));
+
+    assert!(dpll.decisions > 5 * cdcl.decisions, "DPLL made {} decisions, CDCL {}", dpll.decisions, cdcl.decisions);
+    assert!(matches!(CdclSolver.solve(&instance), Ok(SolverResult::Unsat)));
+}
+
+#[test]
+fn test_cdcl_interrupted() {
+    let config = SolverConfig { timeout: Some(Duration::from_millis(50)), ..Default::default() };
+    let start = Instant::now();
+    assert_eq!(solve_cdcl_with_config(pigeonhole(10), Assignment::default(), &config).0, SolverResult::Unknown);
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    let config = SolverConfig { max_decisions: Some(3), ..Default::default() };
+    let (result, stats) = solve_cdcl_with_config(pigeonhole(10), Assignment::default(), &config);
+    assert_eq!((result, stats.decisions), (SolverResult::Unknown, 3));
+
+    let cancel = Arc::new(AtomicBool::new(true));
+    let config = SolverConfig { cancel: Some(cancel), ..Default::default() };
+    assert_eq!(solve_cdcl_with_config(pigeonhole(10), Assignment::default(), &config).0, SolverResult::Unknown);
+
+    // decisions aren't needed for this one
+    assert_eq!(solve_cdcl_with_config(parse_str("a & -a").unwrap(), Assignment::default(), &config).0, SolverResult::Unsat);
+}
@@ -0,0 +1,125 @@
+// Model counting backends.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::expression::{expression::VariableId, normal::{Clause, Literal, CNF}};
+
+use super::{enumerate::ModelIterator, instance::SATInstance};
+
+/// Count the models of `instance` exactly by enumerating all of them.
+pub fn count_models(instance: SATInstance) -> u128 {
+    let (cnf, variables) = counting_cnf(instance);
+
+    ModelIterator::from_cnf(cnf, variables).map(|_| 1).sum()
+}
+
+/// Approximately count the models of `instance` using ApproxMC: random XOR constraints split the
+/// models into cells that are small enough to be counted by enumeration.
+///
+/// With probability at least `1 - delta`, the result is within a factor of `1 + epsilon` of the
+/// exact count. Instances with few models are counted exactly. Panics if the auxiliary variables of
+/// the XOR constraints run out of ids.
+pub fn approx_count_models(instance: SATInstance, epsilon: f64, delta: f64, seed: u64) -> u128 {
+    assert!(epsilon > 0.0, "epsilon has to be positive");
+    assert!(delta > 0.0 && delta < 1.0, "delta has to be in (0, 1)");
+
+    let (cnf, variables) = counting_cnf(instance);
+    let threshold = (1.0 + 9.84 * (1.0 + epsilon / (1.0 + epsilon)) * (1.0 + 1.0 / epsilon).powi(2)).ceil() as usize;
+
+    let count = ModelIterator::from_cnf(cnf.clone(), variables.clone()).take(threshold).count();
+    if count < threshold {
+        return count as u128;
+    }
+
+    let first_free_id = cnf.clauses.iter()
+        .flat_map(|clause| &clause.literals)
+        .map(|literal| literal.var_id)
+        .chain(variables.iter().copied())
+        .max()
+        .map_or(0, |max| max.checked_add(1).expect("Ran out of variable ids"));
+
+    let iterations = (17.0 * (3.0 / delta).log2()).ceil() as usize;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut estimates = Vec::new();
+
+    for _ in 0..iterations {
+        let mut hashed = cnf.clone();
+        let mut next_id = first_free_id;
+
+        // add XOR constraints until the models fit into a single cell
+        for num_xors in 1..=variables.len() {
+            add_random_xor(&mut hashed, &variables, &mut next_id, &mut rng);
+
+            let count = ModelIterator::from_cnf(hashed.clone(), variables.clone()).take(threshold).count();
+            if count < threshold {
+                if count > 0 {
+                    estimates.push((count as u128).saturating_mul(2u128.saturating_pow(num_xors as u32)));
+                }
+                break;
+            }
+        }
+    }
+
+    if estimates.is_empty() {
+        return threshold as u128;
+    }
+
+    estimates.sort();
+    estimates[estimates.len() / 2]
+}
+
+fn counting_cnf(instance: SATInstance) -> (CNF, Vec<VariableId>) {
+    let instance = instance.into_clausal();
+    let mut variables = instance.var_to_str.keys().filter(|var| !instance.auxiliary.contains(var)).copied().collect::<Vec<_>>();
+    variables.sort();
+
+    (CNF::from(instance.expression), variables)
+}
+
+// constrain a random subset of variables to a random parity, chaining binary XORs through
+// auxiliary variables
+fn add_random_xor(cnf: &mut CNF, variables: &[VariableId], next_id: &mut VariableId, rng: &mut StdRng) {
+    let subset = variables.iter().copied().filter(|_| rng.gen_bool(0.5)).collect::<Vec<_>>();
+    let parity = rng.gen_bool(0.5);
+
+    let Some((&first, rest)) = subset.split_first() else {
+        // the empty XOR is false
+        if parity {
+            cnf.clauses.push(Clause::new(Vec::new()));
+        }
+        return;
+    };
+
+    let mut acc = first;
+    for &var in rest {
+        let aux = *next_id;
+        *next_id = aux.checked_add(1).expect("Ran out of variable ids");
+
+        // aux <-> acc ^ var
+        for (aux_value, acc_value, var_value) in [(false, true, true), (false, false, false), (true, false, true), (true, true, false)] {
+            cnf.clauses.push(Clause::new(vec![Literal::new(aux, aux_value), Literal::new(acc, acc_value), Literal::new(var, var_value)]));
+        }
+        acc = aux;
+    }
+
+    cnf.clauses.push(Clause::new(vec![Literal::new(acc, parity)]));
+}
+
+#[cfg(test)]
+use crate::parser::parse_str;
+
+#[test]
+fn test_count_models() {
+    assert_eq!(count_models(parse_str("(a | b | c) & -(a & b & c)").unwrap()), 6);
+    assert_eq!(count_models(parse_str("a & -a & b").unwrap()), 0);
+}
+
+#[test]
+fn test_approx_count_models() {
+    // few models are counted exactly
+    assert_eq!(approx_count_models(parse_str("(a | b | c) & -(a & b & c)").unwrap(), 0.8, 0.2, 0), 6);
+
+    let instance = parse_str("a | b | c | d | e | f").unwrap();
+    let count = approx_count_models(instance, 4.0, 0.8, 1);
+    assert!((63 / 5..=63 * 5).contains(&count), "{} is too far off", count);
+}
@@ -1,51 +1,91 @@
 // Simple DPLL solver implementation.
 
-use std::{collections::HashSet, process::exit};
+use std::{collections::{HashMap, HashSet, VecDeque}, convert::Infallible, sync::{atomic::{AtomicBool, Ordering}, Arc}, time::Duration};
 
-use chumsky::container::{Container, Seq};
-use rand::Rng;
-use rand::seq::SliceRandom;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+// std::time::Instant panics in the browser
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
 
-use crate::expression::{expression::{Assignment, VariableId}, normal::{Clause, Literal, CNF}};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 
-use super::instance::{SATInstance, SolverResult};
+use crate::expression::{expression::{Assignment, Expression, VariableId}, normal::{Clause, Literal, CNF}};
+
+use super::{heuristics::{BranchingHeuristic, VsidsHeap}, instance::{ProgressCallback, SATInstance, Solver, SolverConfig, SolverProgress, SolverResult, SolverStats}, restart::{RestartCounter, RestartPolicy}};
 
 #[derive(Debug)]
 enum DpllSolverResult {
     Sat,
     Unsat,
+    /// Out of time, unwind everything
+    Unknown,
 }
 
-#[derive(Debug)]
-struct DpllClause {
-    literals: Vec<Literal>,
-    is_disabled: bool,
+/// The DPLL solver as a [Solver] backend.
+#[derive(Debug, Clone, Default)]
+pub struct DpllSolver {
+    pub config: SolverConfig,
 }
 
-#[derive(Debug)]
-struct DpllCNF {
-    clauses: Vec<DpllClause>,
+const VSIDS_DECAY_INTERVAL: u64 = 100;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// State of a single solver run shared by all decisions.
+struct DpllContext {
+    heuristic: BranchingHeuristic,
+    pure_literals: bool,
+    /// Variables occurring in the clauses, sorted, the only ones to branch on
+    variables: Vec<VariableId>,
+    rng: StdRng,
+    vsids: VsidsHeap,
+    max_decisions: Option<u64>,
+    deadline: Option<Instant>,
+    cancel: Option<Arc<AtomicBool>>,
+    restarts: Option<RestartPolicy>,
+    restart_counter: RestartCounter,
+    /// Conflicts or decisions until the next restart
+    restart_threshold: u64,
+    decisions_since_restart: u64,
+    /// Last value of every variable that was decided or propagated, kept when backtracking and
+    /// restarting. `None` without phase saving.
+    phases: Option<HashMap<VariableId, bool>>,
+    stats: SolverStats,
+    progress: Option<ProgressCallback>,
+    start: Instant,
+    last_progress: Instant,
+    /// Most variables assigned at once so far
+    max_assigned: usize,
 }
 
-impl DpllCNF {
-    fn has_no_clauses(&self) -> bool {
-        self.clauses.iter().filter(|clause| !clause.is_disabled).count() == 0
-    }
+#[derive(Debug)]
+pub(super) struct DpllClause {
+    pub(super) literals: Vec<Literal>,
+    /// Indices into `literals` of the two watched literals, the same index twice for unit clauses
+    watched: [usize; 2],
+}
 
-    fn has_empty_clause(&self, assignment: &Assignment) -> bool {
-        for clause in self.clauses.iter().filter(|clause| !clause.is_disabled) {
-            if clause.literal_count(&assignment) == 0 {
-                return true;
-            }
-        }
+/// Working copy of the clauses, CDCL also appends learned ones. Only the watches change during the
+/// search, and they stay valid when backtracking.
+#[derive(Debug)]
+pub(super) struct DpllCNF {
+    pub(super) clauses: Vec<DpllClause>,
+    /// Indices of the clauses watching a literal, indexed by [watch_index]
+    watch_lists: Vec<Vec<usize>>,
+}
 
-        false
-    }
+fn watch_index(literal: Literal) -> usize {
+    2 * usize::from(literal.var_id) + usize::from(literal.value)
 }
 
 impl DpllClause {
-    fn new(literals: Vec<Literal>, is_disabled: bool) -> Self {
-        Self { literals, is_disabled }
+    pub(super) fn new(literals: Vec<Literal>) -> Self {
+        let watched = [0, literals.len().saturating_sub(1).min(1)];
+        Self { literals, watched }
+    }
+
+    fn is_satisfied(&self, assignment: &Assignment) -> bool {
+        self.literals.iter().any(|literal| assignment.values.get(&literal.var_id) == Some(&literal.value))
     }
 
     fn literal_count(&self, assignment: &Assignment) -> usize {
@@ -55,80 +95,147 @@ impl DpllClause {
 
 impl From<Clause> for DpllClause {
     fn from(value: Clause) -> Self {
-        let literals = value.literals.into_iter().map(|literal| Literal::from(literal)).collect::<Vec<_>>();
-        Self::new(literals, false)
+        Self::new(value.literals)
     }
 }
 
 impl From<CNF> for DpllCNF {
     fn from(value: CNF) -> Self {
-        let clauses = value.clauses.into_iter().map(|clause| DpllClause::from(clause)).collect::<Vec<_>>();
+        let clauses = value.clauses.into_iter().map(DpllClause::from).collect::<Vec<_>>();
         Self::new(clauses)
     }
 }
 
 impl DpllCNF {
     fn new(clauses: Vec<DpllClause>) -> Self {
-        Self { clauses }
+        let mut cnf = Self { clauses: Vec::with_capacity(clauses.len()), watch_lists: Vec::new() };
+        for clause in clauses {
+            cnf.add_clause(clause);
+        }
+        cnf
     }
 
-    pub fn disable(&mut self, literal: Literal) {
-        // disable enabled clauses with literal
-        for clause in self.clauses.iter_mut().filter(|clause| !clause.is_disabled) {
-            if clause.literals.contains(&literal) {
-                clause.is_disabled = true;
+    /// Sum of `weight` over the clauses that aren't satisfied yet for every unassigned literal in
+    /// them. `weight` gets the number of unassigned literals in the clause.
+    fn literal_scores(&self, assignment: &Assignment, weight: impl Fn(usize) -> f64) -> HashMap<Literal, f64> {
+        let mut scores: HashMap<Literal, f64> = HashMap::new();
+
+        for clause in self.clauses.iter().filter(|clause| !clause.is_satisfied(assignment)) {
+            let weight = weight(clause.literal_count(assignment));
+            for literal in clause.literals.iter().filter(|literal| !assignment.values.contains_key(&literal.var_id)) {
+                *scores.entry(*literal).or_default() += weight;
             }
         }
+
+        scores
     }
 
-    pub fn enable(&mut self, literal: Literal, assignment: &Assignment) {
-        // enable disabled clauses with literal (if no other positive literals are set)
-        'next_clause: for clause in self.clauses.iter_mut().filter(|clause| clause.is_disabled) {
-            if clause.literals.contains(&literal) {
-                for clause_literal in &clause.literals {
-                    if assignment.values.get(&clause_literal.var_id).is_some_and(|value| *value == clause_literal.value) {
-                        continue 'next_clause;
-                    }
-                }
-                clause.is_disabled = false;
+    /// Sorted ids of the variables occurring in the clauses.
+    pub(super) fn variables(&self) -> Vec<VariableId> {
+        let mut variables = self.clauses.iter()
+            .flat_map(|clause| &clause.literals)
+            .map(|literal| literal.var_id)
+            .collect::<Vec<_>>();
+        variables.sort_unstable();
+        variables.dedup();
+        variables
+    }
+
+    /// Append `clause` and watch its first two literals.
+    pub(super) fn add_clause(&mut self, clause: DpllClause) {
+        let index = self.clauses.len();
+        let watched = if clause.literals.is_empty() {
+            &[][..]
+        } else if clause.watched[0] == clause.watched[1] {
+            &clause.watched[..1]
+        } else {
+            &clause.watched[..]
+        };
+
+        for literal_index in watched {
+            let watch_index = watch_index(clause.literals[*literal_index]);
+            if watch_index >= self.watch_lists.len() {
+                self.watch_lists.resize_with(watch_index + 1, Vec::new);
             }
+            self.watch_lists[watch_index].push(index);
         }
+        self.clauses.push(clause);
     }
 }
 
-fn remove_unit_clauses(cnf: &mut DpllCNF, assignment: &mut Assignment, new_assignments: &mut Vec<Literal>) {
-    let mut keep_going = true;
+/// Propagate the literals in `queue`, which were just made true, through the clauses watching their
+/// negation. Implied literals are assigned and queued in turn. Returns the index of a clause with
+/// only false literals if there is a conflict.
+fn propagate(cnf: &mut DpllCNF, assignment: &mut Assignment, new_assignments: &mut Vec<Literal>, queue: &[Literal]) -> Option<usize> {
+    let mut queue = queue.iter().copied().collect::<VecDeque<_>>();
 
-    while keep_going {
-        keep_going = false;
+    while let Some(literal) = queue.pop_front() {
+        let false_literal = literal.not();
+        let Some(watchers) = cnf.watch_lists.get_mut(watch_index(false_literal)) else {
+            continue;
+        };
+        let watchers = std::mem::take(watchers);
+        let mut kept = Vec::with_capacity(watchers.len());
+        let mut conflict = None;
 
-        // find unit clause literal for which the value is known
-        let unit_clause_literal = cnf.clauses
-            .iter()
-            .filter(|clause| !clause.is_disabled)
-            .find(|clause| clause.literal_count(&assignment) == 1)
-            .map(|clause| clause.literals.iter().filter(|literal| !assignment.values.contains_key(&literal.var_id)).next().expect("We just checked this")).copied();
+        for (position, &index) in watchers.iter().enumerate() {
+            let clause = &mut cnf.clauses[index];
+            let slot = usize::from(clause.literals[clause.watched[0]] != false_literal);
+            let other = clause.literals[clause.watched[1 - slot]];
 
-        if let Some(literal) = unit_clause_literal {
-            keep_going = true;
+            // satisfied by the other watch, nothing to do
+            if assignment.values.get(&other.var_id) == Some(&other.value) {
+                kept.push(index);
+                continue;
+            }
+
+            // move the watch to another literal that isn't false
+            let replacement = (0..clause.literals.len())
+                .filter(|literal_index| !clause.watched.contains(literal_index))
+                .find(|literal_index| {
+                    let candidate = clause.literals[*literal_index];
+                    assignment.values.get(&candidate.var_id).is_none_or(|value| *value == candidate.value)
+                });
+            if let Some(literal_index) = replacement {
+                clause.watched[slot] = literal_index;
+                let watch_index = watch_index(clause.literals[literal_index]);
+                if watch_index >= cnf.watch_lists.len() {
+                    cnf.watch_lists.resize_with(watch_index + 1, Vec::new);
+                }
+                cnf.watch_lists[watch_index].push(index);
+                continue;
+            }
 
-            // insert into assignment
-            assignment.values.insert(literal.var_id, literal.value);
-            new_assignments.push(literal);
+            kept.push(index);
+            if assignment.values.contains_key(&other.var_id) {
+                // every literal is false
+                kept.extend_from_slice(&watchers[position + 1..]);
+                conflict = Some(index);
+                break;
+            }
+
+            // unit clause, the other watch has to be true
+            assignment.values.insert(other.var_id, other.value);
+            new_assignments.push(other);
+            queue.push_back(other);
+        }
 
-            // reduce cnf
-            cnf.disable(literal);
+        cnf.watch_lists[watch_index(false_literal)] = kept;
+        if conflict.is_some() {
+            return conflict;
         }
     }
+
+    None
 }
 
-fn eliminate_pure_literals(cnf: &mut DpllCNF, assignment: &mut Assignment, new_assignments: &mut Vec<Literal>) {
+fn eliminate_pure_literals(cnf: &DpllCNF, assignment: &mut Assignment, new_assignments: &mut Vec<Literal>) {
     // find pure literals
     let mut pure_literals: HashSet<Literal> = HashSet::new();
     let mut impure_literals: HashSet<Literal> = HashSet::new();
 
-    for clause in cnf.clauses.iter().filter(|clause| !clause.is_disabled) {
-        for literal in clause.literals.iter().filter(|literal| !assignment.values.contains_key(&literal.var_id)).map(|literal| literal).copied() {
+    for clause in cnf.clauses.iter().filter(|clause| !clause.is_satisfied(assignment)) {
+        for literal in clause.literals.iter().filter(|literal| !assignment.values.contains_key(&literal.var_id)).copied() {
             if pure_literals.contains(&literal.not()) {
                 pure_literals.remove(&literal.not());
 
@@ -142,124 +249,803 @@ fn eliminate_pure_literals(cnf: &mut DpllCNF, assignment: &mut Assignment, new_a
     }
 
     for pure_literal in pure_literals {
-        // assign values to pure literals to make them true
+        // assign values to pure literals to make them true. Purity only holds below the current
+        // decision, so they are undone with it like the literals implied by propagation
         assignment.values.insert(pure_literal.var_id, pure_literal.value);
         new_assignments.push(pure_literal);
+    }
+}
+
+impl DpllContext {
+    fn new(config: &SolverConfig, variables: Vec<VariableId>) -> Self {
+        let mut restarts = config.restarts.clone();
+        let restart_threshold = restarts.as_mut().map_or(u64::MAX, |restarts| restarts.next_threshold());
+
+        Self {
+            heuristic: config.heuristic,
+            pure_literals: config.pure_literals,
+            rng: StdRng::seed_from_u64(config.seed),
+            vsids: VsidsHeap::new(variables.iter().copied()),
+            variables,
+            max_decisions: config.max_decisions,
+            deadline: config.timeout.map(|timeout| Instant::now() + timeout),
+            cancel: config.cancel.clone(),
+            restarts,
+            restart_counter: config.restart_counter,
+            restart_threshold,
+            decisions_since_restart: 0,
+            phases: config.phase_saving.then(HashMap::new),
+            stats: SolverStats::default(),
+            progress: config.progress.clone(),
+            start: Instant::now(),
+            last_progress: Instant::now(),
+            max_assigned: 0,
+        }
+    }
+
+    fn conflict(&mut self, clause: &DpllClause) {
+        self.stats.conflicts += 1;
+        self.stats.conflicts_since_restart += 1;
+
+        if self.heuristic == BranchingHeuristic::Vsids {
+            for literal in &clause.literals {
+                self.vsids.bump(literal.var_id);
+            }
+            if self.stats.conflicts.is_multiple_of(VSIDS_DECAY_INTERVAL) {
+                self.vsids.decay_all();
+            }
+        }
+    }
+
+    // checked before every decision
+    fn interruption(&self) -> Option<DpllSolverResult> {
+        let cancelled = self.cancel.as_ref().is_some_and(|cancel| cancel.load(Ordering::Relaxed));
+        let exhausted = self.max_decisions.is_some_and(|max_decisions| self.stats.decisions >= max_decisions);
 
-        // disable clauses that contain pure literals
-        cnf.disable(pure_literal);
+        let timed_out = self.deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        (cancelled || exhausted || timed_out).then_some(DpllSolverResult::Unknown)
+    }
+
+    // report progress if the last report is long enough ago
+    fn progress(&mut self, depth: u64) {
+        let Some(progress) = &self.progress else {
+            return;
+        };
+
+        let now = Instant::now();
+        if now.duration_since(self.last_progress) >= PROGRESS_INTERVAL {
+            self.last_progress = now;
+            (progress.0)(&SolverProgress {
+                stats: self.stats,
+                elapsed: now.duration_since(self.start),
+                depth,
+                max_assigned: self.max_assigned,
+                num_vars: self.variables.len(),
+            });
+        }
+    }
+
+    fn restart_due(&self) -> bool {
+        match self.restart_counter {
+            RestartCounter::Conflicts => self.stats.conflicts_since_restart >= self.restart_threshold,
+            RestartCounter::Decisions => self.decisions_since_restart >= self.restart_threshold,
+        }
+    }
+
+    fn restart(&mut self) {
+        self.stats.restarts += 1;
+        self.stats.conflicts_since_restart = 0;
+        self.decisions_since_restart = 0;
+        self.restart_threshold = self.restarts.as_mut().map_or(u64::MAX, |restarts| restarts.next_threshold());
+    }
+
+    // remember the values of `literals` for phase saving
+    fn save_phases(&mut self, literals: &[Literal]) {
+        if let Some(phases) = &mut self.phases {
+            phases.extend(literals.iter().map(|literal| (literal.var_id, literal.value)));
+        }
+    }
+
+    /// The literal to try first for the next decision.
+    fn choose_literal(&mut self, cnf: &DpllCNF, assignment: &Assignment) -> Option<Literal> {
+        let var_id = match self.heuristic {
+            BranchingHeuristic::Random => choose_random_variable(&mut self.rng, &self.variables, assignment),
+            BranchingHeuristic::FirstUnassigned => choose_first_unassigned(&self.variables, assignment),
+            BranchingHeuristic::MostFrequent => return choose_best_literal(cnf.literal_scores(assignment, |_| 1.0)),
+            BranchingHeuristic::JeroslowWang => return choose_best_literal(cnf.literal_scores(assignment, |literal_count| 0.5f64.powi(literal_count as i32))),
+            BranchingHeuristic::Vsids => self.vsids.pick_unassigned(assignment),
+        }?;
+
+        let value = self.phases.as_ref().and_then(|phases| phases.get(&var_id)).copied().unwrap_or(true);
+        Some(Literal::new(var_id, value))
     }
 }
 
-fn choose_variable(cnf: &DpllCNF, max_id: VariableId, assignment: &Assignment) -> Option<VariableId> {
-    if assignment.values.len() < usize::try_from(max_id).unwrap() / 2 {
+fn choose_random_variable(rng: &mut StdRng, variables: &[VariableId], assignment: &Assignment) -> Option<VariableId> {
+    if assignment.values.len() < variables.len() / 2 {
         loop {
-            let varid_rand = rand::thread_rng().gen_range(0..=max_id);
+            let varid_rand = variables[rng.gen_range(0..variables.len())];
             if !assignment.values.contains_key(&varid_rand) {
                 return Some(varid_rand);
             }
         }
     } else {
-        let available_varids = (0..=max_id).filter(|id| !assignment.values.contains_key(id)).collect::<Vec<_>>();
-        available_varids.choose(&mut rand::thread_rng()).map(|v| *v)
+        let available_varids = variables.iter().filter(|id| !assignment.values.contains_key(id)).collect::<Vec<_>>();
+        available_varids.choose(rng).copied().copied()
     }
 }
 
-fn solve_dpll_recursive(cnf: &mut DpllCNF, assignment: &mut Assignment, max_id: VariableId) -> DpllSolverResult {
-    // keep track of new assignments so they can be removed on backtrack
-    let mut new_assignments: Vec<Literal> = Vec::new();
+fn choose_first_unassigned(variables: &[VariableId], assignment: &Assignment) -> Option<VariableId> {
+    variables.iter().copied().find(|var_id| !assignment.values.contains_key(var_id))
+}
 
-    // try to find solution by repeatedly applying simple steps
-    remove_unit_clauses(cnf, assignment, &mut new_assignments);
-    eliminate_pure_literals(cnf, assignment, &mut new_assignments);
+// the literal with the highest score, ties are broken deterministically
+fn choose_best_literal(scores: HashMap<Literal, f64>) -> Option<Literal> {
+    scores.into_iter()
+        .max_by(|(lhs, lhs_score), (rhs, rhs_score)| lhs_score.total_cmp(rhs_score).then_with(|| rhs.cmp(lhs)))
+        .map(|(literal, _)| literal)
+}
 
-    // no clauses left => solution found
-    if cnf.has_no_clauses() {
-        return DpllSolverResult::Sat;
+// the watches stay valid, so only the assignment needs to be undone
+fn restore(assignment: &mut Assignment, new_assignments: Vec<Literal>) {
+    for new_literal in new_assignments.into_iter() {
+        assignment.values.remove(&new_literal.var_id);
     }
+}
 
-    // empty clause left => unsat
-    if cnf.has_empty_clause(assignment) {
-        // restore
-        for new_literal in new_assignments.into_iter() {
-            assignment.values.remove(&new_literal.var_id);
-            cnf.enable(new_literal, assignment);
-        }
+/// A decision on the explicit search stack.
+#[derive(Debug)]
+struct DpllFrame {
+    /// The decided literal, with the polarity currently tried
+    decision: Literal,
+    /// Whether `decision` is already the second polarity tried
+    flipped: bool,
+    /// Literals implied by the decision, undone together with it
+    implied: Vec<Literal>,
+}
+
+// the literals implied by the latest decision, or at the root if there is none
+fn implied_literals<'a>(stack: &'a mut [DpllFrame], root: &'a mut Vec<Literal>) -> &'a mut Vec<Literal> {
+    stack.last_mut().map_or(root, |frame| &mut frame.implied)
+}
 
-        return DpllSolverResult::Unsat;
+// undo every decision and everything implied by them, leaving what is implied at the root
+fn backtrack_to_root(assignment: &mut Assignment, stack: &mut Vec<DpllFrame>) {
+    for frame in stack.drain(..).rev() {
+        restore(assignment, frame.implied);
+        assignment.values.remove(&frame.decision.var_id);
     }
+}
 
-    // now we need to guess
-    let var_id = choose_variable(&cnf, max_id, &assignment).expect("There has to be a variable left");
+// undo everything, leaving only the assignment the search started with
+fn unwind(assignment: &mut Assignment, mut stack: Vec<DpllFrame>, root: Vec<Literal>) {
+    backtrack_to_root(assignment, &mut stack);
+    restore(assignment, root);
+}
 
-    // try with var_id set to true
-    assignment.values.insert(var_id, true);
-    cnf.disable(Literal::new(var_id, true));
+// `assigned` are the literals assigned right before the search that still need to be propagated.
+// Decisions live on an explicit stack instead of the call stack, so the depth of the search is
+// only limited by the number of variables.
+fn solve_dpll_iterative(cnf: &mut DpllCNF, assignment: &mut Assignment, context: &mut DpllContext, assigned: &[Literal]) -> DpllSolverResult {
+    let mut stack: Vec<DpllFrame> = Vec::new();
+    // implied before the first decision
+    let mut root: Vec<Literal> = Vec::new();
+    let mut queue = assigned.to_vec();
 
-    if let DpllSolverResult::Sat = solve_dpll_recursive(cnf, assignment, max_id) {
-        return DpllSolverResult::Sat;
-    }
+    loop {
+        let implied = implied_literals(&mut stack, &mut root);
+        let already_implied = implied.len();
+
+        // try to find solution by repeatedly applying simple steps
+        let conflict = propagate(cnf, assignment, implied, &queue);
+        context.stats.propagations += (implied.len() - already_implied) as u64;
+        context.save_phases(&implied[already_implied..]);
+
+        // clause with only false literals => backtrack to the latest decision with an untried
+        // polarity, unsat if there is none
+        if let Some(index) = conflict {
+            context.conflict(&cnf.clauses[index]);
+
+            loop {
+                let Some(frame) = stack.pop() else {
+                    restore(assignment, root);
+                    return DpllSolverResult::Unsat;
+                };
+                restore(assignment, frame.implied);
+                assignment.values.remove(&frame.decision.var_id);
+
+                if !frame.flipped {
+                    let literal = frame.decision.not();
+                    assignment.values.insert(literal.var_id, literal.value);
+                    context.save_phases(&[literal]);
+                    stack.push(DpllFrame { decision: literal, flipped: true, implied: Vec::new() });
+                    queue = vec![literal];
+                    break;
+                }
+            }
+
+            continue;
+        }
+
+        if context.pure_literals {
+            let already_implied = implied.len();
+            eliminate_pure_literals(cnf, assignment, implied);
+            context.stats.pure_literals += (implied.len() - already_implied) as u64;
+        }
+
+        context.stats.max_depth = context.stats.max_depth.max(stack.len() as u64);
+        context.max_assigned = context.max_assigned.max(assignment.values.len());
+        context.progress(stack.len() as u64);
+
+        if let Some(result) = context.interruption() {
+            unwind(assignment, stack, root);
+
+            return result;
+        }
 
-    // restore
-    assignment.values.remove(&var_id);
-    cnf.enable(Literal::new(var_id, true), assignment);
+        // start deciding again, the literals implied at the root stay
+        if context.restart_due() {
+            backtrack_to_root(assignment, &mut stack);
+            context.restart();
+            queue = Vec::new();
+            continue;
+        }
 
-    // try with var_id set to false
-    assignment.values.insert(var_id, false);
-    cnf.disable(Literal::new(var_id, false));
+        // nothing left to decide without a conflict => solution found
+        let Some(literal) = context.choose_literal(cnf, assignment) else {
+            return DpllSolverResult::Sat;
+        };
+        context.stats.decisions += 1;
+        context.decisions_since_restart += 1;
+        context.save_phases(&[literal]);
 
-    if let DpllSolverResult::Sat = solve_dpll_recursive(cnf, assignment, max_id) {
-        return DpllSolverResult::Sat;
+        // try the value chosen by the heuristic first
+        assignment.values.insert(literal.var_id, literal.value);
+        stack.push(DpllFrame { decision: literal, flipped: false, implied: Vec::new() });
+        queue = vec![literal];
     }
+}
 
-    // didn't work? too bad => Unsat
+pub fn solve_dpll(instance: SATInstance, initial_assignment: Assignment) -> SolverResult {
+    solve_dpll_with_config(instance, initial_assignment, SolverConfig::default())
+}
 
-    // restore
-    assignment.values.remove(&var_id);
-    cnf.enable(Literal::new(var_id, false), assignment);
+pub fn solve_dpll_with_config(instance: SATInstance, initial_assignment: Assignment, config: SolverConfig) -> SolverResult {
+    solve_dpll_with_config_and_stats(instance, initial_assignment, config).0
+}
 
-    for new_literal in new_assignments.into_iter() {
-        assignment.values.remove(&new_literal.var_id);
-        cnf.enable(new_literal, assignment);
+/// Like [solve_dpll], but also returns statistics about the search.
+pub fn solve_dpll_with_stats(instance: SATInstance, initial_assignment: Assignment) -> (SolverResult, SolverStats) {
+    solve_dpll_with_config_and_stats(instance, initial_assignment, SolverConfig::default())
+}
+
+/// Like [solve_dpll], but if the instance is unsatisfiable, also returns an unsatisfiable core:
+/// indices into the clauses of [CNF::from_conjuncts] of the expression that are unsatisfiable on
+/// their own, together with `initial_assignment`.
+///
+/// Starting with all clauses, every clause is dropped as long as the rest stays unsatisfiable. No
+/// single clause of the core can be left out then, but there may be smaller cores.
+pub fn solve_dpll_with_core(instance: SATInstance, initial_assignment: Assignment) -> (SolverResult, Option<Vec<usize>>) {
+    let result = solve_dpll(instance.clone(), initial_assignment.clone());
+    if result != SolverResult::Unsat {
+        return (result, None);
     }
 
-    DpllSolverResult::Unsat
+    let cnf = CNF::from_conjuncts(instance.expression);
+    let mut core = (0..cnf.clauses.len()).collect::<Vec<_>>();
+    let mut index = 0;
+    while index < core.len() {
+        let clauses = core.iter().enumerate().filter(|(position, _)| *position != index).map(|(_, clause)| cnf.clauses[*clause].clone()).collect();
+        match solve_dpll_cnf(CNF::new(clauses), initial_assignment.clone(), &SolverConfig::default()).0 {
+            SolverResult::Unsat => {
+                core.remove(index);
+            },
+            _ => index += 1,
+        }
+    }
+
+    (result, Some(core))
 }
 
-pub fn solve_dpll(instance: SATInstance, initial_assignment: Assignment) -> SolverResult {
-    let max_id = VariableId::try_from(instance.var_to_str.len() - 1).expect("Couldn't convert to variable id");
+/// Like [solve_dpll_with_config], but also returns statistics about the search.
+pub fn solve_dpll_with_config_and_stats(instance: SATInstance, initial_assignment: Assignment, config: SolverConfig) -> (SolverResult, SolverStats) {
+    // without variables there is nothing to branch on, e.g. for `p cnf 0 1` or `1 & (0 | 1)`
+    let Some(max_id) = instance.max_variable_id() else {
+        let result = match instance.expression.evaluate(&initial_assignment) {
+            Expression::Constant(true) => SolverResult::Sat(Some(initial_assignment)),
+            _ => SolverResult::Unsat,
+        };
+        return (result, SolverStats::default());
+    };
+
+    // debug builds double check every model against the original formula
+    let original = cfg!(debug_assertions).then(|| instance.clone());
+    let named = instance.var_to_str.keys().copied().collect::<Vec<_>>();
 
-    // reduce cnf according to initial assignment
-    let mut cnf: DpllCNF = CNF::from(instance.expression).into();
+    let mut instance = instance.into_clausal();
+    let auxiliary = std::mem::take(&mut instance.auxiliary);
+
+    match solve_dpll_cnf(CNF::from(instance.expression), initial_assignment, &config) {
+        (SolverResult::Sat(Some(mut assignment)), stats) => {
+            if config.complete_model {
+                for var in named {
+                    assignment.values.entry(var).or_insert(false);
+                }
+            }
+            if let Some(original) = original {
+                // clauses like `a | -a` may be dropped from the CNF, their variables stay unassigned
+                let mut complete = assignment.clone();
+                complete.complete(max_id);
+                if let Err(error) = original.check(&complete) {
+                    panic!("DPLL returned an invalid model: {}", error);
+                }
+            }
+            assignment.values.retain(|var, _| !auxiliary.contains(var));
+            (SolverResult::Sat(Some(assignment)), stats)
+        },
+        result => result,
+    }
+}
+
+/// Solve `cnf` directly, branching only on the variables occurring in it.
+pub(crate) fn solve_dpll_cnf(mut cnf: CNF, initial_assignment: Assignment, config: &SolverConfig) -> (SolverResult, SolverStats) {
+    if config.simplify {
+        cnf.simplify();
+    }
+
+    solve_dpll_clauses(&mut cnf.into(), initial_assignment, config)
+}
+
+/// Like [solve_dpll_cnf] for clauses that are already watched. They can be solved again afterwards,
+/// e.g. with more clauses added.
+pub(super) fn solve_dpll_clauses(cnf: &mut DpllCNF, initial_assignment: Assignment, config: &SolverConfig) -> (SolverResult, SolverStats) {
+    // the initial assignment is propagated at the root like a decision
     let mut assignment = initial_assignment.clone();
+    let mut context = DpllContext::new(config, cnf.variables());
+    let mut assigned = Vec::new();
 
     for (var_id, value) in assignment.values.iter() {
-        cnf.disable(Literal::new(*var_id, *value).into());
+        assigned.push(Literal::new(*var_id, *value));
     }
 
-    match solve_dpll_recursive(&mut cnf, &mut assignment, max_id) {
+    // unit clauses are never triggered by their watches, so assign them up front
+    for clause in cnf.clauses.iter().filter(|clause| clause.literals.len() <= 1) {
+        match clause.literals.first().copied() {
+            Some(literal) if assignment.values.get(&literal.var_id) == Some(&literal.value) => {},
+            Some(literal) if !assignment.values.contains_key(&literal.var_id) => {
+                assignment.values.insert(literal.var_id, literal.value);
+                assigned.push(literal);
+                context.stats.propagations += 1;
+            },
+            _ => {
+                context.conflict(clause);
+                return (SolverResult::Unsat, context.stats);
+            },
+        }
+    }
+
+    let result = match solve_dpll_iterative(cnf, &mut assignment, &mut context, &assigned) {
         DpllSolverResult::Sat => SolverResult::Sat(Some(assignment)),
         DpllSolverResult::Unsat => SolverResult::Unsat,
+        DpllSolverResult::Unknown => SolverResult::Unknown,
+    };
+
+    (result, context.stats)
+}
+
+impl Solver for DpllSolver {
+    type Error = Infallible;
+
+    fn solve(&mut self, instance: &SATInstance) -> Result<SolverResult, Self::Error> {
+        Ok(solve_dpll_with_config(instance.clone(), Assignment::default(), self.config.clone()))
     }
 }
 
 #[test]
-fn test_disable() {
-    let lit0 = Literal::new(0, false);
-    let lit1 = Literal::new(1, true);
-    let lit2 = Literal::new(2, true);
-    let lit3 = Literal::new(3, false);
+fn test_watches() {
+    let a = Literal::new(0, true);
+    let b = Literal::new(1, true);
+    let c = Literal::new(2, true);
+    let d = Literal::new(3, true);
 
     let mut cnf = DpllCNF::new(vec![
-        DpllClause::new(vec![lit0, lit1], false),
-        DpllClause::new(vec![lit1, lit2], false),
-        DpllClause::new(vec![lit3], false),
+        DpllClause::new(vec![a.not(), b]),
+        DpllClause::new(vec![b.not(), c, d]),
+        DpllClause::new(vec![a.not(), c.not()]),
+    ]);
+    let watchers = |cnf: &DpllCNF, literal| cnf.watch_lists.get(watch_index(literal)).cloned().unwrap_or_default();
+    assert_eq!(watchers(&cnf, a.not()), vec![0, 2]);
+
+    // -d moves no watches, a implies b and -c, which leave nothing for the second clause
+    let mut assignment = Assignment::from([(3, false)]);
+    let mut new_assignments = Vec::new();
+    assert_eq!(propagate(&mut cnf, &mut assignment, &mut new_assignments, &[d.not()]), None);
+    assert!(new_assignments.is_empty());
+    assignment.values.insert(0, true);
+    assert_eq!(propagate(&mut cnf, &mut assignment, &mut new_assignments, &[a]), Some(1));
+    assert_eq!(new_assignments, vec![b, c.not()]);
+
+    // backtracking keeps the watches valid, -b now moves the second clause's watch to d
+    assignment.values.clear();
+    assignment.values.insert(1, true);
+    new_assignments.clear();
+    assert_eq!(propagate(&mut cnf, &mut assignment, &mut new_assignments, &[b]), None);
+    assert!(new_assignments.is_empty());
+    assert_eq!(watchers(&cnf, b.not()), Vec::<usize>::new());
+    assert_eq!(watchers(&cnf, d), vec![1]);
+}
+
+#[cfg(test)]
+use crate::{generator::{pigeonhole, pigeonhole_cnf, random_ksat, random_ksat_cnf}, parser::parse_str, solver::cdcl::solve_cdcl};
+
+#[test]
+fn test_solver_configs() {
+    let configs = [
+        SolverConfig::default(),
+        SolverConfig { heuristic: BranchingHeuristic::JeroslowWang, ..Default::default() },
+        SolverConfig { heuristic: BranchingHeuristic::Vsids, ..Default::default() },
+        SolverConfig { heuristic: BranchingHeuristic::FirstUnassigned, ..Default::default() },
+        SolverConfig { heuristic: BranchingHeuristic::MostFrequent, pure_literals: false, ..Default::default() },
+        SolverConfig { pure_literals: false, seed: 7, ..Default::default() },
+        SolverConfig { heuristic: BranchingHeuristic::Vsids, restarts: Some(RestartPolicy::luby(1)), ..Default::default() },
+        SolverConfig { restarts: Some(RestartPolicy::luby(2)), pure_literals: false, ..Default::default() },
+    ];
+    let instances = [
+        (parse_str("(a | -b) & (b | c) & -c").unwrap(), true),
+        (parse_str("(a | b) & (-a | b) & (a | -b) & (-a | -b)").unwrap(), false),
+        (pigeonhole(4), false),
+        (random_ksat(30, 90, 3, 1), true),
+    ];
+
+    for config in configs {
+        for (instance, is_sat) in &instances {
+            match solve_dpll_with_config(instance.clone(), Assignment::default(), config.clone()) {
+                SolverResult::Sat(Some(assignment)) => {
+                    assert!(is_sat, "{:?} found a model for an unsatisfiable instance", config);
+                    assert!(assignment.satisfies_expression(&instance.expression));
+                },
+                SolverResult::Unsat => assert!(!is_sat, "{:?} didn't find a model", config),
+                result => panic!("{:?} gave {:?}", config, result),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_complete_model() {
+    // a satisfies every clause, so JW stops without deciding b
+    let instance = parse_str("a & (a | b)").unwrap();
+    let (a, b) = (instance.str_to_var["a"], instance.str_to_var["b"]);
+    let config = SolverConfig { heuristic: BranchingHeuristic::JeroslowWang, pure_literals: false, ..Default::default() };
+
+    let partial = solve_dpll_with_config(instance.clone(), Assignment::default(), config.clone());
+    assert_eq!(partial.model().unwrap().values, HashMap::from([(a, true)]));
+
+    let complete = solve_dpll_with_config(instance, Assignment::default(), SolverConfig { complete_model: true, ..config });
+    assert_eq!(complete.model().unwrap().values, HashMap::from([(a, true), (b, false)]));
+    assert!(SolverResult::Unsat.model().is_none());
+}
+
+#[test]
+fn test_timeout() {
+    let config = SolverConfig { timeout: Some(Duration::ZERO), ..Default::default() };
+
+    assert!(matches!(solve_dpll_with_config(pigeonhole(8), Assignment::default(), config), SolverResult::Unknown));
+}
+
+#[test]
+fn test_max_decisions() {
+    let config = SolverConfig { max_decisions: Some(10), ..Default::default() };
+    let (result, stats) = solve_dpll_with_config_and_stats(pigeonhole(8), Assignment::default(), config.clone());
+    assert!(matches!(result, SolverResult::Unknown));
+    assert_eq!(stats.decisions, 10);
+
+    // easy instances are still solved within the budget
+    assert!(matches!(solve_dpll_with_config(pigeonhole(2), Assignment::default(), config), SolverResult::Unsat));
+}
+
+#[test]
+fn test_cancel() {
+    let cancel = Arc::new(AtomicBool::new(true));
+    let config = SolverConfig { cancel: Some(cancel.clone()), ..Default::default() };
+    assert_eq!(solve_dpll_with_config(pigeonhole(8), Assignment::default(), config.clone()), SolverResult::Unknown);
+
+    // nothing is left behind, so solving works again once the flag is cleared
+    cancel.store(false, Ordering::Relaxed);
+    assert_eq!(solve_dpll_with_config(pigeonhole(3), Assignment::default(), config), SolverResult::Unsat);
+}
+
+#[test]
+fn test_seed() {
+    let solve = |seed| {
+        let config = SolverConfig { seed, ..Default::default() };
+        match solve_dpll_with_config_and_stats(random_ksat(40, 80, 3, 0), Assignment::default(), config) {
+            (SolverResult::Sat(Some(assignment)), stats) => (assignment.values, stats),
+            (result, _) => panic!("unexpected {:?}", result),
+        }
+    };
+
+    // same model and the same amount of work, so the search went the same way
+    assert_eq!(solve(1), solve(1));
+    assert!(solve(1).1.decisions > 0);
+}
+
+#[test]
+fn test_stats() {
+    let (result, stats) = solve_dpll_with_stats(parse_str("a & -a").unwrap(), Assignment::default());
+    assert!(matches!(result, SolverResult::Unsat));
+    assert_eq!(stats.decisions, 0);
+    assert!(stats.conflicts >= 1);
+
+    // solved by unit propagation alone
+    let (result, stats) = solve_dpll_with_stats(parse_str("a & (-a | b) & (-b | c)").unwrap(), Assignment::default());
+    assert!(matches!(result, SolverResult::Sat(_)));
+    assert_eq!((stats.decisions, stats.propagations, stats.max_depth), (0, 3, 0));
+    assert_eq!(stats.to_string(), "0 decisions, 3 propagations, 0 pure literals, 0 conflicts, 0 restarts, max depth 0");
+
+    let (_, stats) = solve_dpll_with_stats(parse_str("(a | b) & (a | c)").unwrap(), Assignment::default());
+    assert!(stats.pure_literals > 0);
+
+    let config = SolverConfig { restarts: Some(RestartPolicy::luby(1)), ..Default::default() };
+    let (result, stats) = solve_dpll_with_config_and_stats(pigeonhole(4), Assignment::default(), config);
+    assert!(matches!(result, SolverResult::Unsat));
+    assert!(stats.decisions > 0 && stats.propagations > 0 && stats.restarts > 0);
+    assert!((1..=16).contains(&stats.max_depth));
+}
+
+#[test]
+fn test_progress() {
+    // the search is far from done when it's cancelled after the second snapshot
+    let snapshots = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let cancel = Arc::new(AtomicBool::new(false));
+    let progress = {
+        let (snapshots, cancel) = (snapshots.clone(), cancel.clone());
+        ProgressCallback::new(move |progress| {
+            let mut snapshots = snapshots.lock().unwrap();
+            snapshots.push(*progress);
+            if snapshots.len() >= 2 {
+                cancel.store(true, Ordering::Relaxed);
+            }
+        })
+    };
+    let config = SolverConfig { cancel: Some(cancel), progress: Some(progress), ..Default::default() };
+    assert_eq!(solve_dpll_with_config(pigeonhole(10), Assignment::default(), config), SolverResult::Unknown);
+
+    let snapshots = snapshots.lock().unwrap();
+    assert!(!snapshots.is_empty());
+    assert!(snapshots.windows(2).all(|pair| pair[0].stats.decisions <= pair[1].stats.decisions && pair[0].elapsed < pair[1].elapsed));
+    assert!(snapshots.iter().all(|progress| progress.max_assigned <= progress.num_vars && progress.depth > 0));
+}
+
+#[test]
+fn test_large_random_instance() {
+    // a few thousand clauses, where scanning every clause on each assignment used to dominate
+    let instance = random_ksat(1000, 3000, 3, 0);
+    let config = SolverConfig { heuristic: BranchingHeuristic::Vsids, pure_literals: false, ..Default::default() };
+
+    let start = Instant::now();
+    let (result, stats) = solve_dpll_with_config_and_stats(instance.clone(), Assignment::default(), config);
+    let SolverResult::Sat(Some(assignment)) = result else { panic!("expected a model, got {:?}", result) };
+    assert!(assignment.satisfies_expression(&instance.expression));
+    assert!(stats.propagations > 10 * stats.decisions);
+    assert!(start.elapsed() < Duration::from_secs(30), "took {:?}", start.elapsed());
+}
+
+#[test]
+fn test_vsids_focuses_on_conflicts() {
+    // every variable of a pigeonhole formula is alike, so hide one among unrelated variables: 30
+    // of them plus 20 for 5 pigeons in 4 holes. Random branching keeps deciding unrelated
+    // variables, VSIDS moves the ones taking part in conflicts to the front.
+    let noise = random_ksat_cnf(30, 60, 3, 1);
+    let core = pigeonhole_cnf(4).clauses.into_iter()
+        .map(|clause| Clause::new(clause.literals.into_iter().map(|literal| Literal::new(literal.var_id + 30, literal.value)).collect()));
+    let cnf = CNF::new(noise.clauses.into_iter().chain(core).collect());
+    let instance = SATInstance::new(cnf.to_expression(), (0..50).map(|var| (var, format!("x{}", var))).collect());
+
+    let solve = |heuristic| {
+        let (result, stats) = solve_dpll_with_config_and_stats(instance.clone(), Assignment::default(), SolverConfig { heuristic, ..Default::default() });
+        assert!(matches!(result, SolverResult::Unsat));
+        stats.decisions
+    };
+
+    let (random, vsids) = (solve(BranchingHeuristic::Random), solve(BranchingHeuristic::Vsids));
+    assert!(random > 5 * vsids, "random branching made {} decisions, VSIDS {}", random, vsids);
+}
+
+#[test]
+fn test_deep_search() {
+    // x0 -> x1 -> ... -> x9999 with the ids counting down, so deciding the first unassigned id
+    // never implies anything and every variable gets a decision level of its own. With a recursive
+    // call per decision this overflowed the stack of the test thread.
+    let len: VariableId = 10000;
+    let var = |index: VariableId| Expression::Variable(len - 1 - index);
+    let chain = (0..len - 1)
+        .map(|index| Expression::Or(Box::new(Expression::Not(Box::new(var(index)))), Box::new(var(index + 1))))
+        .collect::<Vec<_>>();
+    let instance = SATInstance::new(Expression::conjunction(chain), (0..len).map(|index| (len - 1 - index, format!("x{}", index))).collect());
+    let config = SolverConfig { heuristic: BranchingHeuristic::FirstUnassigned, pure_literals: false, ..Default::default() };
+
+    let (result, stats) = solve_dpll_with_config_and_stats(instance.clone(), Assignment::default(), config);
+    let SolverResult::Sat(Some(assignment)) = result else { panic!("expected a model, got {:?}", result) };
+    assert!(assignment.satisfies_expression(&instance.expression));
+    assert_eq!(stats.decisions, u64::from(len));
+}
+
+#[test]
+fn test_solve_simplified() {
+    let unsat = parse_str("(a | b) & (a | -b) & (-a | c) & (-a | -c)").unwrap();
+    let tautologies = parse_str("(a | -a) & (b | -b | c)").unwrap();
+
+    for simplify in [false, true] {
+        let config = SolverConfig { simplify, ..Default::default() };
+        let (result, stats) = solve_dpll_with_config_and_stats(unsat.clone(), Assignment::default(), config.clone());
+        assert_eq!(result, SolverResult::Unsat);
+        // the empty clause is there right away
+        assert_eq!(stats.decisions == 0, simplify);
+
+        assert!(matches!(solve_dpll_with_config(tautologies.clone(), Assignment::default(), config), SolverResult::Sat(_)));
+    }
+}
+
+#[test]
+fn test_pure_literals_undone() {
+    // deciding a makes p and e pure, then c and d refute the branch. With a false, (a | -p) needs
+    // p to be false again
+    let instance = parse_str("(a | -p) & (p | e) & (-e | a | c) & (-a | c | d) & (-a | c | -d) & (-a | -c | d) & (-a | -c | -d)").unwrap();
+    let config = SolverConfig { heuristic: BranchingHeuristic::FirstUnassigned, simplify: false, ..Default::default() };
+
+    let (result, stats) = solve_dpll_with_config_and_stats(instance.clone(), Assignment::default(), config.clone());
+    let SolverResult::Sat(Some(model)) = result else { panic!("expected a model, got {:?}", result) };
+    assert_eq!(instance.expression.clone().evaluate(&model), Expression::Constant(true));
+    assert!(!model.values[&instance.str_to_var["a"]] && !model.values[&instance.str_to_var["p"]]);
+    assert!(stats.pure_literals >= 2 && stats.conflicts > 0);
+
+    for seed in 0..50 {
+        let instance = random_ksat(15, 60, 3, seed);
+        match solve_dpll_with_config(instance.clone(), Assignment::default(), config.clone()) {
+            SolverResult::Sat(Some(model)) => assert_eq!(instance.expression.clone().evaluate(&model), Expression::Constant(true), "seed {}", seed),
+            result => assert_eq!(result, solve_cdcl(instance, Assignment::default()), "seed {}", seed),
+        }
+    }
+}
+
+#[test]
+fn test_constant_formulas() {
+    for (formula, satisfiable) in [("1 & (0 | 1)", true), ("1 & 0", false)] {
+        let (result, stats) = solve_dpll_with_stats(parse_str(formula).unwrap(), Assignment::default());
+        assert_eq!(matches!(result, SolverResult::Sat(_)), satisfiable, "{}", formula);
+        assert_eq!(stats, SolverStats::default());
+    }
+
+    let instance = SATInstance::new(Expression::Constant(true), HashMap::new());
+    assert_eq!(solve_dpll(instance.clone(), Assignment::default()), SolverResult::Sat(Some(Assignment::default())));
+    assert_eq!(solve_cdcl(instance, Assignment::default()), SolverResult::Sat(Some(Assignment::default())));
+}
+
+#[test]
+fn test_sparse_variable_ids() {
+    // only variable 40, nothing is interned for 0..40
+    let instance = SATInstance::new(Expression::Not(Box::new(Expression::Variable(40))), HashMap::from([(40, "x".to_string())]));
+    let model = Assignment::from([(40, false)]);
+
+    for heuristic in [BranchingHeuristic::Random, BranchingHeuristic::FirstUnassigned, BranchingHeuristic::MostFrequent, BranchingHeuristic::Vsids] {
+        let config = SolverConfig { heuristic, simplify: false, pure_literals: false, complete_model: true, ..Default::default() };
+        assert_eq!(solve_dpll_with_config(instance.clone(), Assignment::default(), config), SolverResult::Sat(Some(model.clone())));
+    }
+    assert_eq!(solve_cdcl(instance, Assignment::default()), SolverResult::Sat(Some(model)));
+
+    // decisions only ever pick 3 and 9
+    let instance = SATInstance::new(parse_str("(a | b) & (-a | -b)").unwrap().expression.substitute(0, &Expression::Variable(3)).substitute(1, &Expression::Variable(9)), HashMap::new());
+    let config = SolverConfig { heuristic: BranchingHeuristic::FirstUnassigned, pure_literals: false, ..Default::default() };
+    let SolverResult::Sat(Some(model)) = solve_dpll_with_config(instance.clone(), Assignment::default(), config) else { panic!("expected a model") };
+    assert_eq!(model.values.keys().copied().collect::<HashSet<_>>(), HashSet::from([3, 9]));
+    assert!(model.satisfies_expression(&instance.expression));
+}
+
+#[test]
+fn test_unsat_core() {
+    let instance = parse_str("a & -a & (b | c)").unwrap();
+    let (result, core) = solve_dpll_with_core(instance.clone(), Assignment::default());
+    assert_eq!(result, SolverResult::Unsat);
+    let core = core.expect("unsatisfiable instances have a core");
+    assert!(core.contains(&0) && core.contains(&1));
+
+    let cnf = CNF::from_conjuncts(instance.expression.clone());
+    let clauses = core.iter().map(|index| cnf.clauses[*index].clone()).collect();
+    let core_instance = SATInstance::new(CNF::new(clauses).to_expression(), instance.var_to_str.clone());
+    assert_eq!(solve_dpll(core_instance, Assignment::default()), SolverResult::Unsat);
+
+    assert!(solve_dpll_with_core(parse_str("a | b").unwrap(), Assignment::default()).1.is_none());
+}
+
+#[test]
+fn test_unsat_core_irreducible() {
+    // the hard part of an overconstrained problem, with satisfiable clauses mixed in
+    let instance = pigeonhole(3);
+    let hard = CNF::from_conjuncts(instance.expression.clone()).clauses.len();
+    let easy = CNF::new(vec![
+        Clause::new(vec![Literal::new(100, true), Literal::new(101, true)]),
+        Clause::new(vec![Literal::new(100, false), Literal::new(102, true)]),
     ]);
+    let padded = SATInstance::new(Expression::And(Box::new(instance.expression.clone()), Box::new(easy.to_expression())), instance.var_to_str.clone());
+
+    let (_, core) = solve_dpll_with_core(padded.clone(), Assignment::default());
+    let core = core.expect("unsatisfiable instances have a core");
+    assert!(core.iter().all(|index| *index < hard));
+    let cnf = CNF::from_conjuncts(padded.expression);
+    let without = |left_out: Option<usize>| CNF::new(core.iter().filter(|index| Some(**index) != left_out).map(|index| cnf.clauses[*index].clone()).collect());
+
+    assert_eq!(solve_dpll_cnf(without(None), Assignment::default(), &SolverConfig::default()).0, SolverResult::Unsat);
+    for index in &core {
+        assert!(matches!(solve_dpll_cnf(without(Some(*index)), Assignment::default(), &SolverConfig::default()).0, SolverResult::Sat(_)));
+    }
+}
 
-    let mut assignment = Assignment::from([(1, true)]);
+#[test]
+fn test_phase_saving() {
+    // deciding any x true runs into a conflict, after which x is flipped to false. Restarting after
+    // every few conflicts revisits the same variables again and again.
+    let instance = parse_str(&(0..8).map(|i| format!("(-x{i} | y{i}) & (-x{i} | -y{i})")).collect::<Vec<_>>().join(" & ")).unwrap();
+    let config = SolverConfig {
+        heuristic: BranchingHeuristic::FirstUnassigned,
+        pure_literals: false,
+        simplify: false,
+        restarts: Some(RestartPolicy::luby(1)),
+        ..Default::default()
+    };
+
+    // every x conflicts once, afterwards it is decided false right away
+    let (result, stats) = solve_dpll_with_config_and_stats(instance.clone(), Assignment::default(), config.clone());
+    assert!(matches!(result, SolverResult::Sat(Some(model)) if instance.check(&model).is_ok()));
+    assert_eq!(stats.conflicts, 8);
+    assert!(stats.restarts > 0);
+
+    let (result, forgetful) = solve_dpll_with_config_and_stats(instance, Assignment::default(), SolverConfig { phase_saving: false, ..config });
+    assert!(matches!(result, SolverResult::Sat(_)));
+    assert!(forgetful.conflicts > stats.conflicts);
+}
+
+#[test]
+fn test_restarts_agree() {
+    let policies = [RestartPolicy::luby(1), RestartPolicy::luby(4), RestartPolicy::geometric(1, 1.5), RestartPolicy::geometric(3, 2.0)];
+    let instances = (0..16).map(|seed| random_ksat(20, 90, 3, seed)).chain((2..6).map(pigeonhole));
+
+    for instance in instances {
+        let expected = solve_dpll_with_config(instance.clone(), Assignment::default(), SolverConfig { restarts: None, ..Default::default() });
+        for policy in &policies {
+            for restart_counter in [RestartCounter::Conflicts, RestartCounter::Decisions] {
+                let config = SolverConfig { restarts: Some(policy.clone()), restart_counter, ..Default::default() };
+                let (result, stats) = solve_dpll_with_config_and_stats(instance.clone(), Assignment::default(), config);
+                match (&result, &expected) {
+                    (SolverResult::Sat(Some(model)), SolverResult::Sat(_)) => assert!(instance.check(model).is_ok()),
+                    (SolverResult::Unsat, SolverResult::Unsat) => (),
+                    _ => panic!("{:?} counting {:?} gave {:?}, without restarts {:?}", policy, restart_counter, result, expected),
+                }
+                assert!(stats.conflicts_since_restart <= stats.conflicts);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_decision_restarts() {
+    // every clause is satisfied by its first decision, so there are decisions but no conflicts
+    let instance = parse_str(&(0..8).map(|i| format!("(x{i} | y{i})")).collect::<Vec<_>>().join(" & ")).unwrap();
+    let config = SolverConfig {
+        heuristic: BranchingHeuristic::FirstUnassigned,
+        pure_literals: false,
+        restarts: Some(RestartPolicy::luby(1)),
+        ..Default::default()
+    };
 
-    cnf.disable(lit2);
+    let (result, stats) = solve_dpll_with_config_and_stats(instance.clone(), Assignment::default(), config.clone());
+    assert!(matches!(result, SolverResult::Sat(Some(model)) if instance.check(&model).is_ok()));
+    assert_eq!((stats.conflicts, stats.restarts), (0, 0));
 
-    assignment.values.remove(&1);
-    cnf.enable(lit2, &assignment);
+    let config = SolverConfig { restart_counter: RestartCounter::Decisions, ..config };
+    let (result, stats) = solve_dpll_with_config_and_stats(instance.clone(), Assignment::default(), config);
+    assert!(matches!(result, SolverResult::Sat(Some(model)) if instance.check(&model).is_ok()));
+    assert_eq!(stats.conflicts, 0);
+    assert!(stats.restarts > 0);
+    assert!(stats.decisions > 8);
 }
@@ -0,0 +1,135 @@
+// Enumeration of all models of an instance.
+
+use crate::expression::{expression::{Assignment, VariableId}, normal::{Clause, Literal, CNF}};
+
+use super::{dpll::solve_dpll_cnf, instance::{SATInstance, SolverConfig, SolverResult}};
+
+/// Lazily yields the models of a CNF, restricted to a set of variables.
+///
+/// Every yielded assignment is complete on these variables (variables the solver didn't need to
+/// decide are set to false), and no two assignments are identical. After each model, a blocking
+/// clause excluding it is added, so the next call to [Iterator::next] solves the formula again.
+#[derive(Debug, Clone)]
+pub struct ModelIterator {
+    cnf: CNF,
+    variables: Vec<VariableId>,
+    exhausted: bool,
+}
+
+impl ModelIterator {
+    /// Enumerate the models of `cnf` projected onto `variables`.
+    pub fn from_cnf(cnf: CNF, variables: Vec<VariableId>) -> Self {
+        Self { cnf, variables, exhausted: false }
+    }
+}
+
+/// Enumerate all models of `instance` over all of its variables. Auxiliary variables are left out,
+/// they are determined by the others anyway.
+pub fn enumerate_models(instance: SATInstance) -> ModelIterator {
+    let mut variables = instance.var_to_str.keys().filter(|var| !instance.auxiliary.contains(var)).copied().collect::<Vec<_>>();
+    variables.sort();
+
+    enumerate_projected_models(instance, variables)
+}
+
+/// Enumerate the models of `instance` projected onto `variables`, so models only differing in
+/// other variables are reported once.
+pub fn enumerate_projected_models(instance: SATInstance, variables: Vec<VariableId>) -> ModelIterator {
+    ModelIterator::from_cnf(CNF::from(instance.into_clausal().expression), variables)
+}
+
+impl Iterator for ModelIterator {
+    type Item = Assignment;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.exhausted {
+            return None;
+        }
+
+        let assignment = match solve_dpll_cnf(self.cnf.clone(), Assignment::default(), &SolverConfig::default()).0 {
+            SolverResult::Sat(assignment) => assignment.unwrap_or_default(),
+            SolverResult::Unsat => {
+                self.exhausted = true;
+                return None;
+            },
+            SolverResult::Unknown => unreachable!("Solving without a timeout always gives an answer"),
+        };
+
+        // every clause is satisfied by the assigned variables, so any values for the rest work
+        let model = self.variables.iter()
+            .map(|var| (*var, assignment.values.get(var).copied().unwrap_or(false)))
+            .collect();
+        let model = Assignment::new(model);
+
+        let blocking_clause = self.variables.iter().map(|var| Literal::new(*var, !model.values[var])).collect();
+        self.cnf.clauses.push(Clause::new(blocking_clause));
+
+        Some(model)
+    }
+}
+
+#[cfg(test)]
+use std::collections::HashSet;
+
+#[cfg(test)]
+use crate::parser::parse_str;
+
+#[test]
+fn test_enumerate_models() {
+    let instance = parse_str("(a | b | c) & -(a & b & c)").unwrap();
+    let models = enumerate_models(instance.clone()).collect::<Vec<_>>();
+
+    assert_eq!(models.len(), 6);
+    assert_eq!(models.iter().collect::<HashSet<_>>().len(), 6);
+    for model in &models {
+        assert_eq!(model.values.len(), 3);
+        assert!(model.satisfies_expression(&instance.expression));
+    }
+}
+
+#[test]
+fn test_enumerate_expands_dont_cares() {
+    // once a is true, b and c don't matter, but each of their values is a model of its own
+    let instance = parse_str("a | (b & c)").unwrap();
+    let models = enumerate_models(instance.clone()).collect::<Vec<_>>();
+
+    assert_eq!(models.len(), 5);
+    assert_eq!(models.iter().filter(|model| model.values[&instance.str_to_var["a"]]).count(), 4);
+    assert_eq!(enumerate_models(instance).take(2).count(), 2);
+}
+
+#[test]
+fn test_enumerate_models_of_implied_variable() {
+    let instance = parse_str("(a | b) & (a | -b)").unwrap();
+    let (a, b) = (instance.str_to_var["a"], instance.str_to_var["b"]);
+
+    let mut models = enumerate_models(instance).map(|model| (model.values[&a], model.values[&b])).collect::<Vec<_>>();
+    models.sort();
+    assert_eq!(models, [(true, false), (true, true)]);
+}
+
+#[test]
+fn test_enumerate_tseitin_models() {
+    let instance = parse_str("(a & b) | (-a & -b)").unwrap();
+
+    let models = enumerate_models(instance.to_tseitin()).collect::<Vec<_>>();
+    assert_eq!(models.len(), 2);
+    assert!(models.iter().all(|model| model.values.len() == 2));
+}
+
+#[test]
+fn test_enumerate_projected_models() {
+    // c doesn't matter, so there are only as many projected models as there are for a | b
+    let instance = parse_str("(a | b) & (c | -c)").unwrap();
+    let projection = vec![instance.str_to_var["a"], instance.str_to_var["b"]];
+
+    assert_eq!(enumerate_models(instance.clone()).count(), 6);
+    assert_eq!(enumerate_projected_models(instance, projection).count(), 3);
+}
+
+#[test]
+fn test_enumerate_lazily() {
+    let instance = parse_str("a | b | c | d | e | f | g | h").unwrap();
+
+    assert_eq!(enumerate_models(instance).take(3).count(), 3);
+}
@@ -0,0 +1,290 @@
+// Running external solvers on instances, e.g. to cross-check results.
+
+use std::{collections::HashMap, env, error::Error, fmt::Display, fs::{self, File}, io::{self, BufWriter, Read, Write}, path::{Path, PathBuf}, process::{Command, Stdio}, sync::atomic::{AtomicUsize, Ordering}, thread, time::{Duration, Instant}};
+
+use crate::expression::{expression::Assignment, normal::{CnfCache, Literal, CNF}};
+
+use super::{dpll::DpllSolver, instance::{SATInstance, Solver, SolverResult}};
+
+// how often a running child is checked for termination
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// distinguishes the input files of concurrent runs in the same process
+static NEXT_INPUT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A solver binary with the interface of the SAT competition, like MiniSat, Kissat or CaDiCaL.
+///
+/// The binary is invoked as `path args... input.cnf` and has to print an `s` line with the
+/// verdict, followed by `v` lines with the model if it is satisfiable.
+#[derive(Debug, Clone)]
+pub struct ExternalSolver {
+    pub path: PathBuf,
+    pub args: Vec<String>,
+    /// Kill the solver and return [SolverResult::Unknown] when it takes longer than this
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub enum ExternalSolverError {
+    /// The binary couldn't be started, e.g. because it doesn't exist
+    Spawn { path: PathBuf, error: io::Error },
+    /// Writing the input or reading the output failed
+    Io(io::Error),
+    /// A `s` or `v` line isn't well-formed
+    MalformedOutput { line: String },
+    /// The solver exited without printing an `s` line
+    MissingStatus,
+    /// The Tseitin encoding of the instance needs more variables than there are ids
+    OutOfVariableIds,
+}
+
+/// Outcome of [compare_with_external].
+#[derive(Debug, Clone)]
+pub enum Comparison {
+    /// Both solvers gave the same verdict and valid models, or at least one of them gave up
+    Agree,
+    /// One solver found the instance satisfiable and the other one didn't
+    Disagree { ours: SolverResult, theirs: SolverResult },
+    /// A solver reported a model that doesn't satisfy the instance
+    InvalidModel { external: bool, model: Assignment },
+}
+
+// removes the file when dropped, even if the solver fails
+struct TempFile(PathBuf);
+
+impl ExternalSolver {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), args: Vec::new(), timeout: None }
+    }
+
+    // stdout of the solver, or None if it timed out
+    fn run(&self, input: &Path) -> Result<Option<String>, ExternalSolverError> {
+        let mut child = Command::new(&self.path)
+            .args(&self.args)
+            .arg(input)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|error| ExternalSolverError::Spawn { path: self.path.clone(), error })?;
+
+        // read concurrently, a solver printing more than the pipe buffer would block otherwise
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let reader = thread::spawn(move || {
+            let mut output = String::new();
+            stdout.read_to_string(&mut output).map(|_| output)
+        });
+
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        while child.try_wait()?.is_none() {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                child.kill()?;
+                child.wait()?;
+                return Ok(None);
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        Ok(Some(reader.join().expect("The reader thread doesn't panic")?))
+    }
+}
+
+impl Solver for ExternalSolver {
+    type Error = ExternalSolverError;
+
+    fn solve(&mut self, instance: &SATInstance) -> Result<SolverResult, Self::Error> {
+        let input = TempFile::new()?;
+        write_input(instance, &input.0)?;
+
+        match self.run(&input.0)? {
+            Some(output) => parse_output(&output, instance),
+            None => Ok(SolverResult::Unknown),
+        }
+    }
+}
+
+impl TempFile {
+    fn new() -> io::Result<Self> {
+        let id = NEXT_INPUT_ID.fetch_add(1, Ordering::Relaxed);
+        let path = env::temp_dir().join(format!("sat-solver-{}-{}.cnf", std::process::id(), id));
+        File::create(&path)?;
+
+        Ok(Self(path))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+// Tseitin encoding of the instance, auxiliary variables come after the instance's variables
+fn write_input(instance: &SATInstance, path: &Path) -> Result<(), ExternalSolverError> {
+    let max_id = instance.var_to_str.keys().copied().chain(instance.max_variable_id()).max();
+    let first_tseitin_id = max_id.map_or(Some(0), |max| max.checked_add(1)).ok_or(ExternalSolverError::OutOfVariableIds)?;
+    let cnf = CNF::from_expression_with_cache(&instance.expression, &mut CnfCache::new(first_tseitin_id))
+        .ok_or(ExternalSolverError::OutOfVariableIds)?;
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    cnf.to_dimacs(&mut writer)?;
+    Ok(writer.flush()?)
+}
+
+fn parse_output(output: &str, instance: &SATInstance) -> Result<SolverResult, ExternalSolverError> {
+    let malformed = |line: &str| ExternalSolverError::MalformedOutput { line: line.to_string() };
+
+    let mut status = None;
+    let mut values = HashMap::new();
+
+    for line in output.lines() {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("s") => {
+                status = match tokens.collect::<Vec<_>>().as_slice() {
+                    ["SATISFIABLE"] => Some(SolverResult::Sat(None)),
+                    ["UNSATISFIABLE"] => Some(SolverResult::Unsat),
+                    ["UNKNOWN"] => Some(SolverResult::Unknown),
+                    _ => return Err(malformed(line)),
+                };
+            },
+            Some("v") => {
+                for token in tokens {
+                    let number = token.parse::<i64>().map_err(|_| malformed(line))?;
+
+                    // the terminating 0 and auxiliary variables of the encoding aren't part of the model
+                    let literal = Literal::from_dimacs(number).filter(|literal| instance.var_to_str.contains_key(&literal.var_id));
+                    if let Some(literal) = literal {
+                        values.insert(literal.var_id, literal.value);
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+
+    match status {
+        Some(SolverResult::Sat(_)) if !values.is_empty() => Ok(SolverResult::Sat(Some(Assignment::new(values)))),
+        Some(result) => Ok(result),
+        None => Err(ExternalSolverError::MissingStatus),
+    }
+}
+
+/// Solve `instance` with the DPLL solver and the external solver at `path`, and check that the
+/// verdicts agree and that every reported model satisfies the instance.
+pub fn compare_with_external(instance: &SATInstance, path: impl Into<PathBuf>) -> Result<Comparison, ExternalSolverError> {
+    let Ok(ours) = DpllSolver::default().solve(instance);
+    let theirs = ExternalSolver::new(path).solve(instance)?;
+
+    let satisfies = |model: &Assignment| model.satisfies_expression(&instance.expression);
+
+    Ok(match (&ours, &theirs) {
+        (SolverResult::Unknown, _) | (_, SolverResult::Unknown) | (SolverResult::Unsat, SolverResult::Unsat) => Comparison::Agree,
+        (SolverResult::Sat(ours_model), SolverResult::Sat(theirs_model)) => {
+            if let Some(model) = ours_model.as_ref().filter(|model| !satisfies(model)) {
+                Comparison::InvalidModel { external: false, model: model.clone() }
+            } else if let Some(model) = theirs_model.as_ref().filter(|model| !satisfies(model)) {
+                Comparison::InvalidModel { external: true, model: model.clone() }
+            } else {
+                Comparison::Agree
+            }
+        },
+        _ => Comparison::Disagree { ours, theirs },
+    })
+}
+
+impl Display for ExternalSolverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExternalSolverError::Spawn { path, error } => write!(f, "couldn't run '{}': {}", path.display(), error),
+            ExternalSolverError::Io(error) => write!(f, "{}", error),
+            ExternalSolverError::MalformedOutput { line } => write!(f, "malformed solver output '{}'", line),
+            ExternalSolverError::MissingStatus => write!(f, "the solver didn't print a status line"),
+            ExternalSolverError::OutOfVariableIds => write!(f, "ran out of variable ids for the Tseitin encoding"),
+        }
+    }
+}
+
+impl Error for ExternalSolverError {}
+
+impl From<io::Error> for ExternalSolverError {
+    fn from(error: io::Error) -> Self {
+        ExternalSolverError::Io(error)
+    }
+}
+
+#[cfg(test)]
+use crate::{expression::expression::{Expression, VariableId}, parser::parse_str};
+
+#[cfg(test)]
+const FAKE_SOLVER: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/fake-solver.sh");
+
+#[cfg(test)]
+fn fake_solver(mode: &str) -> ExternalSolver {
+    let mut solver = ExternalSolver::new(FAKE_SOLVER);
+    solver.args.push(mode.to_string());
+    solver
+}
+
+#[cfg(unix)]
+#[test]
+fn test_external_solver() {
+    let instance = parse_str("(a | b) & (b | c)").unwrap();
+
+    let SolverResult::Sat(Some(model)) = fake_solver("sat").solve(&instance).unwrap() else {
+        panic!("Expected a model");
+    };
+    let model = model.values.iter().map(|(var, value)| (instance.var_to_str[var].as_str(), *value)).collect::<HashMap<_, _>>();
+    assert_eq!(model, HashMap::from([("a", true), ("b", true), ("c", true)]));
+
+    assert!(matches!(fake_solver("unsat").solve(&instance).unwrap(), SolverResult::Unsat));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_external_solver_timeout() {
+    let instance = parse_str("a").unwrap();
+    let mut solver = fake_solver("sleep");
+    solver.timeout = Some(Duration::from_millis(100));
+
+    let start = Instant::now();
+    assert!(matches!(solver.solve(&instance).unwrap(), SolverResult::Unknown));
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_external_solver_errors() {
+    let instance = parse_str("a").unwrap();
+
+    assert!(matches!(ExternalSolver::new("/nonexistent/solver").solve(&instance), Err(ExternalSolverError::Spawn { .. })));
+    assert!(matches!(fake_solver("garbage").solve(&instance), Err(ExternalSolverError::MalformedOutput { .. })));
+    assert!(matches!(fake_solver("silent").solve(&instance), Err(ExternalSolverError::MissingStatus)));
+
+    // the auxiliary variable of the disjunction would need the id after the largest one
+    let var_to_str = HashMap::from([(0, "a".to_string()), (VariableId::MAX - 1, "b".to_string())]);
+    let instance = SATInstance::new(Expression::or(Expression::var(0), Expression::var(VariableId::MAX - 1)), var_to_str);
+    assert!(matches!(fake_solver("sat").solve(&instance), Err(ExternalSolverError::OutOfVariableIds)));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_compare_with_external() {
+    // the fake solver always reports all variables true
+    assert!(matches!(compare_with_external(&parse_str("(a | b) & c").unwrap(), FAKE_SOLVER).unwrap(), Comparison::Agree));
+    assert!(matches!(compare_with_external(&parse_str("a & -b").unwrap(), FAKE_SOLVER).unwrap(), Comparison::InvalidModel { external: true, .. }));
+    assert!(matches!(compare_with_external(&parse_str("a & -a").unwrap(), FAKE_SOLVER).unwrap(), Comparison::Disagree { .. }));
+}
+
+#[test]
+fn test_competition_round_trip() {
+    let instance = parse_str("(a | b) & (-a | c) & -b").unwrap();
+    let result = super::dpll::solve_dpll(instance.clone(), Assignment::default());
+
+    for result in [result, SolverResult::Sat(None), SolverResult::Unsat, SolverResult::Unknown] {
+        let mut output = Vec::new();
+        result.to_competition(&mut output).unwrap();
+        assert_eq!(parse_output(&String::from_utf8(output).unwrap(), &instance).unwrap(), result);
+    }
+}
@@ -0,0 +1,259 @@
+// Differential testing, running instances through several engines and comparing the answers.
+
+use std::{fmt::Display, time::{Duration, Instant}};
+
+use serde::Serialize;
+
+use crate::expression::expression::Assignment;
+
+use super::{cdcl::solve_cdcl_with_config, dpll::solve_dpll_with_config, external::ExternalSolver, instance::{SATInstance, Solver, SolverConfig, SolverResult}};
+
+type SolveFn = Box<dyn FnMut(&SATInstance, Option<Duration>) -> Result<SolverResult, String>>;
+
+/// A solver taking part in [differential].
+pub struct Engine {
+    pub name: String,
+    /// Whether UNSAT answers are proofs. Engines like local search can only find models, so their
+    /// UNSAT answers just mean that they didn't find one and never count as disagreements.
+    pub proves_unsat: bool,
+    solve: SolveFn,
+}
+
+/// Verdict of one engine on one instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum EngineVerdict {
+    Sat,
+    Unsat,
+    Unknown,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HarnessReport {
+    pub engines: Vec<String>,
+    pub instances: Vec<InstanceReport>,
+    pub disagreements: Vec<Disagreement>,
+    pub invalid_models: Vec<InvalidModel>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceReport {
+    pub name: String,
+    /// One run per engine, in the order of [HarnessReport::engines]
+    pub runs: Vec<EngineRun>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EngineRun {
+    pub verdict: EngineVerdict,
+    /// Wall-clock time in seconds
+    pub time: f64,
+    /// Why the engine failed, if the verdict is `ERROR`
+    pub error: Option<String>,
+}
+
+/// Engines claiming SAT and UNSAT on the same instance.
+#[derive(Debug, Clone, Serialize)]
+pub struct Disagreement {
+    pub instance: String,
+    pub sat: Vec<String>,
+    pub unsat: Vec<String>,
+    /// The engines most likely wrong: those claiming UNSAT if there is a valid model, otherwise
+    /// the minority (both sides on a tie)
+    pub suspects: Vec<String>,
+}
+
+/// A SAT answer without a model satisfying the instance.
+#[derive(Debug, Clone, Serialize)]
+pub struct InvalidModel {
+    pub instance: String,
+    pub engine: String,
+    /// Whether there was no model at all, rather than a wrong one
+    pub missing: bool,
+}
+
+impl Engine {
+    /// An engine solving with `solve`, which is passed the instance and the timeout.
+    pub fn new(name: impl Into<String>, solve: impl FnMut(&SATInstance, Option<Duration>) -> Result<SolverResult, String> + 'static) -> Self {
+        Self { name: name.into(), proves_unsat: true, solve: Box::new(solve) }
+    }
+
+    /// Mark UNSAT answers of `self` as not conclusive.
+    pub fn incomplete(self) -> Self {
+        Self { proves_unsat: false, ..self }
+    }
+
+    /// The DPLL solver with `config`, whose timeout is replaced by the per-instance timeout.
+    pub fn dpll(name: impl Into<String>, config: SolverConfig) -> Self {
+        Self::new(name, move |instance, timeout| {
+            let config = SolverConfig { timeout, ..config.clone() };
+            Ok(solve_dpll_with_config(instance.clone(), Assignment::default(), config))
+        })
+    }
+
+    /// The CDCL solver with the per-instance timeout.
+    pub fn cdcl(name: impl Into<String>) -> Self {
+        Self::new(name, |instance, timeout| {
+            let config = SolverConfig { timeout, ..Default::default() };
+            Ok(solve_cdcl_with_config(instance.clone(), Assignment::default(), &config).0)
+        })
+    }
+
+    /// An external solver, whose timeout is replaced by the per-instance timeout.
+    pub fn external(name: impl Into<String>, mut solver: ExternalSolver) -> Self {
+        Self::new(name, move |instance, timeout| {
+            solver.timeout = timeout;
+            solver.solve(instance).map_err(|err| err.to_string())
+        })
+    }
+}
+
+/// Solve every instance with every engine and compare the verdicts and models.
+pub fn differential(instances: &[(String, SATInstance)], engines: &mut [Engine], per_instance_timeout: Option<Duration>) -> HarnessReport {
+    let mut report = HarnessReport {
+        engines: engines.iter().map(|engine| engine.name.clone()).collect(),
+        instances: Vec::new(),
+        disagreements: Vec::new(),
+        invalid_models: Vec::new(),
+    };
+
+    for (name, instance) in instances {
+        let mut runs = Vec::new();
+        // engines with a conclusive answer, and whether a SAT answer has a valid model
+        let mut sat = Vec::new();
+        let mut unsat = Vec::new();
+        let mut model_found = false;
+
+        for engine in engines.iter_mut() {
+            let start = Instant::now();
+            let result = (engine.solve)(instance, per_instance_timeout);
+            let time = start.elapsed().as_secs_f64();
+
+            let verdict = match &result {
+                Ok(SolverResult::Sat(model)) => {
+                    let valid = model.as_ref().is_some_and(|model| model.satisfies_expression(&instance.expression));
+                    if valid {
+                        model_found = true;
+                    } else {
+                        report.invalid_models.push(InvalidModel { instance: name.clone(), engine: engine.name.clone(), missing: model.is_none() });
+                    }
+                    sat.push(engine.name.clone());
+                    EngineVerdict::Sat
+                },
+                Ok(SolverResult::Unsat) => {
+                    if engine.proves_unsat {
+                        unsat.push(engine.name.clone());
+                    }
+                    EngineVerdict::Unsat
+                },
+                Ok(SolverResult::Unknown) => EngineVerdict::Unknown,
+                Err(_) => EngineVerdict::Error,
+            };
+
+            runs.push(EngineRun { verdict, time, error: result.err() });
+        }
+
+        if !sat.is_empty() && !unsat.is_empty() {
+            let suspects = if model_found || sat.len() > unsat.len() {
+                unsat.clone()
+            } else if unsat.len() > sat.len() {
+                sat.clone()
+            } else {
+                sat.iter().chain(&unsat).cloned().collect()
+            };
+            report.disagreements.push(Disagreement { instance: name.clone(), sat, unsat, suspects });
+        }
+
+        report.instances.push(InstanceReport { name: name.clone(), runs });
+    }
+
+    report
+}
+
+impl Display for EngineVerdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EngineVerdict::Sat => write!(f, "SAT"),
+            EngineVerdict::Unsat => write!(f, "UNSAT"),
+            EngineVerdict::Unknown => write!(f, "UNKNOWN"),
+            EngineVerdict::Error => write!(f, "ERROR"),
+        }
+    }
+}
+
+impl HarnessReport {
+    /// Whether all engines agree and every model is valid.
+    pub fn is_consistent(&self) -> bool {
+        self.disagreements.is_empty() && self.invalid_models.is_empty()
+    }
+}
+
+#[cfg(test)]
+use crate::{generator::pigeonhole, parser::parse_str, solver::heuristics::BranchingHeuristic};
+
+#[cfg(test)]
+fn corpus() -> Vec<(String, SATInstance)> {
+    ["(a | b) & (-a | c) & -c", "a & -a", "(a | b | c) & -(a & b & c)", "(a | b) & (-a | b) & (a | -b) & (-a | -b)"]
+        .into_iter()
+        .map(|formula| (formula.to_string(), parse_str(formula).unwrap()))
+        .collect()
+}
+
+#[cfg(test)]
+fn reference_engines() -> Vec<Engine> {
+    vec![
+        Engine::dpll("vsids", SolverConfig { heuristic: BranchingHeuristic::Vsids, ..Default::default() }),
+        Engine::dpll("jw", SolverConfig { heuristic: BranchingHeuristic::JeroslowWang, ..Default::default() }),
+        Engine::cdcl("cdcl"),
+    ]
+}
+
+#[test]
+fn test_differential_agreement() {
+    let mut engines = reference_engines();
+    // never finds anything, like local search on an unsatisfiable instance
+    engines.push(Engine::new("give-up", |_, _| Ok(SolverResult::Unsat)).incomplete());
+
+    let report = differential(&corpus(), &mut engines, Some(Duration::from_secs(10)));
+
+    assert!(report.is_consistent(), "{:?}", report);
+    assert_eq!(report.engines, ["vsids", "jw", "cdcl", "give-up"]);
+    let verdicts = report.instances.iter().map(|instance| instance.runs[0].verdict).collect::<Vec<_>>();
+    assert_eq!(verdicts, [EngineVerdict::Sat, EngineVerdict::Unsat, EngineVerdict::Sat, EngineVerdict::Unsat]);
+}
+
+#[test]
+fn test_differential_disagreement() {
+    let mut engines = reference_engines();
+    // claims the opposite of the truth, with a wrong model for unsatisfiable instances
+    engines.push(Engine::new("liar", |instance, timeout| {
+        Ok(match solve_dpll_with_config(instance.clone(), Assignment::default(), SolverConfig { timeout, ..Default::default() }) {
+            SolverResult::Sat(_) => SolverResult::Unsat,
+            _ => SolverResult::Sat(Some(Assignment::default())),
+        })
+    }));
+    engines.push(Engine::new("broken", |_, _| Err("crashed".to_string())));
+
+    let report = differential(&corpus(), &mut engines, None);
+
+    assert_eq!(report.disagreements.len(), 4);
+    assert!(report.disagreements.iter().all(|disagreement| disagreement.suspects == ["liar"]), "{:?}", report.disagreements);
+    assert_eq!(report.invalid_models.iter().map(|invalid| (invalid.instance.as_str(), invalid.engine.as_str())).collect::<Vec<_>>(), [
+        ("a & -a", "liar"),
+        ("(a | b) & (-a | b) & (a | -b) & (-a | -b)", "liar"),
+    ]);
+    assert!(report.instances.iter().all(|instance| instance.runs[4].verdict == EngineVerdict::Error && instance.runs[4].error.as_deref() == Some("crashed")));
+}
+
+#[test]
+fn test_differential_timeout() {
+    let instances = [("php-10".to_string(), pigeonhole(10))];
+    let report = differential(&instances, &mut reference_engines(), Some(Duration::from_millis(100)));
+
+    assert!(report.is_consistent());
+    for run in &report.instances[0].runs {
+        assert_eq!(run.verdict, EngineVerdict::Unknown);
+        assert!(run.time < 5.0, "{:?}", run);
+    }
+}
@@ -0,0 +1,132 @@
+// Branching heuristics choosing the next decision variable of the DPLL solver.
+
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use crate::expression::expression::{Assignment, VariableId};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BranchingHeuristic {
+    /// A random unassigned variable, seeded by [SolverConfig::seed](super::instance::SolverConfig::seed)
+    #[default]
+    Random,
+    /// The unassigned variable with the lowest id
+    FirstUnassigned,
+    /// The literal occurring most often in clauses that aren't satisfied yet (DLIS)
+    MostFrequent,
+    /// The literal occurring most often in short clauses, weighted by 2^-length
+    JeroslowWang,
+    /// The variable with the highest activity, which is increased whenever the variable is part
+    /// of a conflict and decays over time
+    Vsids,
+}
+
+const VSIDS_DECAY: f64 = 0.95;
+
+/// Activity scores for VSIDS in a max-heap.
+///
+/// Instead of updating entries in place, bumping a variable pushes a new entry and entries with an
+/// outdated activity are dropped lazily when they reach the top.
+#[derive(Debug, Clone)]
+pub struct VsidsHeap {
+    activity: Vec<f64>,
+    increment: f64,
+    heap: BinaryHeap<HeapEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct HeapEntry {
+    activity: f64,
+    var: VariableId,
+}
+
+impl Eq for HeapEntry {}
+
+impl PartialOrd for HeapEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HeapEntry {
+    // higher activity first, lower variable id on ties
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.activity.total_cmp(&other.activity).then_with(|| other.var.cmp(&self.var))
+    }
+}
+
+impl VsidsHeap {
+    /// Heap of `variables`, the only ones ever picked.
+    pub fn new(variables: impl IntoIterator<Item = VariableId>) -> Self {
+        let heap = variables.into_iter().map(|var| HeapEntry { activity: 0.0, var }).collect::<BinaryHeap<_>>();
+        let len = heap.iter().map(|entry| usize::from(entry.var) + 1).max().unwrap_or(0);
+        Self { activity: vec![0.0; len], increment: 1.0, heap }
+    }
+
+    pub fn bump(&mut self, var: VariableId) {
+        let activity = &mut self.activity[usize::from(var)];
+        *activity += self.increment;
+        let activity = *activity;
+
+        if activity > 1e100 {
+            // rescale before running out of precision, which invalidates every entry
+            for activity in self.activity.iter_mut() {
+                *activity *= 1e-100;
+            }
+            self.increment *= 1e-100;
+            let mut variables = self.heap.drain().map(|entry| entry.var).collect::<Vec<_>>();
+            variables.sort_unstable();
+            variables.dedup();
+            self.heap = variables.into_iter().map(|var| HeapEntry { activity: self.activity[usize::from(var)], var }).collect();
+        } else {
+            self.heap.push(HeapEntry { activity, var });
+        }
+    }
+
+    /// Decay all activities. Instead of scaling every activity down, future bumps are scaled up,
+    /// which keeps the order the same.
+    pub fn decay_all(&mut self) {
+        self.increment /= VSIDS_DECAY;
+    }
+
+    /// The unassigned variable with the highest activity. Takes `&mut self` to drop outdated
+    /// entries on the way.
+    pub fn pick_unassigned(&mut self, assignment: &Assignment) -> Option<VariableId> {
+        let mut assigned = Vec::new();
+
+        let picked = loop {
+            let Some(entry) = self.heap.pop() else {
+                break None;
+            };
+
+            if entry.activity != self.activity[usize::from(entry.var)] {
+                continue;
+            }
+
+            // keep the entry, the variable can become unassigned again on backtrack
+            assigned.push(entry);
+            if !assignment.values.contains_key(&entry.var) {
+                break Some(entry.var);
+            }
+        };
+
+        self.heap.extend(assigned);
+        picked
+    }
+}
+
+#[test]
+fn test_vsids_heap() {
+    let mut heap = VsidsHeap::new(0..=3);
+    assert_eq!(heap.pick_unassigned(&Assignment::default()), Some(0));
+
+    heap.bump(2);
+    heap.decay_all();
+    heap.bump(1);
+    assert_eq!(heap.pick_unassigned(&Assignment::default()), Some(1));
+    assert_eq!(heap.pick_unassigned(&Assignment::from([(1, true)])), Some(2));
+    assert_eq!(heap.pick_unassigned(&Assignment::from([(1, true), (2, false)])), Some(0));
+    assert_eq!(heap.pick_unassigned(&Assignment::from([(0, true), (1, true), (2, false), (3, true)])), None);
+
+    // entries of assigned variables aren't lost
+    assert_eq!(heap.pick_unassigned(&Assignment::default()), Some(1));
+}
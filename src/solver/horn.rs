@@ -0,0 +1,103 @@
+// Horn satisfiability in linear time.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::expression::{expression::{Assignment, VariableId}, normal::CNF};
+
+use super::instance::{SATInstance, SolverResult};
+
+/// Solve an instance whose CNF only has Horn clauses, see [CNF::is_horn].
+///
+/// Every variable starts out false and only becomes true if a clause forces it: once all negative
+/// literals of a clause are false, its positive literal has to be true, and without one the
+/// instance is unsatisfiable. This is unit propagation without any decisions, so it takes time
+/// linear in the size of the CNF. The model has as few true variables as possible.
+///
+/// Panics if the CNF isn't Horn.
+pub fn solve_horn(instance: SATInstance) -> SolverResult {
+    let variables = instance.var_to_str.keys().filter(|var| !instance.auxiliary.contains(var)).copied().collect::<Vec<_>>();
+    let cnf = CNF::from(instance.expression);
+    assert!(cnf.is_horn(), "Clauses with more than one positive literal aren't Horn clauses");
+
+    // for every clause, how many of its negative literals aren't false yet
+    let mut remaining = cnf.clauses.iter()
+        .map(|clause| clause.literals.iter().filter(|literal| !literal.value).count())
+        .collect::<Vec<_>>();
+    // the clauses every variable occurs negated in
+    let mut negated_in: HashMap<VariableId, Vec<usize>> = HashMap::new();
+    for (index, clause) in cnf.clauses.iter().enumerate() {
+        for literal in clause.literals.iter().filter(|literal| !literal.value) {
+            negated_in.entry(literal.var_id).or_default().push(index);
+        }
+    }
+
+    // clauses whose negative literals are all false
+    let mut forcing = (0..cnf.clauses.len()).filter(|index| remaining[*index] == 0).collect::<Vec<_>>();
+    let mut true_variables = HashSet::new();
+
+    while let Some(index) = forcing.pop() {
+        let Some(positive) = cnf.clauses[index].literals.iter().find(|literal| literal.value) else {
+            return SolverResult::Unsat;
+        };
+        if !true_variables.insert(positive.var_id) {
+            continue;
+        }
+
+        for clause in negated_in.get(&positive.var_id).into_iter().flatten() {
+            remaining[*clause] -= 1;
+            if remaining[*clause] == 0 {
+                forcing.push(*clause);
+            }
+        }
+    }
+
+    let model = variables.into_iter().map(|var| (var, true_variables.contains(&var))).collect();
+    SolverResult::Sat(Some(Assignment::new(model)))
+}
+
+#[cfg(test)]
+use crate::{expression::normal::{Clause, Literal}, generator::random_ksat_cnf, parser::parse_str, solver::dpll::solve_dpll};
+
+#[test]
+fn test_solve_horn() {
+    // a, a -> b, a & b -> c
+    let instance = parse_str("a & (-a | b) & (-a | -b | c) & (-d | a)").unwrap();
+    let SolverResult::Sat(Some(model)) = solve_horn(instance.clone()) else { panic!("expected a model") };
+    assert!(model.satisfies_expression(&instance.expression));
+    assert_eq!(["a", "b", "c", "d"].map(|name| model.values[&instance.str_to_var[name]]), [true, true, true, false]);
+
+    assert_eq!(solve_horn(parse_str("a & (-a | b) & (-b | -a)").unwrap()), SolverResult::Unsat);
+    assert_eq!(solve_horn(parse_str("(-a | -b) & 0").unwrap()), SolverResult::Unsat);
+    assert_eq!(solve_horn(parse_str("-a | -b").unwrap()), SolverResult::Sat(Some(Assignment::from([(0, false), (1, false)]))));
+}
+
+#[test]
+#[should_panic(expected = "aren't Horn clauses")]
+fn test_solve_horn_not_horn() {
+    solve_horn(parse_str("a | b").unwrap());
+}
+
+#[test]
+fn test_solve_horn_agrees_with_dpll() {
+    for seed in 0..200 {
+        // facts and rules, every positive literal but the first one is negated
+        let facts = random_ksat_cnf(12, 4, 1, seed).clauses;
+        let rules = random_ksat_cnf(12, 30, 3, seed).clauses.into_iter().map(|clause| {
+            let first_positive = clause.literals.iter().position(|literal| literal.value);
+            Clause::new(clause.literals.into_iter().enumerate()
+                .map(|(index, literal)| Literal::new(literal.var_id, literal.value && Some(index) == first_positive))
+                .collect())
+        });
+        let cnf = CNF::new(facts.into_iter().chain(rules).collect());
+        assert!(cnf.is_horn());
+
+        let instance = SATInstance::new(cnf.to_expression(), (0..12).map(|var| (var, format!("x{}", var))).collect());
+        match solve_horn(instance.clone()) {
+            SolverResult::Sat(Some(model)) => {
+                assert!(model.satisfies_expression(&instance.expression), "seed {}", seed);
+                assert!(matches!(solve_dpll(instance, Assignment::default()), SolverResult::Sat(_)), "seed {}", seed);
+            },
+            result => assert_eq!(solve_dpll(instance, Assignment::default()), result, "seed {}", seed),
+        }
+    }
+}
@@ -0,0 +1,195 @@
+// Solving a changing set of clauses without converting the instance again every time.
+
+use std::collections::HashSet;
+
+use crate::expression::{expression::{Assignment, VariableId}, normal::{Clause, Literal, CNF}};
+
+use super::{dpll::{solve_dpll_clauses, DpllCNF, DpllClause}, instance::{SATInstance, SolverConfig, SolverResult, SolverStats}};
+
+/// Refers to a clause added to an [IncrementalSolver], so it can be retracted later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClauseHandle(usize);
+
+/// DPLL solver for an instance with clauses added and retracted between calls to
+/// [IncrementalSolver::solve].
+///
+/// The instance is converted to CNF once. Every added clause gets a selector variable `s` and is
+/// stored as `clause | -s`, so assuming `s` enables the clause and assuming `-s` retracts it
+/// without touching the clauses themselves.
+#[derive(Debug)]
+pub struct IncrementalSolver {
+    pub config: SolverConfig,
+    cnf: DpllCNF,
+    /// Variables of the instance that show up in models
+    variables: HashSet<VariableId>,
+    /// Selector variables of the added clauses and whether the clause is still active, indexed by
+    /// [ClauseHandle]
+    selectors: Vec<(VariableId, bool)>,
+    /// Ids below belong to the instance
+    first_free: VariableId,
+    /// Selector variables and variables from [IncrementalSolver::add_variable] get ids from here on
+    next_id: VariableId,
+    assumptions: Vec<Literal>,
+}
+
+impl IncrementalSolver {
+    pub fn new(instance: SATInstance) -> Self {
+        let instance = instance.into_clausal();
+        let first_free = instance.var_to_str.keys().max().map_or(0, |max| max.checked_add(1).expect("Ran out of variable ids"));
+        let variables = instance.var_to_str.keys().filter(|var| !instance.auxiliary.contains(var)).copied().collect();
+
+        Self {
+            config: SolverConfig::default(),
+            cnf: DpllCNF::from(CNF::from(instance.expression)),
+            variables,
+            selectors: Vec::new(),
+            first_free,
+            next_id: first_free,
+            assumptions: Vec::new(),
+        }
+    }
+
+    /// A new variable of the instance, which shows up in models once a clause uses it.
+    pub fn add_variable(&mut self) -> VariableId {
+        let var = self.next_variable_id();
+        self.variables.insert(var);
+        var
+    }
+
+    /// Add `clause` to all following solves until it is retracted. Its literals have to refer to
+    /// variables of the instance.
+    pub fn add_clause(&mut self, clause: Clause) -> ClauseHandle {
+        let is_instance_variable = |var: VariableId| var < self.first_free || self.variables.contains(&var);
+        assert!(clause.literals.iter().all(|literal| is_instance_variable(literal.var_id)), "Added clauses can only use variables of the instance");
+
+        let selector = self.next_variable_id();
+
+        let mut literals = clause.literals;
+        literals.push(Literal::new(selector, false));
+        self.cnf.add_clause(DpllClause::new(literals));
+        self.selectors.push((selector, true));
+
+        ClauseHandle(self.selectors.len() - 1)
+    }
+
+    /// Remove a clause added with [IncrementalSolver::add_clause]. Retracting it again does
+    /// nothing.
+    pub fn retract_clause(&mut self, handle: ClauseHandle) {
+        self.selectors[handle.0].1 = false;
+    }
+
+    /// Require `literal` to be true in the following solves until the assumptions are cleared.
+    pub fn assume(&mut self, literal: Literal) {
+        self.assumptions.push(literal);
+    }
+
+    pub fn clear_assumptions(&mut self) {
+        self.assumptions.clear();
+    }
+
+    /// Solve the instance with the active clauses under the current assumptions. Models only
+    /// contain variables of the instance.
+    pub fn solve(&mut self) -> SolverResult {
+        self.solve_with_stats().0
+    }
+
+    /// Like [IncrementalSolver::solve], but also returns statistics about the search.
+    pub fn solve_with_stats(&mut self) -> (SolverResult, SolverStats) {
+        let mut assignment = Assignment::new(self.selectors.iter().copied().collect());
+        for literal in &self.assumptions {
+            if assignment.values.insert(literal.var_id, literal.value) == Some(!literal.value) {
+                return (SolverResult::Unsat, SolverStats::default());
+            }
+        }
+
+        match solve_dpll_clauses(&mut self.cnf, assignment, &self.config) {
+            (SolverResult::Sat(Some(mut model)), stats) => {
+                model.values.retain(|var, _| self.variables.contains(var));
+                (SolverResult::Sat(Some(model)), stats)
+            },
+            result => result,
+        }
+    }
+
+    fn next_variable_id(&mut self) -> VariableId {
+        let var = self.next_id;
+        self.next_id = var.checked_add(1).expect("Ran out of variable ids");
+        var
+    }
+}
+
+#[cfg(test)]
+use crate::parser::parse_str;
+
+#[test]
+fn test_incremental_solver() {
+    let instance = parse_str("a | b | c").unwrap();
+    let [a, b, c] = ["a", "b", "c"].map(|name| instance.str_to_var[name]);
+    let mut solver = IncrementalSolver::new(instance);
+
+    let clauses = [
+        vec![Literal::new(a, true), Literal::new(b, true)],
+        vec![Literal::new(a, false), Literal::new(c, true)],
+        vec![Literal::new(b, false), Literal::new(c, true)],
+        vec![Literal::new(a, true), Literal::new(b, true), Literal::new(c, false)],
+        vec![Literal::new(c, true)],
+    ];
+    let cnf = CNF::new(clauses.iter().cloned().map(Clause::new).collect());
+    for clause in cnf.clauses.clone() {
+        solver.add_clause(clause);
+    }
+    let SolverResult::Sat(Some(model)) = solver.solve() else { panic!("expected a model") };
+    assert!(model.satisfies_cnf(&cnf));
+    assert!(model.values.keys().all(|var| [a, b, c].contains(var)));
+
+    let contradiction = solver.add_clause(Clause::new(vec![Literal::new(c, false)]));
+    assert!(matches!(solver.solve(), SolverResult::Unsat));
+
+    solver.retract_clause(contradiction);
+    let SolverResult::Sat(Some(model)) = solver.solve() else { panic!("expected a model") };
+    assert!(model.satisfies_cnf(&cnf));
+}
+
+#[test]
+fn test_incremental_assumptions() {
+    let instance = parse_str("(a | b) & (-a | c)").unwrap();
+    let [a, b, c] = ["a", "b", "c"].map(|name| instance.str_to_var[name]);
+    let mut solver = IncrementalSolver::new(instance);
+
+    solver.assume(Literal::new(a, true));
+    solver.assume(Literal::new(c, false));
+    assert!(matches!(solver.solve(), SolverResult::Unsat));
+
+    solver.clear_assumptions();
+    solver.assume(Literal::new(a, false));
+    assert!(matches!(solver.solve(), SolverResult::Sat(Some(model)) if model.values[&b]));
+
+    // contradicting assumptions
+    solver.assume(Literal::new(a, true));
+    assert!(matches!(solver.solve(), SolverResult::Unsat));
+
+    solver.clear_assumptions();
+    solver.add_clause(Clause::new(vec![]));
+    assert!(matches!(solver.solve(), SolverResult::Unsat));
+}
+
+#[test]
+fn test_incremental_new_variables() {
+    let instance = parse_str("a | b").unwrap();
+    let [a, b] = ["a", "b"].map(|name| instance.str_to_var[name]);
+    let mut solver = IncrementalSolver::new(instance);
+
+    solver.add_clause(Clause::new(vec![Literal::new(a, false)]));
+    let c = solver.add_variable();
+    solver.add_clause(Clause::new(vec![Literal::new(b, false), Literal::new(c, true)]));
+    let d = solver.add_variable();
+    assert!(![a, b, c].contains(&d));
+
+    let (result, stats) = solver.solve_with_stats();
+    let SolverResult::Sat(Some(model)) = result else { panic!("expected a model") };
+    assert_eq!((model.values[&a], model.values[&b], model.values[&c]), (false, true, true));
+    assert!(stats.propagations > 0);
+
+    solver.assume(Literal::new(c, false));
+    assert!(matches!(solver.solve(), SolverResult::Unsat));
+}
@@ -1,20 +1,180 @@
 // SAT problem instance and solution representation.
 
-use std::{collections::HashMap, fmt::Display};
+use std::{collections::{BTreeMap, HashMap, HashSet}, error::Error, fmt::{Debug, Display, Write}, io, sync::{atomic::AtomicBool, Arc}, time::Duration};
 
-use crate::expression::expression::{Assignment, Expression, VariableId};
+use crate::expression::{expression::{Assignment, Expression, ExpressionDisplay, VariableId}, normal::{to_cnf_tseitin, Clause, Literal, CNF}};
+
+use super::{heuristics::BranchingHeuristic, restart::{RestartCounter, RestartPolicy}};
 
 #[derive(Debug, Clone)]
 pub struct SATInstance {
     pub expression: Expression,
     pub var_to_str: HashMap<VariableId, String>,
     pub str_to_var: HashMap<String, VariableId>,
+    /// Variables introduced by encodings like [SATInstance::to_tseitin], which are left out of
+    /// models
+    pub auxiliary: HashSet<VariableId>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SolverResult {
     Sat(Option<Assignment>),
-    Unsat
+    Unsat,
+    /// The solver gave up before finding an answer, e.g. because of a timeout
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+pub struct SolverConfig {
+    pub heuristic: BranchingHeuristic,
+    /// Seed for all random choices, so runs with the same seed are identical
+    pub seed: u64,
+    /// Assign literals that only occur with one polarity
+    pub pure_literals: bool,
+    /// Simplify the CNF before solving, see [CNF::simplify]
+    pub simplify: bool,
+    /// Go back to the root and start deciding again after the number of conflicts given by the
+    /// schedule, or decisions depending on `restart_counter`
+    pub restarts: Option<RestartPolicy>,
+    pub restart_counter: RestartCounter,
+    /// Decide variables with the value they had last, before backtracking or restarting, instead of
+    /// trying true first. Heuristics choosing literals rather than variables pick the value
+    /// themselves.
+    pub phase_saving: bool,
+    /// Return [SolverResult::Unknown] once this many decisions were made
+    pub max_decisions: Option<u64>,
+    /// Return [SolverResult::Unknown] when solving takes longer than this
+    pub timeout: Option<Duration>,
+    /// Return [SolverResult::Unknown] as soon as this is set, e.g. from another thread
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Called a few times per second during the search
+    pub progress: Option<ProgressCallback>,
+    /// Set the variables the search didn't need to decide to false instead of leaving them out of
+    /// the model
+    pub complete_model: bool,
+}
+
+/// Receives [SolverProgress] snapshots of a running search.
+#[derive(Clone)]
+pub struct ProgressCallback(pub Arc<dyn Fn(&SolverProgress) + Send + Sync>);
+
+/// Snapshot of a running search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SolverProgress {
+    pub stats: SolverStats,
+    pub elapsed: Duration,
+    /// Number of decisions on the current branch
+    pub depth: u64,
+    /// Most variables assigned at once so far
+    pub max_assigned: usize,
+    pub num_vars: usize,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        Self { heuristic: BranchingHeuristic::default(), seed: 0, pure_literals: true, simplify: true, restarts: None, restart_counter: RestartCounter::Conflicts, phase_saving: true, max_decisions: None, timeout: None, cancel: None, progress: None, complete_model: false }
+    }
+}
+
+impl SolverResult {
+    /// The model if the instance is satisfiable and the solver returned one.
+    pub fn model(&self) -> Option<&Assignment> {
+        match self {
+            SolverResult::Sat(Some(model)) => Some(model),
+            _ => None,
+        }
+    }
+
+    /// Write `self` like a SAT competition solver: an `s` line with the verdict and, if there is a
+    /// model, a `v` line with its literals in DIMACS numbering (see [Literal::to_dimacs]) ending in
+    /// 0.
+    pub fn to_competition(&self, w: &mut impl io::Write) -> io::Result<()> {
+        match self {
+            SolverResult::Sat(model) => {
+                writeln!(w, "s SATISFIABLE")?;
+
+                if let Some(model) = model {
+                    let mut literals = model.values.iter().map(|(var, value)| Literal::new(*var, *value)).collect::<Vec<_>>();
+                    literals.sort_by_key(|literal| literal.var_id);
+
+                    write!(w, "v")?;
+                    for literal in literals {
+                        write!(w, " {}", literal.to_dimacs())?;
+                    }
+                    writeln!(w, " 0")?;
+                }
+
+                Ok(())
+            },
+            SolverResult::Unsat => writeln!(w, "s UNSATISFIABLE"),
+            SolverResult::Unknown => writeln!(w, "s UNKNOWN"),
+        }
+    }
+}
+
+impl ProgressCallback {
+    pub fn new(callback: impl Fn(&SolverProgress) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+}
+
+impl Debug for ProgressCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ProgressCallback").finish_non_exhaustive()
+    }
+}
+
+/// A SAT solver backend, so applications can swap engines or run several on the same instance.
+pub trait Solver {
+    type Error;
+
+    fn solve(&mut self, instance: &SATInstance) -> Result<SolverResult, Self::Error>;
+}
+
+/// Why an assignment isn't a model of an instance, see [SATInstance::check].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelError {
+    /// Clauses of the instance in CNF whose literals are all false
+    Falsified(Vec<Clause>),
+    /// Nothing is falsified, but the formula depends on these variables without a value
+    Unassigned(Vec<VariableId>),
+}
+
+/// An assignment in terms of variable names, displayed as `name = value` lines sorted by name.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NamedAssignment(pub BTreeMap<String, bool>);
+
+/// Displays an assignment as `{ a = true, d = false }`, see [Assignment::display_with_names].
+pub struct AssignmentDisplay<'a> {
+    assignment: &'a Assignment,
+    instance: Option<&'a SATInstance>,
+}
+
+/// Displays a result as `SAT: { a = true, ... }`, just `SAT` without a model, `UNSAT` or
+/// `UNKNOWN`, see [SolverResult::display_with_names].
+pub struct SolverResultDisplay<'a> {
+    result: &'a SolverResult,
+    instance: Option<&'a SATInstance>,
+}
+
+/// A name passed to [Assignment::from_names] isn't a variable of the instance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownVariable(pub String);
+
+/// Counters describing the work done by the solver.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SolverStats {
+    pub decisions: u64,
+    /// Literals assigned by unit propagation
+    pub propagations: u64,
+    /// Literals assigned because they only occur with one polarity
+    pub pure_literals: u64,
+    pub conflicts: u64,
+    pub restarts: u64,
+    /// Conflicts after the last restart, all of them without restarts
+    pub conflicts_since_restart: u64,
+    /// Most decisions on a single branch
+    pub max_depth: u64,
 }
 
 impl SATInstance {
@@ -23,14 +183,552 @@ impl SATInstance {
         for (var, str) in var_to_str.iter() {
             str_to_var.insert(str.clone(), *var);
         }
-        Self { expression, var_to_str, str_to_var }
+        Self { expression, var_to_str, str_to_var, auxiliary: HashSet::new() }
+    }
+
+    /// Instance of `expression` in CNF using [to_cnf_tseitin]. The auxiliary variables get ids
+    /// after the ones in `var_names` and `expression`, are named `_t0`, `_t1`, ... and are left out
+    /// of models.
+    pub fn from_expression_tseitin(expression: Expression, var_names: HashMap<VariableId, String>) -> SATInstance {
+        let max_id = var_names.keys().copied().chain(expression.variables()).max();
+        let Some(mut next_id) = max_id.map_or(Some(0), |max| max.checked_add(1)) else {
+            // no ids left for auxiliary variables
+            return SATInstance::new(CNF::from(expression).to_expression(), var_names);
+        };
+
+        let (cnf, auxiliary) = to_cnf_tseitin(&expression, &mut next_id);
+
+        let mut instance = SATInstance::new(cnf.to_expression(), var_names);
+        for (var, name) in auxiliary {
+            instance.auxiliary.insert(var);
+            instance.str_to_var.insert(name.clone(), var);
+            instance.var_to_str.insert(var, name);
+        }
+
+        instance
+    }
+
+    /// Equisatisfiable instance in CNF, see [SATInstance::from_expression_tseitin].
+    pub fn to_tseitin(&self) -> SATInstance {
+        let mut instance = SATInstance::from_expression_tseitin(self.expression.clone(), self.var_to_str.clone());
+        instance.auxiliary.extend(&self.auxiliary);
+        instance
+    }
+
+    /// `self` if its expression is already a conjunction of clauses, otherwise
+    /// [SATInstance::to_tseitin]. Solvers convert the expression with [CNF::from], which takes
+    /// exponential time and space on anything else, e.g. parity.
+    pub fn into_clausal(self) -> SATInstance {
+        match self.expression.is_clausal() {
+            true => self,
+            false => self.to_tseitin(),
+        }
+    }
+
+    /// Check that `assignment` satisfies the expression of `self`, independently of any solver.
+    /// Variables without a value are fine as long as the expression doesn't depend on them.
+    /// Auxiliary variables are part of the expression, so models of encoded instances have to be
+    /// checked before they are left out.
+    pub fn check(&self, assignment: &Assignment) -> Result<(), ModelError> {
+        match self.expression.clone().evaluate(assignment) {
+            Expression::Constant(true) => Ok(()),
+            Expression::Constant(false) => {
+                let falsified = CNF::from(self.expression.clone()).clauses.into_iter()
+                    .filter(|clause| clause.literals.iter().all(|literal| assignment.values.get(&literal.var_id) == Some(&!literal.value)))
+                    .collect();
+                Err(ModelError::Falsified(falsified))
+            },
+            residual => {
+                let mut unassigned = residual.variables().into_iter().collect::<Vec<_>>();
+                unassigned.sort();
+                Err(ModelError::Unassigned(unassigned))
+            },
+        }
+    }
+
+    /// `assignment` with the variable names of `self`. Variables without a name are called `v<id>`.
+    pub fn name_assignment(&self, assignment: &Assignment) -> NamedAssignment {
+        NamedAssignment(assignment.values.iter()
+            .map(|(var, value)| (self.var_to_str.get(var).cloned().unwrap_or_else(|| format!("v{}", var)), *value))
+            .collect())
+    }
+
+    /// The largest variable id occurring in the expression of `self`, `None` if it is constant.
+    /// Ids don't have to be dense, so this isn't necessarily the number of variables minus one.
+    pub fn max_variable_id(&self) -> Option<VariableId> {
+        self.expression.variables().into_iter().max()
+    }
+
+    /// `self` with the variables of `assignment` fixed to their values and the expression
+    /// evaluated as far as possible. The variables that are left keep their names, but get the ids
+    /// 0, 1, ... in the order of their old ids. A constant expression leaves no variables at all.
+    pub fn apply_partial(&self, assignment: &Assignment) -> SATInstance {
+        let expression = self.expression.clone().evaluate(assignment);
+        let mut remaining = expression.variables().into_iter().collect::<Vec<_>>();
+        remaining.sort();
+
+        let ids = remaining.into_iter().enumerate()
+            .map(|(id, var)| (var, VariableId::try_from(id).expect("There are fewer variables than before")))
+            .collect::<HashMap<_, _>>();
+        let var_to_str = ids.iter()
+            .map(|(var, id)| (*id, self.var_to_str.get(var).cloned().unwrap_or_else(|| format!("v{}", var))))
+            .collect();
+
+        let mut instance = SATInstance::new(rename_variables(expression, &ids), var_to_str);
+        instance.auxiliary = self.auxiliary.iter().filter_map(|var| ids.get(var)).copied().collect();
+        instance
+    }
+
+    /// `assignment` without the auxiliary variables of `self`.
+    pub fn without_auxiliary(&self, mut assignment: Assignment) -> Assignment {
+        assignment.values.retain(|var, _| !self.auxiliary.contains(var));
+        assignment
+    }
+}
+
+impl SATInstance {
+    /// `self` in DIMACS CNF format, converted with [CNF::from], see [Literal::to_dimacs]. The
+    /// `c` lines before the header map the numbers back to the variable names.
+    pub fn to_dimacs(&self) -> String {
+        let mut output = String::new();
+
+        // constants are folded away, unless the whole expression is one
+        let cnf = match self.expression.clone().evaluate(&Assignment::default()) {
+            Expression::Constant(true) => return "p cnf 0 0\n".to_string(),
+            Expression::Constant(false) => return "p cnf 0 1\n0\n".to_string(),
+            expression => CNF::from(expression),
+        };
+
+        let mut variables = self.var_to_str.iter().collect::<Vec<_>>();
+        variables.sort();
+        for (var, name) in variables {
+            writeln!(output, "c {} {}", Literal::new(*var, true).to_dimacs(), name).expect("Writing to a String doesn't fail");
+        }
+
+        let num_vars = self.var_to_str.keys().map(|var| Literal::new(*var, true).to_dimacs()).max().unwrap_or(0);
+        writeln!(output, "p cnf {} {}", num_vars, cnf.clauses.len()).expect("Writing to a String doesn't fail");
+        for clause in &cnf.clauses {
+            for literal in &clause.literals {
+                write!(output, "{} ", literal.to_dimacs()).expect("Writing to a String doesn't fail");
+            }
+            output.push_str("0\n");
+        }
+
+        output
+    }
+}
+
+impl Assignment {
+    /// Assignment of the variables of `instance` with the given names.
+    pub fn from_names(instance: &SATInstance, values: &[(&str, bool)]) -> Result<Assignment, UnknownVariable> {
+        values.iter()
+            .map(|(name, value)| instance.str_to_var.get(*name).map(|var| (*var, *value)).ok_or_else(|| UnknownVariable(name.to_string())))
+            .collect::<Result<_, _>>()
+            .map(Assignment::new)
+    }
+
+    /// Display `self` with the variable names of `instance`, sorted by name. Variables without a
+    /// name are called `v<id>`.
+    pub fn display_with_names<'a>(&'a self, instance: &'a SATInstance) -> AssignmentDisplay<'a> {
+        AssignmentDisplay { assignment: self, instance: Some(instance) }
+    }
+}
+
+impl SolverResult {
+    /// Display `self` with the variable names of `instance` if there is one. Without it, variables
+    /// are called `v<id>`, which is what [Display] does.
+    pub fn display_with_names<'a>(&'a self, instance: Option<&'a SATInstance>) -> SolverResultDisplay<'a> {
+        SolverResultDisplay { result: self, instance }
+    }
+}
+
+impl Display for AssignmentDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let values = match self.instance {
+            Some(instance) => instance.name_assignment(self.assignment).0.into_iter().collect::<Vec<_>>(),
+            None => {
+                let mut values = self.assignment.values.iter().collect::<Vec<_>>();
+                values.sort();
+                values.into_iter().map(|(var, value)| (format!("v{}", var), *value)).collect()
+            },
+        };
+
+        if values.is_empty() {
+            return write!(f, "{{}}");
+        }
+
+        write!(f, "{{ ")?;
+        for (index, (name, value)) in values.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} = {}", name, value)?;
+        }
+        write!(f, " }}")
+    }
+}
+
+impl Display for SolverResultDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.result {
+            SolverResult::Sat(Some(model)) => write!(f, "SAT: {}", AssignmentDisplay { assignment: model, instance: self.instance }),
+            SolverResult::Sat(None) => write!(f, "SAT"),
+            SolverResult::Unsat => write!(f, "UNSAT"),
+            SolverResult::Unknown => write!(f, "UNKNOWN"),
+        }
+    }
+}
+
+impl Display for SolverResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.display_with_names(None).fmt(f)
+    }
+}
+
+impl Display for NamedAssignment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, value) in &self.0 {
+            writeln!(f, "{} = {}", name, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for SolverStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} decisions, {} propagations, {} pure literals, {} conflicts, {} restarts, max depth {}",
+            self.decisions, self.propagations, self.pure_literals, self.conflicts, self.restarts, self.max_depth,
+        )
+    }
+}
+
+impl Display for UnknownVariable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown variable '{}'", self.0)
+    }
+}
+
+impl Error for UnknownVariable {}
+
+// replace every variable with its id in `ids`, which has to contain all of them
+fn rename_variables(expression: Expression, ids: &HashMap<VariableId, VariableId>) -> Expression {
+    let binary = |operator: fn(Box<Expression>, Box<Expression>) -> Expression, lhs: Box<Expression>, rhs: Box<Expression>| {
+        operator(Box::new(rename_variables(*lhs, ids)), Box::new(rename_variables(*rhs, ids)))
+    };
+
+    match expression {
+        Expression::Variable(var) => Expression::Variable(ids[&var]),
+        Expression::Constant(_) => expression,
+        Expression::Not(expr) => Expression::Not(Box::new(rename_variables(*expr, ids))),
+        Expression::And(lhs, rhs) => binary(Expression::And, lhs, rhs),
+        Expression::Or(lhs, rhs) => binary(Expression::Or, lhs, rhs),
+        Expression::Xor(lhs, rhs) => binary(Expression::Xor, lhs, rhs),
+        Expression::Implies(lhs, rhs) => binary(Expression::Implies, lhs, rhs),
+        Expression::Iff(lhs, rhs) => binary(Expression::Iff, lhs, rhs),
+    }
+}
+
+impl Display for ModelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModelError::Falsified(clauses) => write!(f, "{} clauses are falsified", clauses.len()),
+            ModelError::Unassigned(variables) => write!(f, "variables {:?} have no value", variables),
+        }
+    }
+}
+
+impl Error for ModelError {}
+
+impl SATInstance {
+    /// Display the expression of `self` with variable names instead of ids.
+    pub fn display_expression(&self) -> ExpressionDisplay<'_> {
+        self.expression.display_with_names(&self.var_to_str)
     }
 }
 
 impl Display for SATInstance {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Instance containing {} variables", self.var_to_str.len())?;
+        writeln!(f, "Expression of size {} and depth {}", self.expression.size(), self.expression.depth())?;
+
+        write!(f, "Expression: {}", self.display_expression().colored())
+    }
+}
+
+#[cfg(test)]
+use rand::{rngs::StdRng, SeedableRng};
+
+#[cfg(test)]
+use crate::{expression::normal::random_expression, parser::{parse_dimacs_str, parse_str}};
+
+#[test]
+fn test_display_expression() {
+    let instance = parse_str("(a | -b) & -(c & d) & -(a | b) & (c | d & a)").unwrap();
+
+    assert_eq!(instance.display_expression().to_string(), "(a | -b) & -(c & d) & -(a | b) & (c | (d & a))");
+}
+
+// whether both instances evaluate to the same value under every assignment of their variables
+#[cfg(test)]
+fn equivalent(lhs: &SATInstance, rhs: &SATInstance) -> bool {
+    let vars = lhs.var_to_str.keys().chain(rhs.var_to_str.keys()).copied().collect::<std::collections::HashSet<_>>().into_iter().collect::<Vec<_>>();
+
+    (0..1u32 << vars.len()).all(|bits| {
+        let assignment = Assignment::new(vars.iter().enumerate().map(|(i, var)| (*var, bits & (1 << i) != 0)).collect());
+        let value = |instance: &SATInstance| assignment.satisfies_expression(&instance.expression);
+        value(lhs) == value(rhs)
+    })
+}
+
+#[test]
+fn test_display_round_trip() {
+    for formula in ["a & (b | -c)", "a -> b -> c", "(a -> b) -> c", "-(a ^ b) <-> (c | 0)", "a & 1 | --b", "((a | b) & c) ^ (a -> -c)"] {
+        let instance = parse_str(formula).unwrap();
+        let displayed = instance.display_expression().to_string();
+        assert_eq!(instance.display_expression().to_string(), displayed);
+
+        // names appear in the same order, so they get the same ids
+        let reparsed = parse_str(&displayed).unwrap();
+        assert_eq!(reparsed.str_to_var, instance.str_to_var, "{}", displayed);
+        assert!(equivalent(&instance, &reparsed), "{} => {}", formula, displayed);
+
+        // the colors are only around the parentheses
+        let colored = instance.display_expression().colored().to_string();
+        let stripped = colored.split('\x1b').enumerate().map(|(i, part)| if i == 0 { part } else { &part[part.find('m').unwrap() + 1..] }).collect::<String>();
+        assert_eq!(stripped, displayed);
+    }
+
+    // without names, variables are called v<id>
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..100 {
+        let expression = random_expression(&mut rng, 4);
+        let reparsed = parse_str(&expression.to_string()).unwrap();
+
+        for bits in 0..16 {
+            let assignment = Assignment::new((0..4).map(|var| (var, bits & (1 << var) != 0)).collect());
+            let renamed = Assignment::new(reparsed.str_to_var.iter().map(|(name, var)| (*var, assignment.values[&name[1..].parse().unwrap()])).collect());
+            assert_eq!(assignment.satisfies_expression(&expression), renamed.satisfies_expression(&reparsed.expression), "{}", expression);
+        }
+    }
+}
+
+#[test]
+fn test_operators() {
+    for (sugar, plain) in [("a -> b", "-a | b"), ("a <-> b", "(a & b) | (-a & -b)"), ("a ^ b", "(a | b) & -(a & b)")] {
+        let (sugar, plain) = (parse_str(sugar).unwrap(), parse_str(plain).unwrap());
+        assert!(equivalent(&sugar, &plain), "{}", sugar.display_expression());
+
+        let desugared = SATInstance::new(sugar.expression.clone().desugar(), sugar.var_to_str.clone());
+        assert!(equivalent(&sugar, &desugared));
+        assert!(equivalent(&sugar, &parse_dimacs_str(&sugar.to_dimacs()).unwrap()));
+    }
+
+    // '->' groups to the right, everything else binds stronger than '<->'
+    let instance = parse_str("a <-> b -> c -> d | e ^ f & -g").unwrap();
+    assert!(matches!(&instance.expression, Expression::Iff(_, rhs) if matches!(rhs.as_ref(), Expression::Implies(_, rhs) if matches!(rhs.as_ref(), Expression::Implies(_, _)))));
+    assert_eq!(instance.display_expression().to_string(), "a <-> (b -> c -> (d | (e ^ (f & -g))))");
+    assert_eq!(parse_str("((a -> b) -> c) & -(a ^ b)").unwrap().display_expression().to_string(), "((a -> b) -> c) & -(a ^ b)");
+}
 
-        write!(f, "Expression: {}", self.expression)
+#[test]
+fn test_solve_operators() {
+    let instance = parse_str("(a ^ b) & (b <-> c) & (c -> -a) & a").unwrap();
+    let expected = NamedAssignment(BTreeMap::from([("a".to_string(), true), ("b".to_string(), false), ("c".to_string(), false)]));
+
+    let model = super::dpll::solve_dpll(instance.clone(), Assignment::default()).model().cloned().unwrap();
+    assert_eq!(instance.name_assignment(&model), expected);
+    let model = super::dpll::solve_dpll(instance.to_tseitin(), Assignment::default()).model().cloned().unwrap();
+    assert_eq!(instance.name_assignment(&model), expected);
+
+    assert!(matches!(super::dpll::solve_dpll(parse_str("(a ^ b) & (a <-> b)").unwrap(), Assignment::default()), SolverResult::Unsat));
+}
+
+#[test]
+fn test_to_dimacs() {
+    let instance = parse_str("(a | -b) & (b | c) & -(a & c)").unwrap();
+    let dimacs = instance.to_dimacs();
+
+    assert!(dimacs.starts_with("c 1 a\nc 2 b\nc 3 c\np cnf 3 "), "{}", dimacs);
+    assert!(equivalent(&instance, &parse_dimacs_str(&dimacs).unwrap()));
+
+    assert_eq!(parse_str("a | 1").unwrap().to_dimacs(), "p cnf 0 0\n");
+    assert_eq!(parse_str("-a & 0").unwrap().to_dimacs(), "p cnf 0 1\n0\n");
+    assert!(matches!(super::dpll::solve_dpll(parse_dimacs_str("p cnf 0 1\n0\n").unwrap(), Assignment::default()), SolverResult::Unsat));
+}
+
+#[test]
+fn test_to_dimacs_round_trip() {
+    let dimacs = std::fs::read_to_string("tests/fixtures/simple.cnf").unwrap();
+    let instance = parse_dimacs_str(&dimacs).unwrap();
+
+    let written = instance.to_dimacs();
+    let reparsed = parse_dimacs_str(&written).unwrap();
+    assert_eq!(reparsed.var_to_str, instance.var_to_str);
+    assert!(equivalent(&instance, &reparsed));
+}
+
+// xor of all `vars`, split in halves so the expression has quadratic instead of exponential size
+#[cfg(test)]
+fn parity(vars: &[VariableId]) -> Expression {
+    if let [var] = vars {
+        return Expression::Variable(*var);
     }
+
+    let (lhs, rhs) = vars.split_at(vars.len() / 2);
+    let (lhs, rhs) = (Box::new(parity(lhs)), Box::new(parity(rhs)));
+    // a ^ b written as (a | b) & -(a & b)
+    Expression::And(Box::new(Expression::Or(lhs.clone(), rhs.clone())), Box::new(Expression::Not(Box::new(Expression::And(lhs, rhs)))))
+}
+
+#[test]
+fn test_to_tseitin() {
+    let instance = parse_str("(a & b) | -(c | a)").unwrap();
+    let tseitin = instance.to_tseitin();
+
+    assert_eq!(tseitin.auxiliary.len(), 3);
+    assert!(tseitin.auxiliary.iter().all(|var| tseitin.var_to_str[var].starts_with("_t") && usize::from(*var) >= 3));
+    assert_eq!(tseitin.var_to_str.len(), 6);
+
+    let SolverResult::Sat(Some(model)) = super::dpll::solve_dpll(tseitin, Assignment::default()) else { panic!("(a & b) | -(c | a) is satisfiable") };
+    assert!(model.values.keys().all(|var| usize::from(*var) < 3));
+    assert!(model.satisfies_expression(&instance.expression));
+}
+
+#[test]
+fn test_from_expression_tseitin() {
+    let names = HashMap::from([(0, "a".to_string()), (1, "b".to_string())]);
+    // -(a & b) & (a | b)
+    let expression = Expression::And(
+        Box::new(Expression::Not(Box::new(Expression::And(Box::new(Expression::Variable(0)), Box::new(Expression::Variable(1)))))),
+        Box::new(Expression::Or(Box::new(Expression::Variable(0)), Box::new(Expression::Variable(1)))),
+    );
+    let instance = SATInstance::from_expression_tseitin(expression.clone(), names);
+
+    assert_eq!(instance.var_to_str.len(), 5);
+    assert_eq!(instance.str_to_var["_t0"], 2);
+    assert_eq!(instance.auxiliary, HashSet::from([2, 3, 4]));
+
+    let SolverResult::Sat(Some(model)) = super::dpll::solve_dpll(instance, Assignment::default()) else { panic!("a xor b is satisfiable") };
+    assert_eq!(model.values.len(), 2);
+    assert!(model.satisfies_expression(&expression));
+}
+
+#[test]
+fn test_to_tseitin_parity() {
+    // distribution doubles the clauses with every variable, Tseitin adds a few per operator
+    let vars = (0..12).collect::<Vec<_>>();
+    let instance = SATInstance::new(parity(&vars), vars.iter().map(|var| (*var, format!("x{}", var))).collect());
+    let tseitin = instance.to_tseitin();
+    assert!(to_cnf_tseitin(&instance.expression, &mut 12).0.clauses.len() < 500);
+
+    let SolverResult::Sat(Some(model)) = super::dpll::solve_dpll(tseitin, Assignment::default()) else { panic!("parity is satisfiable") };
+    assert!(model.values.keys().all(|var| usize::from(*var) < 12));
+    assert!(model.satisfies_expression(&instance.expression));
+
+    let vars = (0..6).collect::<Vec<_>>();
+    let contradiction = Expression::And(Box::new(parity(&vars)), Box::new(Expression::Not(Box::new(parity(&vars)))));
+    let unsat = SATInstance::new(contradiction, vars.iter().map(|var| (*var, format!("x{}", var))).collect());
+    assert!(matches!(super::dpll::solve_dpll(unsat.to_tseitin(), Assignment::default()), SolverResult::Unsat));
+}
+
+#[test]
+fn test_check() {
+    let instance = parse_str("(a | b) & (-a | c) & -d").unwrap();
+    let [a, b, c, d] = ["a", "b", "c", "d"].map(|name| instance.str_to_var[name]);
+
+    assert_eq!(instance.check(&Assignment::from([(a, true), (b, false), (c, true), (d, false)])), Ok(()));
+    // b doesn't matter once a and c are set
+    assert_eq!(instance.check(&Assignment::from([(a, true), (c, true), (d, false)])), Ok(()));
+
+    let wrong = Assignment::from([(a, true), (b, true), (c, false), (d, false)]);
+    let Err(ModelError::Falsified(clauses)) = instance.check(&wrong) else { panic!("-a | c is falsified") };
+    assert_eq!(clauses.len(), 1);
+    assert_eq!(clauses[0].literals.iter().collect::<HashSet<_>>(), HashSet::from([&Literal::new(a, false), &Literal::new(c, true)]));
+
+    assert_eq!(instance.check(&Assignment::from([(a, true), (d, false)])), Err(ModelError::Unassigned(vec![c])));
+    assert!(matches!(instance.check(&Assignment::default()), Err(ModelError::Unassigned(variables)) if variables.len() == 4));
+}
+
+#[test]
+fn test_check_solver_models() {
+    // DPLL checks its models itself in debug builds, CDCL only gets checked here
+    for seed in 0..10 {
+        let instance = crate::generator::random_ksat(30, 120, 3, seed);
+        for result in [super::dpll::solve_dpll(instance.clone(), Assignment::default()), super::cdcl::solve_cdcl(instance.clone(), Assignment::default())] {
+            if let Some(model) = result.model() {
+                assert_eq!(instance.check(model), Ok(()), "seed {}", seed);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_named_assignment() {
+    let instance = parse_str("(b | -a) & c").unwrap();
+
+    let assignment = Assignment::from_names(&instance, &[("b", true), ("a", false), ("c", true)]).unwrap();
+    assert!(!assignment.values[&instance.str_to_var["a"]]);
+    assert_eq!(Assignment::from_names(&instance, &[("a", true), ("d", false)]).unwrap_err(), UnknownVariable("d".to_string()));
+
+    let named = instance.name_assignment(&assignment);
+    assert!(named.0["c"]);
+    assert_eq!(named.to_string(), "a = false\nb = true\nc = true\n");
+
+    let result = super::dpll::solve_dpll(instance.clone(), Assignment::from_names(&instance, &[("a", true)]).unwrap());
+    assert_eq!(instance.name_assignment(result.model().unwrap()).to_string(), "a = true\nb = true\nc = true\n");
+}
+
+#[test]
+fn test_max_variable_id() {
+    assert_eq!(parse_str("1 & (0 | 1)").unwrap().max_variable_id(), None);
+    assert_eq!(parse_str("a & (b | -c)").unwrap().max_variable_id(), Some(2));
+
+    // ids with gaps, also in DIMACS
+    let sparse = SATInstance::new(Expression::Or(Box::new(Expression::Variable(40)), Box::new(Expression::Variable(7))), HashMap::from([(7, "a".to_string()), (40, "b".to_string())]));
+    assert_eq!(sparse.max_variable_id(), Some(40));
+    assert!(sparse.to_dimacs().contains("p cnf 41 1\n"));
+}
+
+#[test]
+fn test_display_with_names() {
+    let instance = parse_str("foo & (bar | -foo)").unwrap();
+    let result = super::dpll::solve_dpll(instance.clone(), Assignment::default());
+
+    let displayed = result.display_with_names(Some(&instance)).to_string();
+    assert!(displayed.contains("foo = "), "{}", displayed);
+    assert_eq!(displayed, "SAT: { bar = true, foo = true }");
+    assert_eq!(result.to_string(), "SAT: { v0 = true, v1 = true }");
+
+    let unnamed = Assignment::from([(7, false), (0, true)]);
+    assert_eq!(unnamed.display_with_names(&instance).to_string(), "{ foo = true, v7 = false }");
+    assert_eq!(Assignment::default().display_with_names(&instance).to_string(), "{}");
+
+    assert_eq!(SolverResult::Unsat.display_with_names(Some(&instance)).to_string(), "UNSAT");
+    assert_eq!(SolverResult::Sat(None).to_string(), "SAT");
+    assert_eq!(SolverResult::Unknown.to_string(), "UNKNOWN");
+}
+
+#[test]
+fn test_apply_partial() {
+    let instance = parse_str("(a & b) | (-a & c)").unwrap();
+    let applied = instance.apply_partial(&Assignment::from_names(&instance, &[("a", true)]).unwrap());
+    assert_eq!(applied.var_to_str, HashMap::from([(0, "b".to_string())]));
+    assert!(equivalent(&applied, &parse_str("b").unwrap()));
+
+    // the remaining variables keep their names and order
+    let instance = parse_str("a | b | (c & d)").unwrap();
+    let applied = instance.apply_partial(&Assignment::from_names(&instance, &[("b", false), ("c", true)]).unwrap());
+    assert_eq!(applied.str_to_var, HashMap::from([("a".to_string(), 0), ("d".to_string(), 1)]));
+    assert_eq!(applied.display_expression().to_string(), "a | d");
+
+    let applied = instance.apply_partial(&Assignment::from_names(&instance, &[("a", true)]).unwrap());
+    assert_eq!(applied.expression, Expression::Constant(true));
+    assert!(applied.var_to_str.is_empty() && applied.str_to_var.is_empty());
+    assert!(matches!(super::dpll::solve_dpll(applied, Assignment::default()), SolverResult::Sat(_)));
+
+    let applied = parse_str("a & b").unwrap().apply_partial(&Assignment::from([(1, false)]));
+    assert_eq!(super::dpll::solve_dpll(applied, Assignment::default()), SolverResult::Unsat);
 }
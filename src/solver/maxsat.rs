@@ -0,0 +1,101 @@
+// Maximum satisfiability: the most clauses of a CNF that can be satisfied at once.
+
+use crate::{encodings::cardinality::at_most_k, expression::{expression::{Assignment, VariableId}, normal::{Clause, Literal, CNF}}};
+
+use super::{dpll::solve_dpll_cnf, instance::{SATInstance, SolverConfig, SolverResult}};
+
+/// The maximum number of clauses of the CNF of `instance` that are satisfied at once, together with
+/// an assignment satisfying that many.
+///
+/// Every clause `c` gets a relaxation variable `r` and becomes `c | r`. Then, starting with none of
+/// them, the number `n - k` of relaxation variables allowed to be true is raised until the formula
+/// is satisfiable.
+pub fn solve_maxsat(instance: SATInstance) -> (usize, Assignment) {
+    let variables = instance.var_to_str.keys().filter(|var| !instance.auxiliary.contains(var)).copied().collect::<Vec<_>>();
+    // simplifying the whole expression would merge contradicting clauses
+    let cnf = CNF::from_conjuncts(instance.expression);
+    if cnf.clauses.is_empty() {
+        return (0, Assignment::default());
+    }
+
+    let first_free = cnf.clauses.iter()
+        .flat_map(|clause| &clause.literals)
+        .map(|literal| literal.var_id)
+        .chain(variables.iter().copied())
+        .max()
+        .map_or(0, |max| max.checked_add(1).expect("Ran out of variable ids"));
+
+    // a model of `clauses` restricted to the variables of the instance
+    let witness = |clauses: Vec<Clause>| match solve_dpll_cnf(CNF::new(clauses), Assignment::default(), &SolverConfig::default()).0 {
+        SolverResult::Sat(Some(mut model)) => {
+            model.values.retain(|var, _| variables.contains(var));
+            for var in &variables {
+                model.values.entry(*var).or_insert(false);
+            }
+            Some(model)
+        },
+        _ => None,
+    };
+
+    let n = cnf.clauses.len();
+    if let Some(model) = witness(cnf.clauses.clone()) {
+        return (n, model);
+    }
+
+    for k in (0..n).rev() {
+        let relaxations = (first_free..).take(n).collect::<Vec<VariableId>>();
+        let mut fresh = first_free.checked_add(VariableId::try_from(n).expect("Ran out of variable ids")).expect("Ran out of variable ids");
+
+        let mut clauses = cnf.clauses.iter().zip(&relaxations)
+            .map(|(clause, relaxation)| Clause::new(clause.literals.iter().copied().chain([Literal::new(*relaxation, true)]).collect()))
+            .collect::<Vec<_>>();
+        // at least k clauses are satisfied without relaxing them
+        clauses.extend(at_most_k(&relaxations, n - k, &mut fresh));
+
+        if let Some(model) = witness(clauses) {
+            return (k, model);
+        }
+    }
+
+    unreachable!("No clauses have to be satisfied for k = 0")
+}
+
+#[cfg(test)]
+use crate::{generator::pigeonhole, parser::parse_str};
+
+#[test]
+fn test_maxsat() {
+    let instance = parse_str("a & -a & b").unwrap();
+    let b = instance.str_to_var["b"];
+
+    let (count, model) = solve_maxsat(instance);
+    assert_eq!(count, 2);
+    assert_eq!(model.values.len(), 2);
+    assert!(model.values[&b]);
+}
+
+#[test]
+fn test_maxsat_satisfiable() {
+    let instance = parse_str("(a | b) & (-a | c)").unwrap();
+
+    let (count, model) = solve_maxsat(instance.clone());
+    assert_eq!(count, 2);
+    assert!(model.satisfies_expression(&instance.expression));
+}
+
+#[test]
+fn test_maxsat_pigeonhole() {
+    // dropping any single clause makes 3 pigeons fit into 2 holes
+    let instance = pigeonhole(2);
+    let cnf = CNF::from(instance.expression.clone());
+
+    let (count, model) = solve_maxsat(instance);
+    assert_eq!(count, cnf.clauses.len() - 1);
+    assert_eq!(cnf.clauses.iter().filter(|clause| model.satisfies_cnf(&CNF::new(vec![(*clause).clone()]))).count(), count);
+}
+
+#[test]
+fn test_maxsat_empty() {
+    let instance = SATInstance::new(CNF::new(vec![]).to_expression(), Default::default());
+    assert_eq!(solve_maxsat(instance), (0, Assignment::default()));
+}
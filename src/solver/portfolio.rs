@@ -0,0 +1,103 @@
+// Racing differently configured DPLL solvers on several threads.
+
+use std::{sync::{atomic::{AtomicBool, Ordering}, mpsc, Arc}, thread};
+
+use crate::expression::expression::Assignment;
+
+use super::{dpll::solve_dpll_with_config, heuristics::BranchingHeuristic, instance::{SATInstance, SolverConfig, SolverResult}, restart::RestartPolicy};
+
+// the heuristics the workers take turns with, the random one differs by seed as well
+const HEURISTICS: [BranchingHeuristic; 4] = [BranchingHeuristic::Vsids, BranchingHeuristic::Random, BranchingHeuristic::JeroslowWang, BranchingHeuristic::MostFrequent];
+
+// every worker gets its own seed, every other one restarts
+fn worker_config(index: usize, cancel: Arc<AtomicBool>) -> SolverConfig {
+    SolverConfig {
+        heuristic: HEURISTICS[index % HEURISTICS.len()],
+        seed: index as u64,
+        restarts: (index % 2 == 1).then(|| RestartPolicy::luby(100)),
+        cancel: Some(cancel),
+        ..Default::default()
+    }
+}
+
+/// Solve `instance` with `num_threads` DPLL workers using different heuristics and seeds, at least
+/// one. The first worker to find a model or prove unsatisfiability wins and the others are
+/// cancelled. Auxiliary variables are left out of the model.
+///
+/// Panics if the winning model doesn't satisfy the instance.
+pub fn solve_parallel(instance: SATInstance, num_threads: usize) -> SolverResult {
+    // the workers keep the auxiliary variables, so their models can be checked
+    let mut full = instance.clone();
+    full.auxiliary.clear();
+    let max_id = full.max_variable_id();
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for index in 0..num_threads.max(1) {
+            let (full, sender, config) = (&full, sender.clone(), worker_config(index, cancel.clone()));
+            // the receiver is gone once there is a winner, so failing to send is fine
+            scope.spawn(move || sender.send((index, solve_dpll_with_config(full.clone(), Assignment::default(), config))));
+        }
+        drop(sender);
+
+        // all workers being cancelled or out of time leaves the result unknown
+        let mut result = SolverResult::Unknown;
+        for (index, answer) in receiver.iter() {
+            match answer {
+                SolverResult::Sat(Some(model)) => {
+                    // variables of dropped tautologies may be left unassigned
+                    let mut complete = model.clone();
+                    if let Some(max_id) = max_id {
+                        complete.complete(max_id);
+                    }
+                    if let Err(error) = full.check(&complete) {
+                        panic!("Worker {} returned an invalid model: {}", index, error);
+                    }
+
+                    result = SolverResult::Sat(Some(instance.without_auxiliary(model)));
+                },
+                SolverResult::Unknown => continue,
+                answer => result = answer,
+            }
+            break;
+        }
+
+        cancel.store(true, Ordering::Relaxed);
+        result
+    })
+}
+
+#[cfg(test)]
+use crate::{generator::{pigeonhole, random_ksat}, parser::parse_str, solver::dpll::solve_dpll};
+
+#[test]
+fn test_solve_parallel() {
+    let instance = parse_str("(a | b) & (-a | c) & -c").unwrap();
+    let SolverResult::Sat(Some(model)) = solve_parallel(instance.clone(), 4) else { panic!("expected a model") };
+    assert!(model.satisfies_expression(&instance.expression));
+
+    assert_eq!(solve_parallel(pigeonhole(5), 4), SolverResult::Unsat);
+    // no threads still means one worker
+    assert_eq!(solve_parallel(parse_str("a & -a").unwrap(), 0), SolverResult::Unsat);
+
+    // auxiliary variables are left out
+    let SolverResult::Sat(Some(model)) = solve_parallel(instance.to_tseitin(), 3) else { panic!("expected a model") };
+    assert_eq!(model.values.len(), 3);
+}
+
+#[test]
+fn test_solve_parallel_agrees_with_dpll() {
+    // close to the satisfiability threshold, where random 3-SAT is hardest
+    for seed in 0..8 {
+        let instance = random_ksat(60, 256, 3, seed);
+        let parallel = solve_parallel(instance.clone(), 4);
+        let sequential = solve_dpll(instance.clone(), Assignment::default());
+
+        assert_eq!(matches!(parallel, SolverResult::Sat(_)), matches!(sequential, SolverResult::Sat(_)), "seed {}", seed);
+        if let SolverResult::Sat(Some(model)) = parallel {
+            assert!(model.satisfies_expression(&instance.expression), "seed {}", seed);
+        }
+    }
+}
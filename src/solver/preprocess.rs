@@ -0,0 +1,424 @@
+// Preprocessing passes on a CNF that can be run before handing it to any solver.
+
+use std::collections::HashMap;
+
+use crate::expression::{expression::Assignment, normal::{Clause, Literal, CNF}};
+
+// drop the clauses satisfied by `assignment` and the false literals from the rest
+fn reduce(cnf: &mut CNF, assignment: &Assignment) {
+    cnf.clauses.retain(|clause| !clause.literals.iter().any(|literal| assignment.values.get(&literal.var_id) == Some(&literal.value)));
+    for clause in &mut cnf.clauses {
+        clause.literals.retain(|literal| !assignment.values.contains_key(&literal.var_id));
+        clause.literals.sort();
+        clause.literals.dedup();
+    }
+}
+
+/// Assign the literal of every unit clause and propagate it, until there are no unit clauses left.
+///
+/// `cnf` is reduced by `assignment` on the way: satisfied clauses are dropped and false literals are
+/// removed, so together with `assignment` it has the same models as before. If propagation runs
+/// into a conflict, the empty clause is the only clause left. Returns the literals assigned by the
+/// pass in the order they were assigned.
+pub fn unit_propagate(cnf: &mut CNF, assignment: &mut Assignment) -> Vec<Literal> {
+    reduce(cnf, assignment);
+
+    let mut occurrences: HashMap<Literal, Vec<usize>> = HashMap::new();
+    for (index, clause) in cnf.clauses.iter().enumerate() {
+        for literal in &clause.literals {
+            occurrences.entry(*literal).or_default().push(index);
+        }
+    }
+
+    let mut satisfied = vec![false; cnf.clauses.len()];
+    let mut queue = cnf.clauses.iter().filter(|clause| clause.literals.len() == 1).map(|clause| clause.literals[0]).collect::<Vec<_>>();
+    let mut forced = Vec::new();
+    let mut conflict = cnf.clauses.iter().any(|clause| clause.literals.is_empty());
+
+    while let Some(literal) = queue.pop() {
+        if conflict {
+            break;
+        }
+        match assignment.values.get(&literal.var_id) {
+            Some(value) if *value == literal.value => continue,
+            Some(_) => {
+                conflict = true;
+                break;
+            },
+            None => {},
+        }
+
+        assignment.values.insert(literal.var_id, literal.value);
+        forced.push(literal);
+
+        for index in occurrences.get(&literal).into_iter().flatten() {
+            satisfied[*index] = true;
+        }
+        for index in occurrences.get(&literal.not()).into_iter().flatten() {
+            if satisfied[*index] {
+                continue;
+            }
+
+            let clause = &mut cnf.clauses[*index];
+            clause.literals.retain(|other| *other != literal.not());
+            match clause.literals.as_slice() {
+                [] => conflict = true,
+                [unit] => queue.push(*unit),
+                _ => {},
+            }
+        }
+    }
+
+    if conflict {
+        cnf.clauses = vec![Clause::new(vec![])];
+    } else {
+        let mut index = 0;
+        cnf.clauses.retain(|_| {
+            index += 1;
+            !satisfied[index - 1]
+        });
+    }
+
+    forced
+}
+
+/// Assign every literal whose negation doesn't occur in the clauses and drop the clauses it
+/// satisfies, until there are no pure literals left.
+///
+/// `cnf` is reduced by `assignment` first, like in [unit_propagate]. Only satisfiability is
+/// preserved: every model of the result together with `assignment` satisfies the original CNF, but
+/// there may be models of the original CNF setting the pure literals the other way. Returns the
+/// literals assigned by the pass.
+pub fn eliminate_pure_literals(cnf: &mut CNF, assignment: &mut Assignment) -> Vec<Literal> {
+    reduce(cnf, assignment);
+
+    let mut counts: HashMap<Literal, usize> = HashMap::new();
+    let mut occurrences: HashMap<Literal, Vec<usize>> = HashMap::new();
+    for (index, clause) in cnf.clauses.iter().enumerate() {
+        for literal in &clause.literals {
+            *counts.entry(*literal).or_default() += 1;
+            occurrences.entry(*literal).or_default().push(index);
+        }
+    }
+
+    let mut pure = counts.keys().filter(|literal| !counts.contains_key(&literal.not())).copied().collect::<Vec<_>>();
+    let mut removed = vec![false; cnf.clauses.len()];
+    let mut assigned = Vec::new();
+
+    while let Some(literal) = pure.pop() {
+        if assignment.values.contains_key(&literal.var_id) {
+            continue;
+        }
+        assignment.values.insert(literal.var_id, literal.value);
+        assigned.push(literal);
+
+        // dropping the satisfied clauses can make the literals in them pure
+        for index in occurrences.get(&literal).into_iter().flatten() {
+            if removed[*index] {
+                continue;
+            }
+            removed[*index] = true;
+
+            for other in &cnf.clauses[*index].literals {
+                let count = counts.get_mut(other).expect("Counted above");
+                *count -= 1;
+                if *count == 0 {
+                    counts.remove(other);
+                    if counts.contains_key(&other.not()) {
+                        pure.push(other.not());
+                    }
+                }
+            }
+        }
+    }
+
+    let mut index = 0;
+    cnf.clauses.retain(|_| {
+        index += 1;
+        !removed[index - 1]
+    });
+
+    assigned
+}
+
+// the variables of `clause` folded into 64 bits, `signature(a) & !signature(b) != 0` rules out
+// that the variables of `a` are a subset of those of `b`
+fn signature(clause: &Clause) -> u64 {
+    clause.literals.iter().fold(0, |signature, literal| signature | 1 << (literal.var_id % 64))
+}
+
+fn sort_literals(cnf: &mut CNF) {
+    for clause in &mut cnf.clauses {
+        clause.literals.sort();
+        clause.literals.dedup();
+    }
+}
+
+/// Remove every clause that contains all literals of another clause (forward subsumption), of
+/// duplicate clauses only the first is kept. The models don't change.
+///
+/// Candidates are found through the smallest literal of the shorter clause and ruled out cheaply by
+/// comparing bitsets of their variables before checking the literals. The literals of the remaining
+/// clauses are sorted and deduplicated.
+pub fn forward_subsume(cnf: &mut CNF) {
+    sort_literals(cnf);
+    let signatures = cnf.clauses.iter().map(signature).collect::<Vec<_>>();
+
+    // shorter clauses first, they are the only ones that can subsume the longer ones
+    let mut order = (0..cnf.clauses.len()).collect::<Vec<_>>();
+    order.sort_by_key(|index| cnf.clauses[*index].literals.len());
+
+    // the kept clauses by their smallest literal
+    let mut kept: HashMap<Literal, Vec<usize>> = HashMap::new();
+    let mut subsumed = vec![false; cnf.clauses.len()];
+    let mut empty = None;
+
+    for index in order {
+        let clause = &cnf.clauses[index];
+        let Some(first) = clause.literals.first() else {
+            // the empty clause subsumes every other one
+            subsumed[index] = empty.is_some();
+            empty.get_or_insert(index);
+            continue;
+        };
+
+        subsumed[index] = empty.is_some() || clause.literals.iter().any(|literal| {
+            kept.get(literal).into_iter().flatten().any(|other| {
+                signatures[*other] & !signatures[index] == 0
+                    && cnf.clauses[*other].literals.iter().all(|literal| clause.literals.binary_search(literal).is_ok())
+            })
+        });
+        if !subsumed[index] {
+            kept.entry(*first).or_default().push(index);
+        }
+    }
+
+    let mut index = 0;
+    cnf.clauses.retain(|_| {
+        index += 1;
+        !subsumed[index - 1]
+    });
+}
+
+/// Strengthen clauses by self-subsuming resolution: if a clause `A | x` and a clause `B | -x` exist
+/// with every literal of `A` in `B`, resolving them gives `B`, so `-x` is removed from the second
+/// clause. Repeated until no clause can be strengthened. The models don't change.
+///
+/// The literals of every clause are sorted and deduplicated.
+pub fn self_subsume(cnf: &mut CNF) {
+    sort_literals(cnf);
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+
+        let mut signatures = cnf.clauses.iter().map(signature).collect::<Vec<_>>();
+        let mut occurrences: HashMap<Literal, Vec<usize>> = HashMap::new();
+        for (index, clause) in cnf.clauses.iter().enumerate() {
+            for literal in &clause.literals {
+                occurrences.entry(*literal).or_default().push(index);
+            }
+        }
+
+        for index in 0..cnf.clauses.len() {
+            // literals of the clause can be removed while it's being looked at
+            let mut position = 0;
+            while let Some(&literal) = cnf.clauses[index].literals.get(position) {
+                position += 1;
+
+                for &other in occurrences.get(&literal.not()).into_iter().flatten() {
+                    if other == index || signatures[index] & !signatures[other] != 0 {
+                        continue;
+                    }
+
+                    // the lists aren't updated when literals are removed
+                    let (clause, strengthened) = (&cnf.clauses[index], &cnf.clauses[other]);
+                    let resolvable = strengthened.literals.binary_search(&literal.not()).is_ok()
+                        && clause.literals.iter().all(|l| *l == literal || strengthened.literals.binary_search(l).is_ok());
+                    if resolvable {
+                        cnf.clauses[other].literals.retain(|l| *l != literal.not());
+                        signatures[other] = signature(&cnf.clauses[other]);
+                        changed = true;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+use crate::{expression::expression::{Expression, VariableId}, generator::random_ksat_cnf, solver::{dpll::solve_dpll_cnf, instance::{SolverConfig, SolverResult}}};
+
+#[cfg(test)]
+fn is_sat(cnf: &CNF) -> bool {
+    matches!(solve_dpll_cnf(cnf.clone(), Assignment::default(), &SolverConfig::default()).0, SolverResult::Sat(_))
+}
+
+#[cfg(test)]
+fn clause(literals: &[(VariableId, bool)]) -> Clause {
+    Clause::new(literals.iter().map(|(var, value)| Literal::new(*var, *value)).collect())
+}
+
+#[test]
+fn test_unit_propagate() {
+    // a, a -> b, b -> c, and (-c | d | e) loses -c
+    let mut cnf = CNF::new(vec![
+        clause(&[(0, true)]),
+        clause(&[(0, false), (1, true)]),
+        clause(&[(1, false), (2, true)]),
+        clause(&[(2, false), (3, true), (4, true)]),
+    ]);
+    let mut assignment = Assignment::default();
+
+    let forced = unit_propagate(&mut cnf, &mut assignment);
+    assert_eq!(forced, [Literal::new(0, true), Literal::new(1, true), Literal::new(2, true)]);
+    assert_eq!(cnf.clauses, [clause(&[(3, true), (4, true)])]);
+
+    let mut cnf = CNF::new(vec![clause(&[(0, true)]), clause(&[(0, false), (1, true)]), clause(&[(1, false)])]);
+    unit_propagate(&mut cnf, &mut Assignment::default());
+    assert_eq!(cnf.clauses, [Clause::new(vec![])]);
+
+    // the assignment is taken into account
+    let mut cnf = CNF::new(vec![clause(&[(0, false), (1, true)])]);
+    let mut assignment = Assignment::from([(0, true)]);
+    assert_eq!(unit_propagate(&mut cnf, &mut assignment), [Literal::new(1, true)]);
+    assert!(cnf.clauses.is_empty());
+}
+
+#[test]
+fn test_unit_propagate_random() {
+    for seed in 0..50 {
+        let units = random_ksat_cnf(20, 4, 1, seed);
+        let original = CNF::new(random_ksat_cnf(20, 60, 3, seed).clauses.into_iter().chain(random_ksat_cnf(20, 30, 2, seed).clauses).chain(units.clauses).collect());
+        let mut cnf = original.clone();
+        let mut assignment = Assignment::default();
+
+        let forced = unit_propagate(&mut cnf, &mut assignment);
+        assert_eq!(forced.len(), assignment.values.len());
+        assert_eq!(is_sat(&cnf), is_sat(&original), "seed {}", seed);
+
+        // no clause is unit under the assignment
+        for clause in &original.clauses {
+            let satisfied = clause.literals.iter().any(|literal| assignment.values.get(&literal.var_id) == Some(&literal.value));
+            let unassigned = clause.literals.iter().filter(|literal| !assignment.values.contains_key(&literal.var_id)).count();
+            assert!(satisfied || unassigned != 1 || cnf.clauses == [Clause::new(vec![])], "seed {}: {:?}", seed, clause);
+        }
+        for clause in &cnf.clauses {
+            assert_ne!(clause.literals.len(), 1, "seed {}", seed);
+        }
+
+        // the assignment together with a model of the rest satisfies the original
+        if let SolverResult::Sat(Some(model)) = solve_dpll_cnf(cnf.clone(), assignment.clone(), &SolverConfig::default()).0 {
+            assert!(model.satisfies_cnf(&original), "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn test_eliminate_pure_literals() {
+    // a is pure, b and c occur both ways in the rest
+    let mut cnf = CNF::new(vec![
+        clause(&[(0, true), (1, false)]),
+        clause(&[(1, true), (2, false)]),
+        clause(&[(1, false), (2, true)]),
+    ]);
+    let mut assignment = Assignment::default();
+
+    let assigned = eliminate_pure_literals(&mut cnf, &mut assignment);
+    assert_eq!(assigned, [Literal::new(0, true)]);
+    assert_eq!(cnf.clauses.len(), 2);
+
+    // removing (a | b) makes -b pure
+    let mut cnf = CNF::new(vec![clause(&[(0, true), (1, true)]), clause(&[(1, false), (2, true)]), clause(&[(2, false), (3, true)])]);
+    let mut assignment = Assignment::default();
+    eliminate_pure_literals(&mut cnf, &mut assignment);
+    assert!(cnf.clauses.is_empty());
+    assert!(assignment.values[&0] && assignment.values[&3]);
+}
+
+#[test]
+fn test_eliminate_pure_literals_random() {
+    for seed in 0..50 {
+        let original = random_ksat_cnf(15, 40, 3, seed);
+        let mut cnf = original.clone();
+        let mut assignment = Assignment::default();
+
+        let assigned = eliminate_pure_literals(&mut cnf, &mut assignment);
+        assert_eq!(assigned.len(), assignment.values.len());
+        assert_eq!(is_sat(&cnf), is_sat(&original), "seed {}", seed);
+
+        // every remaining variable occurs both ways
+        let literals = cnf.clauses.iter().flat_map(|clause| &clause.literals).collect::<Vec<_>>();
+        for literal in &literals {
+            assert!(!assignment.values.contains_key(&literal.var_id));
+            assert!(literals.contains(&&literal.not()), "seed {}: {:?} is pure", seed, literal);
+        }
+
+        if let SolverResult::Sat(Some(model)) = solve_dpll_cnf(cnf.clone(), assignment.clone(), &SolverConfig::default()).0 {
+            assert!(model.satisfies_cnf(&original), "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn test_forward_subsume() {
+    // (a | b) subsumes (a | b | c), the duplicate of (-a | c) goes too
+    let mut cnf = CNF::new(vec![
+        clause(&[(0, true), (1, true), (2, true)]),
+        clause(&[(1, true), (0, true)]),
+        clause(&[(0, false), (2, true)]),
+        clause(&[(2, true), (0, false)]),
+    ]);
+    forward_subsume(&mut cnf);
+    assert_eq!(cnf.clauses, [clause(&[(0, true), (1, true)]), clause(&[(0, false), (2, true)])]);
+
+    // variables 1 and 65 share a signature bit, the literals still have to match
+    let mut cnf = CNF::new(vec![clause(&[(1, true)]), clause(&[(65, true), (2, true)])]);
+    forward_subsume(&mut cnf);
+    assert_eq!(cnf.clauses.len(), 2);
+
+    let mut cnf = CNF::new(vec![clause(&[(0, true)]), Clause::new(vec![]), Clause::new(vec![])]);
+    forward_subsume(&mut cnf);
+    assert_eq!(cnf.clauses, [Clause::new(vec![])]);
+}
+
+#[test]
+fn test_self_subsume() {
+    // resolving (-a | b | c) and (a | b | c) on a gives (b | c)
+    let mut cnf = CNF::new(vec![clause(&[(0, true), (1, true), (2, true)]), clause(&[(0, false), (1, true), (2, true)])]);
+    self_subsume(&mut cnf);
+    assert!(cnf.clauses.contains(&clause(&[(1, true), (2, true)])));
+
+    // (a | b) strengthens (-a | b | c) to (b | c), but not the other way around
+    let mut cnf = CNF::new(vec![clause(&[(0, true), (1, true)]), clause(&[(0, false), (1, true), (2, true)]), clause(&[(1, false), (3, true)])]);
+    self_subsume(&mut cnf);
+    assert_eq!(cnf.clauses, [clause(&[(0, true), (1, true)]), clause(&[(1, true), (2, true)]), clause(&[(1, false), (3, true)])]);
+
+    // a and -a leave the empty clause
+    let mut cnf = CNF::new(vec![clause(&[(0, true)]), clause(&[(0, false)])]);
+    self_subsume(&mut cnf);
+    assert!(cnf.clauses.iter().any(|clause| clause.literals.is_empty()));
+}
+
+#[test]
+fn test_subsumption_random() {
+    for seed in 0..50 {
+        let original = CNF::new(random_ksat_cnf(6, 30, 3, seed).clauses.into_iter().chain(random_ksat_cnf(6, 10, 2, seed).clauses).collect());
+        let expression: Expression = original.clone().into();
+
+        let mut subsumed = original.clone();
+        forward_subsume(&mut subsumed);
+        assert!(Expression::from(subsumed.clone()).equivalent(&expression, 6), "seed {}", seed);
+        for (index, clause) in subsumed.clauses.iter().enumerate() {
+            for (other, candidate) in subsumed.clauses.iter().enumerate() {
+                assert!(index == other || !clause.literals.iter().all(|literal| candidate.literals.contains(literal)), "seed {}", seed);
+            }
+        }
+
+        let mut strengthened = original.clone();
+        self_subsume(&mut strengthened);
+        assert!(Expression::from(strengthened.clone()).equivalent(&expression, 6), "seed {}", seed);
+        let literals = |cnf: &CNF| cnf.clauses.iter().map(|clause| clause.literals.len()).sum::<usize>();
+        assert!(literals(&strengthened) <= literals(&original), "seed {}", seed);
+    }
+}
@@ -0,0 +1,116 @@
+// Restart schedules for the DPLL solver.
+
+/// When to restart, in numbers of conflicts since the last restart, or decisions, see
+/// [RestartCounter].
+#[derive(Debug, Clone, PartialEq)]
+pub enum RestartPolicy {
+    Luby(LubyRestartPolicy),
+    Geometric(GeometricRestartPolicy),
+}
+
+impl RestartPolicy {
+    pub fn luby(unit: u64) -> Self {
+        RestartPolicy::Luby(LubyRestartPolicy::new(unit))
+    }
+
+    pub fn geometric(first: u64, factor: f64) -> Self {
+        RestartPolicy::Geometric(GeometricRestartPolicy::new(first, factor))
+    }
+
+    /// The number of conflicts until the next restart.
+    pub fn next_threshold(&mut self) -> u64 {
+        match self {
+            RestartPolicy::Luby(policy) => policy.next_threshold(),
+            RestartPolicy::Geometric(policy) => policy.next_threshold(),
+        }
+    }
+}
+
+/// What the thresholds of a [RestartPolicy] count.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RestartCounter {
+    /// Conflicts since the last restart
+    #[default]
+    Conflicts,
+    /// Decisions since the last restart
+    Decisions,
+}
+
+/// Restarts after a number of conflicts following the Luby sequence (1, 1, 2, 1, 1, 2, 4, ...)
+/// multiplied by `unit`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LubyRestartPolicy {
+    pub unit: u64,
+    index: u64,
+}
+
+impl LubyRestartPolicy {
+    pub fn new(unit: u64) -> Self {
+        Self { unit, index: 0 }
+    }
+
+    /// The number of conflicts until the next restart.
+    pub fn next_threshold(&mut self) -> u64 {
+        self.index += 1;
+        luby(self.index).saturating_mul(self.unit)
+    }
+}
+
+/// Restarts after `first` conflicts, then after `factor` times as many as the last time.
+/// Without clause learning, only a `factor` above 1 guarantees that the search terminates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeometricRestartPolicy {
+    pub first: u64,
+    pub factor: f64,
+    next: f64,
+}
+
+impl GeometricRestartPolicy {
+    pub fn new(first: u64, factor: f64) -> Self {
+        Self { first, factor, next: first as f64 }
+    }
+
+    /// The number of conflicts until the next restart, rounded down but at least one.
+    pub fn next_threshold(&mut self) -> u64 {
+        let threshold = self.next;
+        self.next *= self.factor;
+        // saturates at u64::MAX
+        (threshold as u64).max(1)
+    }
+}
+
+// the i-th element (starting at 1) of the Luby sequence
+fn luby(mut i: u64) -> u64 {
+    loop {
+        // 2^(k - 1) <= i < 2^k
+        let k = u64::BITS - i.leading_zeros();
+        if i == (1 << k) - 1 {
+            return 1 << (k - 1);
+        }
+
+        i -= (1 << (k - 1)) - 1;
+    }
+}
+
+#[test]
+fn test_luby_sequence() {
+    let mut policy = LubyRestartPolicy::new(1);
+    let sequence = (0..16).map(|_| policy.next_threshold()).collect::<Vec<_>>();
+
+    assert_eq!(sequence, [1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8, 1]);
+
+    let mut policy = LubyRestartPolicy::new(100);
+    assert_eq!((0..3).map(|_| policy.next_threshold()).collect::<Vec<_>>(), [100, 100, 200]);
+}
+
+#[test]
+fn test_geometric_sequence() {
+    let mut policy = RestartPolicy::geometric(100, 1.5);
+    assert_eq!((0..5).map(|_| policy.next_threshold()).collect::<Vec<_>>(), [100, 150, 225, 337, 506]);
+
+    let mut policy = GeometricRestartPolicy::new(0, 2.0);
+    assert_eq!(policy.next_threshold(), 1);
+    let mut policy = GeometricRestartPolicy::new(u64::MAX / 2, 4.0);
+    policy.next_threshold();
+    assert_eq!(policy.next_threshold(), u64::MAX);
+}
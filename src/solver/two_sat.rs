@@ -0,0 +1,181 @@
+// 2-SAT in linear time via the strongly connected components of the implication graph.
+
+use std::collections::HashMap;
+
+use crate::expression::{expression::{Assignment, VariableId}, normal::{Literal, CNF}};
+
+use super::{instance::{SolverResult, SolverStats}, preprocess::unit_propagate};
+
+const UNVISITED: usize = usize::MAX;
+
+/// Solve a CNF whose clauses have at most two literals, see [CNF::is_2sat].
+///
+/// Unit clauses are propagated first. Every remaining clause `a | b` becomes the implications
+/// `-a -> b` and `-b -> a`, so the CNF is unsatisfiable exactly if a literal and its negation imply
+/// each other, i.e. share a strongly connected component. Otherwise, taking the literal of every
+/// variable whose component comes later in topological order gives a model. The model assigns
+/// every variable of the CNF.
+///
+/// Panics if a clause has more than two literals.
+pub fn solve_2sat(cnf: &CNF) -> SolverResult {
+    solve_2sat_with_stats(cnf).0
+}
+
+/// Like [solve_2sat], but also returns statistics. There are no decisions, the literals of unit
+/// clauses are counted as propagations and finding the CNF unsatisfiable as a single conflict.
+pub fn solve_2sat_with_stats(cnf: &CNF) -> (SolverResult, SolverStats) {
+    assert!(cnf.is_2sat(), "Clauses with more than two literals aren't 2-SAT clauses");
+
+    let mut variables = cnf.clauses.iter().flat_map(|clause| &clause.literals).map(|literal| literal.var_id).collect::<Vec<_>>();
+    variables.sort_unstable();
+    variables.dedup();
+
+    let mut reduced = cnf.clone();
+    let mut assignment = Assignment::default();
+    let mut stats = SolverStats { propagations: unit_propagate(&mut reduced, &mut assignment).len() as u64, ..Default::default() };
+    if reduced.clauses.iter().any(|clause| clause.literals.is_empty()) {
+        (stats.conflicts, stats.conflicts_since_restart) = (1, 1);
+        return (SolverResult::Unsat, stats);
+    }
+
+    // literal `v` of the i-th variable is node `2 * i`, `-v` is node `2 * i + 1`
+    let indices = variables.iter().enumerate().map(|(index, var)| (*var, index)).collect::<HashMap<VariableId, usize>>();
+    let node = |literal: Literal| 2 * indices[&literal.var_id] + usize::from(!literal.value);
+
+    let mut graph = vec![Vec::new(); 2 * variables.len()];
+    for clause in &reduced.clauses {
+        // unit propagation leaves exactly two literals
+        let [a, b] = clause.literals[..] else { unreachable!("Clauses have two literals after unit propagation") };
+        graph[node(a.not())].push(node(b));
+        graph[node(b.not())].push(node(a));
+    }
+
+    let components = strongly_connected_components(&graph);
+    for (index, var) in variables.iter().enumerate() {
+        if assignment.values.contains_key(var) {
+            continue;
+        }
+        let (positive, negative) = (components[2 * index], components[2 * index + 1]);
+        if positive == negative {
+            (stats.conflicts, stats.conflicts_since_restart) = (1, 1);
+            return (SolverResult::Unsat, stats);
+        }
+        // components are numbered in reverse topological order, so the literal implied by the
+        // other one has the lower number
+        assignment.values.insert(*var, positive < negative);
+    }
+
+    (SolverResult::Sat(Some(assignment)), stats)
+}
+
+// Tarjan's algorithm without recursion, so long chains of implications don't overflow the stack.
+// Returns the component of every node, numbered in the order they are completed, which is a reverse
+// topological order.
+fn strongly_connected_components(graph: &[Vec<usize>]) -> Vec<usize> {
+    let mut index = vec![UNVISITED; graph.len()];
+    let mut lowlink = vec![0; graph.len()];
+    let mut on_stack = vec![false; graph.len()];
+    let mut component = vec![UNVISITED; graph.len()];
+    let mut stack = Vec::new();
+    let mut next_index = 0;
+    let mut next_component = 0;
+
+    for root in 0..graph.len() {
+        if index[root] != UNVISITED {
+            continue;
+        }
+
+        // nodes being visited and the index of the next edge to follow
+        let mut calls = vec![(root, 0)];
+        index[root] = next_index;
+        lowlink[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some((node, edge)) = calls.pop() {
+            if let Some(&next) = graph[node].get(edge) {
+                calls.push((node, edge + 1));
+                if index[next] == UNVISITED {
+                    index[next] = next_index;
+                    lowlink[next] = next_index;
+                    next_index += 1;
+                    stack.push(next);
+                    on_stack[next] = true;
+                    calls.push((next, 0));
+                } else if on_stack[next] {
+                    lowlink[node] = lowlink[node].min(index[next]);
+                }
+                continue;
+            }
+
+            // every edge is followed, `node` is the root of a component if nothing leads back above it
+            if lowlink[node] == index[node] {
+                loop {
+                    let member = stack.pop().expect("The root is still on the stack");
+                    on_stack[member] = false;
+                    component[member] = next_component;
+                    if member == node {
+                        break;
+                    }
+                }
+                next_component += 1;
+            }
+            if let Some(&(parent, _)) = calls.last() {
+                lowlink[parent] = lowlink[parent].min(lowlink[node]);
+            }
+        }
+    }
+
+    component
+}
+
+#[cfg(test)]
+use crate::{generator::random_ksat_cnf, parser::parse_str, solver::{dpll::solve_dpll_cnf, instance::SolverConfig}};
+
+#[test]
+fn test_solve_2sat() {
+    // a -> b -> c -> d, starting with a
+    let instance = parse_str("a & (-a | b) & (-b | c) & (-c | d)").unwrap();
+    let cnf = CNF::from(instance.expression.clone());
+    let SolverResult::Sat(Some(model)) = solve_2sat(&cnf) else { panic!("expected a model") };
+    assert!(model.satisfies_expression(&instance.expression));
+    assert!(["a", "b", "c", "d"].iter().all(|name| model.values[&instance.str_to_var[*name]]));
+
+    // a -> b -> c -> -a forces a to be false
+    let instance = parse_str("(-a | b) & (-b | c) & (-c | -a) & (a | d)").unwrap();
+    let SolverResult::Sat(Some(model)) = solve_2sat(&CNF::from(instance.expression.clone())) else { panic!("expected a model") };
+    assert!(model.satisfies_expression(&instance.expression));
+    assert!(!model.values[&instance.str_to_var["a"]]);
+}
+
+#[test]
+fn test_solve_2sat_unsat() {
+    // a -> b -> -a -> -b -> a
+    let cycle = parse_str("(-a | b) & (-b | -a) & (a | -b) & (b | a)").unwrap();
+    assert_eq!(solve_2sat(&CNF::from(cycle.expression)), SolverResult::Unsat);
+
+    assert_eq!(solve_2sat(&CNF::from(parse_str("a & (-a | b) & -b").unwrap().expression)), SolverResult::Unsat);
+    assert_eq!(solve_2sat(&CNF::from(parse_str("a & 0").unwrap().expression)), SolverResult::Unsat);
+}
+
+#[test]
+#[should_panic(expected = "Clauses with more than two literals aren't 2-SAT clauses")]
+fn test_solve_2sat_not_2sat() {
+    solve_2sat(&CNF::from(parse_str("a | b | c").unwrap().expression));
+}
+
+#[test]
+fn test_solve_2sat_agrees_with_dpll() {
+    // 2-SAT is satisfiable with high probability below one clause per variable and unsatisfiable
+    // above it
+    for seed in 0..40 {
+        let cnf = random_ksat_cnf(30, 25 + seed as usize, 2, seed);
+        let two_sat = solve_2sat(&cnf);
+
+        match solve_dpll_cnf(cnf.clone(), Assignment::default(), &SolverConfig::default()).0 {
+            SolverResult::Sat(_) => assert!(matches!(&two_sat, SolverResult::Sat(Some(model)) if model.satisfies_cnf(&cnf)), "seed {}", seed),
+            _ => assert_eq!(two_sat, SolverResult::Unsat, "seed {}", seed),
+        }
+    }
+}
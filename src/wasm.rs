@@ -0,0 +1,66 @@
+// WebAssembly bindings for solving formulas in the browser.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{expression::{expression::Assignment, normal::CNF}, parser::{parse_dimacs_str, parse_str}, solver::{dpll::solve_dpll, instance::{SATInstance, SolverResult}}};
+
+/// A parsed formula, which JavaScript only sees as an opaque handle.
+#[wasm_bindgen]
+pub struct Formula {
+    instance: SATInstance,
+}
+
+/// The object returned by [solve] and [solve_dimacs].
+#[derive(Debug, Serialize)]
+struct Solution {
+    /// `SAT`, `UNSAT` or `UNKNOWN`
+    status: &'static str,
+    /// Variable names mapped to their values, `null` unless satisfiable
+    model: Option<BTreeMap<String, bool>>,
+}
+
+/// Parse a formula in the expression syntax, throwing an error message if it is invalid.
+#[wasm_bindgen]
+pub fn parse_formula(s: &str) -> Result<Formula, JsValue> {
+    parse_str(s)
+        .map(|instance| Formula { instance })
+        .map_err(|err| JsValue::from_str(&err.report(s)))
+}
+
+/// Solve a formula, returning `{ status, model }`.
+#[wasm_bindgen]
+pub fn solve(formula: &Formula) -> JsValue {
+    solution(&formula.instance)
+}
+
+/// Parse and solve a formula in DIMACS CNF, throwing an error message if it is invalid.
+#[wasm_bindgen]
+pub fn solve_dimacs(text: &str) -> Result<JsValue, JsValue> {
+    let instance = parse_dimacs_str(text).map_err(|err| JsValue::from_str(&err.to_string()))?;
+
+    Ok(solution(&instance))
+}
+
+/// The CNF of a formula in the expression syntax.
+#[wasm_bindgen]
+pub fn to_cnf_string(formula: &Formula) -> String {
+    let instance = &formula.instance;
+    let cnf = CNF::from(instance.expression.clone());
+
+    SATInstance::new(cnf.to_expression(), instance.var_to_str.clone()).display_expression().to_string()
+}
+
+fn solution(instance: &SATInstance) -> JsValue {
+    let model = |assignment: &Assignment| assignment.values.iter().map(|(var, value)| (instance.var_to_str[var].clone(), *value)).collect();
+
+    let solution = match solve_dpll(instance.clone(), Assignment::default()) {
+        SolverResult::Sat(assignment) => Solution { status: "SAT", model: Some(assignment.as_ref().map(model).unwrap_or_default()) },
+        SolverResult::Unsat => Solution { status: "UNSAT", model: None },
+        SolverResult::Unknown => Solution { status: "UNKNOWN", model: None },
+    };
+
+    serde_wasm_bindgen::to_value(&solution).expect("Solutions are serializable")
+}
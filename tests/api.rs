@@ -0,0 +1,47 @@
+// The library as seen by a dependent crate, only through the re-exports at the crate root.
+
+use std::collections::HashMap;
+
+use sat_solver::{parse_dimacs_str, parse_expression, parse_str, solve, Assignment, Clause, Expression, Literal, ParseError, SATInstance, SolverResult, CNF};
+
+#[test]
+fn test_solve_parsed() {
+    let instance = parse_str("(a | b) & (-a | c) & -c").unwrap();
+    let SolverResult::Sat(Some(model)) = solve(&instance) else { panic!("expected a model") };
+    assert!(model.satisfies_expression(&instance.expression));
+    assert!(model.values[&instance.str_to_var["b"]]);
+
+    assert_eq!(solve(&parse_str("a & -a").unwrap()), SolverResult::Unsat);
+    assert_eq!(solve(&parse_dimacs_str("p cnf 1 2\n1 0\n-1 0\n").unwrap()), SolverResult::Unsat);
+}
+
+#[test]
+fn test_build_expressions() {
+    // (x0 | x1) & -x0
+    let expression = Expression::and(Expression::or(Expression::var(0), Expression::var(1)), Expression::not(Expression::var(0)));
+    let names = HashMap::from([(0, "x0".to_string()), (1, "x1".to_string())]);
+    let instance = SATInstance::new(expression.clone(), names);
+
+    let SolverResult::Sat(Some(model)) = solve(&instance) else { panic!("expected a model") };
+    assert_eq!(model, Assignment::from([(0, false), (1, true)]));
+    assert_eq!(expression, parse_str("(a | b) & -a").unwrap().expression);
+}
+
+#[test]
+fn test_clauses() {
+    let cnf = CNF::new(vec![
+        Clause::new(vec![Literal::new(0, true), Literal::new(1, true)]),
+        Clause::new(vec![Literal::new(0, false)]),
+    ]);
+    let instance = SATInstance::new(cnf.clone().to_expression(), HashMap::new());
+
+    let SolverResult::Sat(Some(model)) = solve(&instance) else { panic!("expected a model") };
+    assert!(model.satisfies_cnf(&cnf));
+    assert_eq!(CNF::from(instance.expression).clauses.len(), 2);
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(matches!(parse_str("a & & b"), Err(ParseError::UnexpectedToken { byte_offset: 4, found: '&', .. })));
+    assert!(matches!(parse_expression("  "), Err(ParseError::Empty)));
+}
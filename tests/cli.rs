@@ -0,0 +1,727 @@
+// Integration tests driving the sat-solver binary.
+
+use std::{collections::BTreeMap, io::Write, process::{Command, Output, Stdio}};
+
+use serde::Deserialize;
+
+fn run(args: &[&str]) -> Output {
+    run_with_stdin(args, "")
+}
+
+fn run_with_stdin(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sat-solver"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Couldn't run the binary");
+
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+#[test]
+fn test_solve_expression() {
+    let output = run(&["solve", "tests/fixtures/simple.sat", "--color", "never"]);
+
+    assert_eq!(output.status.code(), Some(10));
+    assert_eq!(stdout(&output), "SAT\na = true\nb = true\nc = false\n");
+}
+
+#[test]
+fn test_solve_unsat() {
+    let output = run(&["solve", "tests/fixtures/unsat.sat", "--color", "never"]);
+
+    assert_eq!(output.status.code(), Some(20));
+    assert_eq!(stdout(&output), "UNSAT\n");
+}
+
+#[test]
+fn test_solve_dimacs_competition() {
+    let output = run(&["solve", "tests/fixtures/simple.cnf", "--output", "competition"]);
+    assert_eq!(output.status.code(), Some(10));
+    assert_eq!(stdout(&output), "s SATISFIABLE\nv -1 2 0\n");
+
+    let output = run(&["solve", "tests/fixtures/unsat.cnf", "--output", "competition"]);
+    assert_eq!(output.status.code(), Some(20));
+    assert_eq!(stdout(&output), "s UNSATISFIABLE\n");
+}
+
+// every line of the output of a competition solver starts with `s `, `v ` or `c `
+fn assert_competition_output(output: &str) {
+    for line in output.lines() {
+        assert!(["s ", "v ", "c "].iter().any(|prefix| line.starts_with(prefix)), "non-conforming line '{}'", line);
+    }
+}
+
+#[test]
+fn test_solve_competition_mode() {
+    let cases = [
+        ("tests/fixtures/simple.cnf", 10, "s SATISFIABLE", Some("v -1 2 0")),
+        ("tests/fixtures/simple.sat", 10, "s SATISFIABLE", Some("v 1 2 -3 0")),
+        ("tests/fixtures/unsat.cnf", 20, "s UNSATISFIABLE", None),
+        ("tests/fixtures/unsat.sat", 20, "s UNSATISFIABLE", None),
+    ];
+
+    for (path, code, status, values) in cases {
+        let output = run(&["solve", path, "--competition", "--color", "always"]);
+        let stdout = stdout(&output);
+
+        assert_eq!(output.status.code(), Some(code), "wrong exit code for {}", path);
+        assert_competition_output(&stdout);
+        assert_eq!(stdout.lines().filter(|line| line.starts_with("s ")).collect::<Vec<_>>(), [status]);
+        assert_eq!(stdout.lines().find(|line| line.starts_with("v ")), values);
+        assert!(stderr(&output).is_empty());
+    }
+
+    let output = run(&["solve", "tests/fixtures/invalid.sat", "--competition"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).is_empty());
+    assert!(stderr(&output).contains("error"));
+}
+
+#[test]
+fn test_solve_2sat() {
+    let output = run(&["solve", "tests/fixtures/simple.sat", "-v", "--color", "never"]);
+    assert_eq!(output.status.code(), Some(10));
+    assert!(stdout(&output).contains("c 2-SAT instance, solved with the implication graph\n"));
+    assert!(stdout(&output).ends_with("SAT\na = true\nb = true\nc = false\n"));
+
+    // three literals in a clause
+    let output = run_with_stdin(&["solve", "-", "-v", "--color", "never"], "(a | b | c) & -a & -b");
+    assert!(!stdout(&output).contains("2-SAT"));
+    assert!(stdout(&output).ends_with("SAT\na = false\nb = false\nc = true\n"));
+}
+
+#[test]
+fn test_solve_parity() {
+    // converting by distribution takes forever on this, the Tseitin encoding is linear
+    let parity = (1..22).fold("x0".to_string(), |chain, var| format!("({} ^ x{})", chain, var));
+    let output = run_with_stdin(&["solve", "-", "--verify", "--color", "never"], &parity);
+    assert_eq!(output.status.code(), Some(10));
+
+    let text = stdout(&output);
+    let model = text.lines().filter(|line| line.contains(" = ")).collect::<Vec<_>>();
+    assert_eq!(model.len(), 22, "auxiliary variables are left out: {}", text);
+    assert_eq!(model.iter().filter(|line| line.ends_with("true")).count() % 2, 1);
+    assert!(text.ends_with("c VERIFIED model\n"));
+
+    let output = run_with_stdin(&["solve", "-", "--all-models", "--limit", "3"], &parity);
+    assert_eq!(stdout(&output).lines().count(), 3);
+}
+
+#[test]
+fn test_solve_stdin() {
+    let output = run_with_stdin(&["solve", "-", "--color", "never"], "x & -y");
+
+    assert_eq!(output.status.code(), Some(10));
+    assert_eq!(stdout(&output), "SAT\nx = true\ny = false\n");
+}
+
+#[test]
+fn test_solve_assumptions() {
+    let output = run(&["solve", "tests/fixtures/simple.sat", "--assume", "a=0", "--color", "never"]);
+    assert_eq!(output.status.code(), Some(20));
+    assert_eq!(stdout(&output), "UNSAT\n");
+
+    let output = run(&["solve", "tests/fixtures/simple.cnf", "--assume", "-2", "--color", "never"]);
+    assert_eq!(stdout(&output), "UNSAT\n");
+
+    let output = run(&["solve", "tests/fixtures/simple.sat", "--assume", "a=1,b,-c", "--color", "never"]);
+    assert_eq!(stdout(&output), "SAT\na = true\nb = true\nc = false\n");
+}
+
+#[test]
+fn test_solve_errors() {
+    for args in [
+        &["solve", "tests/fixtures/missing.sat"][..],
+        &["solve", "tests/fixtures/invalid.sat"],
+        &["solve", "tests/fixtures/simple.sat", "--format", "dimacs"],
+        &["solve", "tests/fixtures/simple.sat", "--assume", "d"],
+        &["solve", "tests/fixtures/simple.sat", "--assume", "a=2"],
+    ] {
+        let output = run(args);
+
+        assert!(!output.status.success(), "{:?} should fail", args);
+        assert!(stdout(&output).is_empty());
+        assert!(stderr(&output).contains("error"));
+    }
+}
+
+#[test]
+fn test_solve_syntax_error() {
+    let output = run(&["solve", "tests/fixtures/invalid.sat"]);
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("line 1: unexpected '&' at byte 4"), "{}", stderr(&output));
+    assert!(stderr(&output).contains("  a & & b\n      ^\n"), "{}", stderr(&output));
+}
+
+#[test]
+fn test_solve_all_models() {
+    let output = run(&["solve", "tests/fixtures/six.sat", "--all-models"]);
+    assert!(output.status.success());
+
+    let mut models = stdout(&output).lines().map(str::to_string).collect::<Vec<_>>();
+    assert_eq!(models.len(), 6);
+    models.sort();
+    models.dedup();
+    assert_eq!(models.len(), 6);
+    assert!(!models.contains(&"-a -b -c".to_string()) && !models.contains(&"a b c".to_string()));
+
+    let output = run(&["solve", "tests/fixtures/six.sat", "--all-models", "--limit", "3"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output).lines().count(), 3);
+
+    let output = run(&["solve", "tests/fixtures/six.sat", "--all-models", "--project", "a,b"]);
+    assert_eq!(stdout(&output).lines().count(), 4);
+}
+
+#[test]
+fn test_solve_count() {
+    for args in [&["--count"][..], &["--count", "--exact"], &["--count", "--approx", "--epsilon", "0.2", "--delta", "0.1"]] {
+        let output = run(&[&["solve", "tests/fixtures/six.sat"][..], args].concat());
+
+        assert!(output.status.success());
+        assert_eq!(stdout(&output), "6\n");
+    }
+
+    let output = run(&["solve", "tests/fixtures/six.sat", "--count", "--project", "a"]);
+    assert_eq!(stdout(&output), "2\n");
+}
+
+#[test]
+fn test_solve_exclusive_modes() {
+    for args in [
+        &["--all-models", "--count"][..],
+        &["--all-models", "--competition"],
+        &["--count", "--competition"],
+        &["--count", "--exact", "--approx"],
+        &["--limit", "3"],
+        &["--count", "--epsilon", "0.2"],
+    ] {
+        let output = run(&[&["solve", "tests/fixtures/six.sat"][..], args].concat());
+
+        assert_eq!(output.status.code(), Some(2), "{:?} should be rejected", args);
+        assert!(stdout(&output).is_empty());
+    }
+}
+
+fn config_lines(output: &Output) -> Vec<String> {
+    stdout(output).lines().filter(|line| line.starts_with("c ") && line.contains(" = ")).map(str::to_string).collect()
+}
+
+#[test]
+fn test_solve_config() {
+    let output = run(&["solve", "tests/fixtures/simple.cnf", "-v", "--timeout", "1m 30s", "--seed", "42", "--heuristic", "vsids", "--no-pure-literal", "--restarts", "luby:100"]);
+    assert_eq!(output.status.code(), Some(10));
+    assert_eq!(config_lines(&output), ["c timeout = 1m 30s", "c seed = 42", "c heuristic = vsids", "c no-pure-literal = true", "c restarts = luby:100"]);
+
+    let config = format!("{}/solver.toml", env!("CARGO_TARGET_TMPDIR"));
+    std::fs::write(&config, "timeout = \"30s\"\nseed = 1\nheuristic = \"jw\"\nrestarts = \"luby:10\"\n").unwrap();
+
+    // flags take precedence over the file
+    let output = run(&["solve", "tests/fixtures/simple.cnf", "-v", "--config", &config, "--seed", "2", "--restarts", "none"]);
+    assert_eq!(output.status.code(), Some(10));
+    assert_eq!(config_lines(&output), ["c timeout = 30s", "c seed = 2", "c heuristic = jw", "c no-pure-literal = false", "c restarts = none"]);
+
+    let output = run(&["solve", "tests/fixtures/simple.cnf", "-v", "--restarts", "geometric:100:1.5"]);
+    assert_eq!(output.status.code(), Some(10));
+    assert!(config_lines(&output).contains(&"c restarts = geometric:100:1.5".to_string()));
+}
+
+#[test]
+fn test_solve_config_errors() {
+    let output = run(&["solve", "tests/fixtures/simple.cnf", "--timeout", "30 parsecs"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("--timeout"));
+
+    let output = run(&["solve", "tests/fixtures/simple.cnf", "--restarts", "geometric"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    for (content, message) in [("seed = 1\nheuristc = \"jw\"\n", "heuristc"), ("timeout = \"soon\"\n", "soon")] {
+        let config = format!("{}/invalid-{}.toml", env!("CARGO_TARGET_TMPDIR"), message);
+        std::fs::write(&config, content).unwrap();
+
+        let output = run(&["solve", "tests/fixtures/simple.cnf", "--config", &config]);
+        assert!(!output.status.success());
+        assert!(stderr(&output).contains(message), "{}", stderr(&output));
+    }
+}
+
+#[test]
+fn test_solve_timeout() {
+    let php = format!("{}/timeout-php.cnf", env!("CARGO_TARGET_TMPDIR"));
+    assert!(run(&["generate", "php", "--holes", "9", "-o", &php]).status.success());
+
+    let output = run(&["solve", &php, "--competition", "--timeout", "1ms"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_competition_output(&stdout(&output));
+    assert_eq!(stdout(&output).lines().filter(|line| line.starts_with("s ")).collect::<Vec<_>>(), ["s UNKNOWN"]);
+
+    let output = run(&["solve", &php, "--output", "competition", "--timeout", "1ms"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "s UNKNOWN\n");
+
+    let output = run(&["solve", &php, "--timeout", "1ms", "--color", "never"]);
+    assert_eq!(output.status.code(), Some(30));
+    assert_eq!(stdout(&output), "UNKNOWN\n");
+}
+
+#[test]
+fn test_solve_stats() {
+    let output = run(&["solve", "tests/fixtures/simple.sat", "--stats", "--color", "never"]);
+    assert_eq!(output.status.code(), Some(10));
+    let text = stdout(&output);
+    assert!(text.starts_with("SAT\n"));
+    for field in ["decisions:", "propagations:", "pure literals:", "conflicts:", "restarts:", "since restart:", "max depth:", "time:"] {
+        assert!(text.lines().any(|line| line.starts_with(field)), "missing {}", field);
+    }
+
+    let output = run(&["solve", "tests/fixtures/unsat.cnf", "--stats", "--competition"]);
+    assert_eq!(output.status.code(), Some(20));
+    assert_competition_output(&stdout(&output));
+    assert!(stdout(&output).contains("c conflicts:"));
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonResult {
+    verdict: String,
+    model: Option<BTreeMap<String, bool>>,
+    stats: JsonStats,
+    config: BTreeMap<String, serde_json::Value>,
+    time: f64,
+    verified: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonStats {
+    decisions: u64,
+    propagations: u64,
+    pure_literals: u64,
+    conflicts: u64,
+    restarts: u64,
+    conflicts_since_restart: u64,
+    max_depth: u64,
+}
+
+fn solve_json(args: &[&str]) -> JsonResult {
+    let output = run(&[&["solve", "--json"][..], args].concat());
+    let result: JsonResult = serde_json::from_str(&stdout(&output)).unwrap();
+
+    let code = match result.verdict.as_str() {
+        "SAT" => 10,
+        "UNSAT" => 20,
+        _ => 30,
+    };
+    assert_eq!(output.status.code(), Some(code));
+    result
+}
+
+#[test]
+fn test_solve_json() {
+    let result = solve_json(&["tests/fixtures/simple.sat", "--seed", "3"]);
+    assert_eq!(result.verdict, "SAT");
+    assert_eq!(result.model, Some(BTreeMap::from([("a".to_string(), true), ("b".to_string(), true), ("c".to_string(), false)])));
+    assert_eq!(result.config["seed"], 3);
+    assert_eq!(result.config["heuristic"], "random");
+    assert_eq!((result.stats.decisions, result.stats.propagations, result.stats.pure_literals, result.stats.max_depth), (0, 3, 0, 0));
+    assert!(result.time >= 0.0);
+    assert_eq!(result.verified, None);
+
+    let result = solve_json(&["tests/fixtures/unsat.cnf"]);
+    assert_eq!(result.verdict, "UNSAT");
+    assert_eq!(result.model, None);
+    assert!(result.stats.conflicts > 0);
+    assert_eq!(result.stats.conflicts_since_restart, result.stats.conflicts);
+
+    let php = format!("{}/json-php.cnf", env!("CARGO_TARGET_TMPDIR"));
+    assert!(run(&["generate", "php", "--holes", "9", "-o", &php]).status.success());
+    let result = solve_json(&[&php, "--timeout", "1ms"]);
+    assert_eq!(result.verdict, "UNKNOWN");
+    assert_eq!(result.model, None);
+    assert_eq!(result.config["timeout"], "1ms");
+    assert_eq!(result.stats.restarts, 0);
+}
+
+#[test]
+fn test_solve_json_all_models() {
+    let output = run(&["solve", "tests/fixtures/six.sat", "--json", "--all-models"]);
+    assert!(output.status.success());
+
+    let models: Vec<BTreeMap<String, bool>> = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(models.len(), 6);
+    assert!(models.iter().all(|model| model.len() == 3));
+}
+
+#[test]
+fn test_solve_verify() {
+    let output = run(&["solve", "tests/fixtures/simple.sat", "--verify", "--color", "never"]);
+    assert_eq!(output.status.code(), Some(10));
+    assert_eq!(stdout(&output), "SAT\na = true\nb = true\nc = false\nc VERIFIED model\n");
+
+    let output = run(&["solve", "tests/fixtures/unsat.cnf", "--verify", "--competition"]);
+    assert_eq!(output.status.code(), Some(20));
+    assert_eq!(stdout(&output).lines().filter(|line| !line.starts_with("c sat-solver")).collect::<Vec<_>>(), ["c 2 variables", "s UNSATISFIABLE", "c VERIFIED re-solve"]);
+
+    let result = solve_json(&["tests/fixtures/simple.cnf", "--verify"]);
+    assert_eq!(result.verified.as_deref(), Some("model"));
+}
+
+#[test]
+fn test_solve_progress() {
+    let php = format!("{}/progress-php.cnf", env!("CARGO_TARGET_TMPDIR"));
+    assert!(run(&["generate", "php", "--holes", "9", "-o", &php]).status.success());
+
+    let output = run(&["solve", &php, "--progress", "--timeout", "1s", "--color", "never"]);
+    assert_eq!(output.status.code(), Some(30));
+    assert_eq!(stdout(&output), "UNKNOWN\n");
+    // stderr isn't a terminal, so there are plain log lines instead of an updating line
+    assert!(stderr(&output).lines().any(|line| line.starts_with("c progress: ") && line.contains("decisions/s")), "{}", stderr(&output));
+    assert!(!stderr(&output).contains(['\r', '\x1b']));
+}
+
+fn verdict(path: &str) -> String {
+    let output = run(&["solve", path, "--output", "competition"]);
+    assert!(matches!(output.status.code(), Some(10 | 20)), "solving {} failed", path);
+    stdout(&output).lines().next().unwrap().to_string()
+}
+
+#[test]
+fn test_convert_preserves_satisfiability() {
+    let conversions = [
+        ("simple.sat", "dimacs", "tseitin"),
+        ("simple.sat", "dimacs", "distribute"),
+        ("unsat.sat", "dimacs", "tseitin"),
+        ("unsat.sat", "dimacs", "distribute"),
+        ("simple.cnf", "expr", "distribute"),
+        ("unsat.cnf", "expr", "distribute"),
+    ];
+
+    for (input, to, encoding) in conversions {
+        let input = format!("tests/fixtures/{}", input);
+        let converted = format!("{}/convert-{}-{}-{}", env!("CARGO_TARGET_TMPDIR"), input.replace('/', "_"), to, encoding);
+
+        let output = run(&["convert", &input, "--to", to, "--encoding", encoding, "-o", &converted]);
+        assert!(output.status.success(), "converting {} failed: {}", input, stderr(&output));
+        assert!(stdout(&output).is_empty());
+
+        assert_eq!(verdict(&input), verdict(&converted), "{} changed satisfiability", converted);
+    }
+}
+
+#[test]
+fn test_convert_output() {
+    let map = format!("{}/convert-simple.map", env!("CARGO_TARGET_TMPDIR"));
+    let output = run(&["convert", "tests/fixtures/simple.cnf", "--from", "dimacs", "--to", "expr", "--map", &map]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "(x1 | x2) & -x1\n");
+    assert_eq!(std::fs::read_to_string(&map).unwrap(), "x1 1\nx2 2\n");
+
+    let output = run_with_stdin(&["convert", "-", "--to", "dimacs"], "a & -b");
+    assert!(output.status.success());
+    let dimacs = stdout(&output);
+    assert!(dimacs.starts_with("p cnf 2 2\n"));
+    assert!(dimacs.contains("1 0\n"));
+    assert!(dimacs.contains("-2 0\n"));
+}
+
+#[test]
+fn test_convert_unsupported() {
+    let output = run(&["convert", "tests/fixtures/simple.sat", "--to", "expr"]);
+
+    assert!(!output.status.success());
+    assert!(stdout(&output).is_empty());
+    assert!(stderr(&output).contains("expr -> dimacs, dimacs -> expr"));
+}
+
+#[test]
+fn test_generate_golden() {
+    let cases = [
+        (&["generate", "random-ksat", "--vars", "10", "--ratio", "4.2", "--seed", "3"][..], "random-ksat.cnf"),
+        (&["generate", "php", "--holes", "3"], "php.cnf"),
+        (&["generate", "php", "--holes", "2", "--format", "expr"], "php.sat"),
+    ];
+
+    for (args, golden) in cases {
+        let output = run(args);
+
+        assert!(output.status.success());
+        assert_eq!(stdout(&output), std::fs::read_to_string(format!("tests/fixtures/golden/{}", golden)).unwrap(), "{} differs", golden);
+    }
+}
+
+#[test]
+fn test_generate_sudoku() {
+    let generate = |seed: &str| stdout(&run(&["generate", "sudoku", "--blank", "50", "--seed", seed]));
+
+    let dimacs = generate("1");
+    assert!(dimacs.starts_with("c generated by sat-solver generate sudoku --blank 50 --seed 1\np cnf 729 "));
+    assert_eq!(dimacs.lines().filter(|line| line.split_whitespace().count() == 2).count(), 81 - 50);
+    assert_eq!(dimacs, generate("1"));
+    assert_ne!(dimacs, generate("2"));
+}
+
+#[test]
+fn test_generate_then_solve() {
+    let php = format!("{}/generate-php.cnf", env!("CARGO_TARGET_TMPDIR"));
+    assert!(run(&["generate", "php", "--holes", "3", "-o", &php]).status.success());
+    assert_eq!(verdict(&php), "s UNSATISFIABLE");
+
+    let sudoku = stdout(&run(&["generate", "sudoku", "--blank", "30", "--seed", "7", "--format", "expr"]));
+    let output = run_with_stdin(&["solve", "-", "--output", "competition"], &sudoku);
+    assert_eq!(stdout(&output).lines().next(), Some("s SATISFIABLE"));
+
+    let ksat = stdout(&run(&["generate", "random-ksat", "--vars", "20", "--ratio", "2"]));
+    let output = run_with_stdin(&["solve", "-", "--output", "competition"], &ksat);
+    assert_eq!(stdout(&output).lines().next(), Some("s SATISFIABLE"));
+}
+
+#[test]
+fn test_generate_errors() {
+    for args in [
+        &["generate", "random-ksat", "--vars", "2", "--ratio", "1", "--k", "3"][..],
+        &["generate", "random-ksat", "--vars", "3", "--ratio", "100"],
+        &["generate", "random-ksat", "--vars", "3", "--ratio", "-1"],
+    ] {
+        let output = run(args);
+
+        assert!(!output.status.success(), "{:?} should fail", args);
+        assert!(stderr(&output).contains("error"));
+    }
+}
+
+#[test]
+fn test_run_project() {
+    // the instance path is relative to the project file, not the working directory
+    let output = run(&["run", "tests/fixtures/simple.satproj", "--color", "never"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "SAT\na = true\nb = true\nc = false\n");
+
+    let project = format!("{}/inline.satproj", env!("CARGO_TARGET_TMPDIR"));
+    std::fs::write(&project, "assumptions = [\"-1\"]\n\n[instance]\ndimacs = \"p cnf 2 2\\n1 2 0\\n-1 -2 0\\n\"\n\n[expected]\nverdict = \"SAT\"\n").unwrap();
+    let output = run(&["run", &project, "--color", "never"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "SAT\n1 = false\n2 = true\n");
+}
+
+#[test]
+fn test_run_project_errors() {
+    for (name, content, message) in [
+        ("mismatch", "[instance]\nexpression = \"a & -a\"\n\n[expected]\nverdict = \"SAT\"\n", "expected SAT, but the result is UNSAT"),
+        ("assumption", "assumptions = [\"d\"]\n\n[instance]\nexpression = \"a | b\"\n", "unknown variable 'd'"),
+        ("missing", "[instance]\npath = \"missing.sat\"\n", "missing.sat"),
+        ("unknown-key", "[instance]\nexpression = \"a\"\n\n[expected]\nverdict = \"SAT\"\nmodels = 1\n", "models"),
+    ] {
+        let project = format!("{}/{}.satproj", env!("CARGO_TARGET_TMPDIR"), name);
+        std::fs::write(&project, content).unwrap();
+
+        let output = run(&["run", &project]);
+        assert!(!output.status.success(), "{} should fail", name);
+        assert!(stderr(&output).contains(message), "{}", stderr(&output));
+    }
+}
+
+#[test]
+fn test_color_choice() {
+    let run_colored = |color: &str, no_color: bool| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_sat-solver"));
+        command.args(["solve", "tests/fixtures/missing.sat", "--color", color]).current_dir(env!("CARGO_MANIFEST_DIR"));
+        if no_color {
+            command.env("NO_COLOR", "1");
+        }
+        let output = command.output().unwrap();
+        assert!(!output.status.success());
+
+        stderr(&output)
+    };
+
+    // colors are compiled out without the color feature
+    let colors = cfg!(all(feature = "color", not(feature = "no-color")));
+    assert_eq!(run_colored("always", false).contains('\x1b'), colors);
+    // the flag takes precedence over the environment
+    assert_eq!(run_colored("always", true).contains('\x1b'), colors);
+    assert!(!run_colored("never", false).contains('\x1b'));
+    // output isn't a terminal
+    assert!(!run_colored("auto", false).contains('\x1b'));
+
+    for (args, code) in [(&["solve", "tests/fixtures/simple.sat", "--verify"][..], 10), (&["solve", "tests/fixtures/unsat.sat"], 20), (&["convert", "tests/fixtures/simple.cnf", "--to", "expr"], 0)] {
+        let output = run(&[args, &["--color", "never"]].concat());
+        assert_eq!(output.status.code(), Some(code));
+        assert!(!stdout(&output).contains('\x1b'));
+    }
+}
+
+#[test]
+fn test_compare() {
+    let corpus = format!("{}/compare-corpus", env!("CARGO_TARGET_TMPDIR"));
+    std::fs::create_dir_all(&corpus).unwrap();
+    std::fs::write(format!("{}/sat.sat", corpus), "(a | b) & -a").unwrap();
+    std::fs::write(format!("{}/unsat.cnf", corpus), "p cnf 1 2\n1 0\n-1 0\n").unwrap();
+
+    let output = run(&["compare", &corpus]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let text = stdout(&output);
+    let lines = text.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("instance") && lines[0].contains("dpll-vsids") && lines[0].contains("cdcl"));
+    assert!(lines[1].starts_with("sat.sat") && lines[1].matches("SAT").count() == 4);
+    assert!(lines[2].starts_with("unsat.cnf") && lines[2].matches("UNSAT").count() == 4);
+
+    // the fake solver claims every formula is satisfiable with everything true, which is wrong for both
+    let output = run(&["compare", &corpus, "--external", "tests/fixtures/fake-solver.sh", "--json"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("1 disagreements and 2 invalid models"), "{}", stderr(&output));
+
+    let report: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(report["disagreements"][0]["instance"], "unsat.cnf");
+    assert_eq!(report["disagreements"][0]["suspects"], serde_json::json!(["tests/fixtures/fake-solver.sh"]));
+    assert_eq!(report["instances"][1]["runs"][4]["verdict"], "SAT");
+}
+
+// a running `serve` process, killed when dropped
+struct Server(std::process::Child);
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn spawn_server(args: &[&str]) -> Server {
+    let child = Command::new(env!("CARGO_BIN_EXE_sat-solver"))
+        .arg("serve")
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Couldn't run the binary");
+
+    Server(child)
+}
+
+// send a request line and read the next response line
+fn request(stream: &mut impl Write, responses: &mut impl std::io::BufRead, line: &str) -> serde_json::Value {
+    writeln!(stream, "{}", line).unwrap();
+    response(responses)
+}
+
+fn response(responses: &mut impl std::io::BufRead) -> serde_json::Value {
+    let mut line = String::new();
+    responses.read_line(&mut line).unwrap();
+    serde_json::from_str(&line).unwrap_or_else(|err| panic!("invalid response {:?}: {}", line, err))
+}
+
+#[cfg(unix)]
+#[test]
+fn test_serve_session() {
+    use std::{io::BufReader, os::unix::net::UnixStream, time::{Duration, Instant}};
+
+    let path = format!("{}/serve-{}.sock", env!("CARGO_TARGET_TMPDIR"), std::process::id());
+    let _server = spawn_server(&["--socket", &path]);
+
+    let start = Instant::now();
+    let mut stream = loop {
+        match UnixStream::connect(&path) {
+            Ok(stream) => break stream,
+            Err(_) if start.elapsed() < Duration::from_secs(10) => std::thread::sleep(Duration::from_millis(20)),
+            Err(err) => panic!("the server didn't start: {}", err),
+        }
+    };
+    let mut responses = BufReader::new(stream.try_clone().unwrap());
+
+    let loaded = request(&mut stream, &mut responses, r#"{"id": 1, "type": "load", "expression": "(a | b) & -a"}"#);
+    assert_eq!(loaded, serde_json::json!({"id": 1, "type": "loaded", "variables": 2}));
+
+    let result = request(&mut stream, &mut responses, r#"{"id": 2, "type": "solve"}"#);
+    assert_eq!(result["id"], 2);
+    assert_eq!(result["type"], "result");
+    assert_eq!(result["verdict"], "SAT");
+    assert_eq!(result["model"], serde_json::json!({"a": false, "b": true}));
+
+    let added = request(&mut stream, &mut responses, r#"{"id": 3, "type": "add-clause", "literals": ["-b", "c"]}"#);
+    assert_eq!(added, serde_json::json!({"id": 3, "type": "clause-added", "variables": 3}));
+
+    let result = request(&mut stream, &mut responses, r#"{"id": 4, "type": "solve", "assumptions": ["-c"], "timeout": 10}"#);
+    assert_eq!(result["verdict"], "UNSAT");
+    assert_eq!(request(&mut stream, &mut responses, r#"{"id": 5, "type": "model"}"#), serde_json::json!({"id": 5, "type": "model", "model": null}));
+
+    let result = request(&mut stream, &mut responses, r#"{"type": "solve"}"#);
+    assert_eq!(result["id"], serde_json::Value::Null);
+    assert_eq!(result["model"], serde_json::json!({"a": false, "b": true, "c": true}));
+    let stats = request(&mut stream, &mut responses, r#"{"id": "stats", "type": "stats"}"#);
+    assert_eq!(stats["id"], "stats");
+    assert!(stats["stats"]["propagations"].as_u64().unwrap() > 0);
+
+    // malformed requests are answered with errors and the connection stays usable
+    let error = request(&mut stream, &mut responses, "not json");
+    assert_eq!(error["type"], "error");
+    assert!(error["message"].as_str().unwrap().starts_with("invalid JSON"));
+    stream.write_all(b"{\"id\": 5, \"type\": \"\xff\"}\n").unwrap();
+    let error = response(&mut responses);
+    assert_eq!(error["type"], "error");
+    assert!(error["message"].as_str().unwrap().starts_with("requests must be UTF-8"));
+    let error = request(&mut stream, &mut responses, r#"{"id": 6, "type": "frobnicate"}"#);
+    assert_eq!((&error["id"], &error["type"]), (&serde_json::json!(6), &serde_json::json!("error")));
+    let error = request(&mut stream, &mut responses, r#"{"id": 7, "type": "solve", "assumptions": ["d"]}"#);
+    assert_eq!(error["message"], "unknown variable 'd' in assumption");
+    let error = request(&mut stream, &mut responses, r#"{"id": 8, "type": "load", "dimacs": "p cnf 1 1\n2 0\n"}"#);
+    assert_eq!(error["type"], "error");
+
+    let loaded = request(&mut stream, &mut responses, r#"{"id": 9, "type": "load", "dimacs": "p cnf 1 2\n1 0\n-1 0\n"}"#);
+    assert_eq!(loaded["variables"], 1);
+    assert_eq!(request(&mut stream, &mut responses, r#"{"id": 10, "type": "solve"}"#)["verdict"], "UNSAT");
+}
+
+#[test]
+fn test_serve_cancel() {
+    use std::{io::{BufRead, BufReader}, net::TcpStream, time::{Duration, Instant}};
+
+    let mut server = spawn_server(&["--tcp", "127.0.0.1:0"]);
+    let mut line = String::new();
+    BufReader::new(server.0.stderr.take().unwrap()).read_line(&mut line).unwrap();
+    let address = line.trim().strip_prefix("c listening on ").unwrap_or_else(|| panic!("unexpected output {:?}", line));
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    let mut responses = BufReader::new(stream.try_clone().unwrap());
+
+    let php = stdout(&run(&["generate", "php", "--holes", "10"]));
+    let load = serde_json::json!({"id": 1, "type": "load", "dimacs": php});
+    assert_eq!(request(&mut stream, &mut responses, &load.to_string())["type"], "loaded");
+
+    writeln!(stream, r#"{{"id": 2, "type": "solve"}}"#).unwrap();
+    let error = request(&mut stream, &mut responses, r#"{"id": 3, "type": "add-clause", "literals": ["1"]}"#);
+    assert_eq!(error["message"], "a solve is running, cancel it or wait for the result");
+
+    std::thread::sleep(Duration::from_millis(200));
+    let start = Instant::now();
+    let cancelled = request(&mut stream, &mut responses, r#"{"id": 4, "type": "cancel"}"#);
+    assert_eq!(cancelled, serde_json::json!({"id": 4, "type": "cancelled", "running": true}));
+
+    let result = response(&mut responses);
+    assert_eq!((&result["id"], &result["verdict"]), (&serde_json::json!(2), &serde_json::json!("UNKNOWN")));
+    assert!(start.elapsed() < Duration::from_secs(2), "cancelling took {:?}", start.elapsed());
+
+    let cancelled = request(&mut stream, &mut responses, r#"{"id": 5, "type": "cancel"}"#);
+    assert_eq!(cancelled["running"], false);
+
+    // the session keeps its solver after the cancelled solve
+    let added = request(&mut stream, &mut responses, r#"{"id": 6, "type": "add-clause", "literals": ["1"]}"#);
+    assert_eq!(added["type"], "clause-added");
+    let result = request(&mut stream, &mut responses, r#"{"id": 7, "type": "solve", "assumptions": ["-1"]}"#);
+    assert_eq!(result["verdict"], "UNSAT");
+}
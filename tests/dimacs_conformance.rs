@@ -0,0 +1,111 @@
+// Conformance tests of the DIMACS reader and writer on the dark corners of the format.
+
+use std::collections::BTreeSet;
+
+use sat_solver::{expression::normal::CNF, parser::{parse_dimacs_str, parse_dimacs_str_lenient, DimacsParseError}, solver::{dpll::solve_dpll, instance::{SATInstance, SolverResult}}};
+
+const COMMENTS_BETWEEN_CLAUSES: &str = "c leading comment\np cnf 3 3\n1 -2 0\nc between clauses\n2 3 0\nc another one\n-1 -3 0\n";
+const MULTILINE_CLAUSES: &str = "p cnf 4 3\n1 2\n3 0 -4\n-1\n0 4 -2 0\n";
+const EXTRA_WHITESPACE: &str = "  c indented comment\n\np  cnf   3   2 \n\t1   -3\t0   \n  2 3 0\t\n\n";
+const SATLIB_TRAILER: &str = "c SATLIB style\np cnf 3 2\n 1 -2 3 0\n-1 2 0\n%\n0\n\n";
+const UNDERCOUNTED_HEADER: &str = "p cnf 2 1\n1 2 0\n-3 1 0\n";
+const OVERCOUNTED_HEADER: &str = "p cnf 5 4\n1 -2 0\n2 0\n";
+
+// the clauses as sets of signed DIMACS literals, independent of clause and literal order
+fn clause_database(instance: &SATInstance) -> BTreeSet<BTreeSet<i64>> {
+    CNF::from(instance.expression.clone()).clauses.iter()
+        .map(|clause| clause.literals.iter().map(|literal| {
+            let number = i64::from(literal.var_id) + 1;
+            if literal.value { number } else { -number }
+        }).collect())
+        .collect()
+}
+
+fn database(clauses: &[&[i64]]) -> BTreeSet<BTreeSet<i64>> {
+    clauses.iter().map(|clause| clause.iter().copied().collect()).collect()
+}
+
+fn write_dimacs(instance: &SATInstance) -> String {
+    let mut output = Vec::new();
+    CNF::from(instance.expression.clone()).to_dimacs(&mut output).unwrap();
+
+    String::from_utf8(output).unwrap()
+}
+
+fn assert_round_trip(instance: &SATInstance) {
+    let written = write_dimacs(instance);
+    let reparsed = parse_dimacs_str(&written).unwrap_or_else(|err| panic!("Couldn't parse written DIMACS: {}\n{}", err, written));
+
+    assert_eq!(clause_database(&reparsed), clause_database(instance));
+}
+
+// a model of the instance parsed from `text` has to satisfy an independently parsed copy
+fn assert_model_verifies(text: &str) {
+    let instance = parse_dimacs_str_lenient(text).unwrap();
+    let SolverResult::Sat(Some(model)) = solve_dpll(instance, Default::default()) else {
+        panic!("Expected a model");
+    };
+
+    let copy = parse_dimacs_str_lenient(text).unwrap();
+    assert!(model.satisfies_expression(&copy.expression));
+}
+
+#[test]
+fn test_comments_between_clauses() {
+    let instance = parse_dimacs_str(COMMENTS_BETWEEN_CLAUSES).unwrap();
+
+    assert_eq!(clause_database(&instance), database(&[&[1, -2], &[2, 3], &[-1, -3]]));
+    assert_round_trip(&instance);
+    assert_model_verifies(COMMENTS_BETWEEN_CLAUSES);
+}
+
+#[test]
+fn test_multiline_clauses() {
+    let instance = parse_dimacs_str(MULTILINE_CLAUSES).unwrap();
+
+    assert_eq!(clause_database(&instance), database(&[&[1, 2, 3], &[-4, -1], &[4, -2]]));
+    assert_round_trip(&instance);
+    assert_model_verifies(MULTILINE_CLAUSES);
+}
+
+#[test]
+fn test_extra_whitespace() {
+    let instance = parse_dimacs_str(EXTRA_WHITESPACE).unwrap();
+
+    assert_eq!(instance.var_to_str.len(), 3);
+    assert_eq!(clause_database(&instance), database(&[&[1, -3], &[2, 3]]));
+    assert_round_trip(&instance);
+    assert_model_verifies(EXTRA_WHITESPACE);
+}
+
+#[test]
+fn test_satlib_trailer() {
+    let instance = parse_dimacs_str(SATLIB_TRAILER).unwrap();
+
+    assert_eq!(clause_database(&instance), database(&[&[1, -2, 3], &[-1, 2]]));
+    assert_round_trip(&instance);
+    assert_model_verifies(SATLIB_TRAILER);
+}
+
+#[test]
+fn test_undercounted_header() {
+    assert_eq!(parse_dimacs_str(UNDERCOUNTED_HEADER).unwrap_err(), DimacsParseError::VariableOutOfRange { line: 3, literal: -3, num_vars: 2 });
+
+    let instance = parse_dimacs_str_lenient(UNDERCOUNTED_HEADER).unwrap();
+    assert_eq!(instance.var_to_str.len(), 3);
+    assert_eq!(instance.str_to_var["3"], 2);
+    assert_eq!(clause_database(&instance), database(&[&[1, 2], &[-3, 1]]));
+    assert_round_trip(&instance);
+    assert_model_verifies(UNDERCOUNTED_HEADER);
+}
+
+#[test]
+fn test_overcounted_header() {
+    assert_eq!(parse_dimacs_str(OVERCOUNTED_HEADER).unwrap_err(), DimacsParseError::ClauseCountMismatch { declared: 4, found: 2 });
+
+    let instance = parse_dimacs_str_lenient(OVERCOUNTED_HEADER).unwrap();
+    assert_eq!(instance.var_to_str.len(), 5);
+    assert_eq!(clause_database(&instance), database(&[&[1, -2], &[2]]));
+    assert_round_trip(&instance);
+    assert_model_verifies(OVERCOUNTED_HEADER);
+}
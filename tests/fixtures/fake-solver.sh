@@ -0,0 +1,31 @@
+#!/bin/sh
+# Stand-in for an external solver in tests. Claims every formula is satisfiable by setting all
+# variables to true, unless the first of two arguments selects another behaviour.
+
+if [ $# -eq 2 ]; then
+    mode=$1
+    shift
+else
+    mode=sat
+fi
+
+case $mode in
+    sat)
+        echo "c fake solver"
+        echo "s SATISFIABLE"
+        awk '/^p cnf/ { printf "v"; for (i = 1; i <= $3; i++) printf " %d", i; print " 0" }' "$1"
+        exit 10
+        ;;
+    unsat)
+        echo "s UNSATISFIABLE"
+        exit 20
+        ;;
+    sleep)
+        exec sleep 10
+        ;;
+    garbage)
+        echo "s MAYBE"
+        ;;
+    silent)
+        ;;
+esac
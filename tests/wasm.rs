@@ -0,0 +1,59 @@
+// Tests of the WebAssembly bindings, run with `wasm-pack test --headless --firefox -- --features wasm`.
+
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use std::collections::BTreeMap;
+
+use sat_solver::wasm::{parse_formula, solve, solve_dimacs, to_cnf_string};
+use serde::Deserialize;
+use wasm_bindgen_test::{wasm_bindgen_test, wasm_bindgen_test_configure};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[derive(Debug, Deserialize)]
+struct Solution {
+    status: String,
+    model: Option<BTreeMap<String, bool>>,
+}
+
+#[wasm_bindgen_test]
+fn test_parse_solve_model() {
+    let formula = parse_formula("(a | -b) & (b | c) & -c").unwrap();
+    let solution: Solution = serde_wasm_bindgen::from_value(solve(&formula)).unwrap();
+
+    assert_eq!(solution.status, "SAT");
+    assert_eq!(solution.model, Some(BTreeMap::from([("a".to_string(), true), ("b".to_string(), true), ("c".to_string(), false)])));
+
+    let formula = parse_formula("a & -a").unwrap();
+    let solution: Solution = serde_wasm_bindgen::from_value(solve(&formula)).unwrap();
+    assert_eq!(solution.status, "UNSAT");
+    assert_eq!(solution.model, None);
+
+    assert!(parse_formula("a & ").is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_solve_dimacs() {
+    let solution: Solution = serde_wasm_bindgen::from_value(solve_dimacs("p cnf 2 2\n1 2 0\n-1 0\n").unwrap()).unwrap();
+
+    assert_eq!(solution.status, "SAT");
+    assert_eq!(solution.model, Some(BTreeMap::from([("1".to_string(), false), ("2".to_string(), true)])));
+    assert!(solve_dimacs("1 2 0\n").is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_to_cnf_string() {
+    let cnf = to_cnf_string(&parse_formula("a | b & c").unwrap());
+
+    // the order of clauses and literals isn't fixed
+    let mut clauses = cnf.split(" & ")
+        .map(|clause| {
+            let mut literals = clause.trim_matches(['(', ')']).split(" | ").collect::<Vec<_>>();
+            literals.sort();
+            literals
+        })
+        .collect::<Vec<_>>();
+    clauses.sort();
+
+    assert_eq!(clauses, [["a", "b"], ["a", "c"]]);
+}